@@ -1,18 +1,57 @@
-use std::{fmt::Write, fs, path::PathBuf, process, sync::Arc};
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+};
 
 use anyhow::anyhow;
+use clap::ValueEnum;
 use rustc_hash::FxHashMap;
-use starpls_bazel::client::{BazelCLI, BazelClient};
+use serde::Serialize;
+use starpls_bazel::{
+    client::{BazelCLI, BazelClient},
+    load_builtins,
+};
 use starpls_common::{FileInfo, Severity};
-use starpls_ide::{Analysis, Change};
+use starpls_ide::{Analysis, Change, InferenceOptions};
 
 use crate::{
     document::{self, DefaultFileLoader, PathInterner},
     server::{load_bazel_build_language, load_bazel_builtins},
+    ServerArgs,
 };
 
-pub(crate) fn run_check(paths: Vec<String>, output_base: Option<String>) -> anyhow::Result<()> {
-    let bazel_client = Arc::new(BazelCLI::default());
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// One `path:line:col - severity: message` line per diagnostic.
+    Text,
+    /// A JSON array of diagnostics.
+    Json,
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    path: &'a str,
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+    severity: &'static str,
+    message: String,
+}
+
+pub(crate) fn run_check(
+    paths: Vec<String>,
+    output_base: Option<String>,
+    format: OutputFormat,
+    args: ServerArgs,
+) -> anyhow::Result<()> {
+    let bazel_client = Arc::new(match args.bazel_path {
+        Some(ref bazel_path) => BazelCLI::new(bazel_path),
+        None => BazelCLI::default(),
+    });
     let info = bazel_client.info()?;
     let external_output_base = output_base
         .map(PathBuf::from)
@@ -35,7 +74,10 @@ pub(crate) fn run_check(paths: Vec<String>, output_base: Option<String>) -> anyh
         };
 
     let (fetch_repo_sender, _) = crossbeam_channel::unbounded();
-    let builtins = load_bazel_builtins()?;
+    let builtins = match args.builtins_path {
+        Some(ref path) => load_builtins(path)?,
+        None => load_bazel_builtins()?,
+    };
     let rules = load_bazel_build_language(&*bazel_client)?;
     let interner = Arc::new(PathInterner::default());
     let loader = DefaultFileLoader::new(
@@ -47,12 +89,22 @@ pub(crate) fn run_check(paths: Vec<String>, output_base: Option<String>) -> anyh
         fetch_repo_sender,
         bzlmod_enabled,
     );
-    let mut analysis = Analysis::new(Arc::new(loader), Default::default());
+    let mut analysis = Analysis::new(
+        Arc::new(loader),
+        InferenceOptions {
+            allow_identity_operators: args.allow_identity_operators,
+            disallow_dict_union: args.disallow_dict_union,
+            infer_ctx_attributes: args.infer_ctx_attributes,
+            use_code_flow_analysis: args.use_code_flow_analysis,
+            max_inference_depth: args.max_inference_depth,
+        },
+    );
     let mut change = Change::default();
     let mut file_ids = Vec::new();
     let mut original_paths = FxHashMap::default();
     analysis.set_builtin_defs(builtins, rules);
 
+    let paths = expand_paths(&info.workspace, paths)?;
     for path in &paths {
         let err = || anyhow!("Could not resolve the path {:?} as a Starlark file.", path);
         let resolved = PathBuf::from(path).canonicalize().map_err(|_| err())?;
@@ -82,32 +134,53 @@ pub(crate) fn run_check(paths: Vec<String>, output_base: Option<String>) -> anyh
 
     let snap = analysis.snapshot();
     let mut rendered_diagnostics = String::new();
+    let mut json_diagnostics = Vec::new();
     let mut has_error = false;
 
-    for file_id in file_ids.into_iter() {
+    for (file_id, diagnostics) in snap.diagnostics_for_files(&file_ids)? {
         let line_index = snap.line_index(file_id).unwrap().unwrap();
+        let path = original_paths.get(&file_id).unwrap();
 
-        for diagnostic in snap.diagnostics(file_id)? {
+        for diagnostic in diagnostics {
             let start = line_index.line_col(diagnostic.range.range.start());
-            writeln!(
-                &mut rendered_diagnostics,
-                "{}:{}:{} - {}: {}",
-                original_paths.get(&file_id).unwrap(),
-                start.line + 1,
-                start.col + 1,
-                match diagnostic.severity {
-                    Severity::Warning => "warn",
-                    Severity::Error => {
-                        has_error = true;
-                        "error"
-                    }
-                },
-                diagnostic.message,
-            )?;
+            let severity = match diagnostic.severity {
+                Severity::Hint => "hint",
+                Severity::Warning => "warn",
+                Severity::Error => {
+                    has_error = true;
+                    "error"
+                }
+            };
+            match format {
+                OutputFormat::Text => writeln!(
+                    &mut rendered_diagnostics,
+                    "{}:{}:{} - {}: {}",
+                    path,
+                    start.line + 1,
+                    start.col + 1,
+                    severity,
+                    diagnostic.message,
+                )?,
+                OutputFormat::Json => {
+                    let end = line_index.line_col(diagnostic.range.range.end());
+                    json_diagnostics.push(JsonDiagnostic {
+                        path,
+                        line: start.line + 1,
+                        column: start.col + 1,
+                        end_line: end.line + 1,
+                        end_column: end.col + 1,
+                        severity,
+                        message: diagnostic.message,
+                    });
+                }
+            }
         }
     }
 
-    print!("{}", rendered_diagnostics);
+    match format {
+        OutputFormat::Text => print!("{}", rendered_diagnostics),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&json_diagnostics)?),
+    }
 
     if has_error {
         process::exit(1);
@@ -115,3 +188,111 @@ pub(crate) fn run_check(paths: Vec<String>, output_base: Option<String>) -> anyh
 
     Ok(())
 }
+
+/// Expands directories and glob patterns into the Starlark files that they contain. Patterns
+/// support `*` and `?` within a path component, and `**` for any number of directories. Other
+/// paths are checked as given.
+fn expand_paths(workspace: &Path, paths: Vec<String>) -> anyhow::Result<Vec<String>> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let components = path.split('/').collect::<Vec<_>>();
+        let (base, pattern) = match components
+            .iter()
+            .position(|component| component.contains(['*', '?']))
+        {
+            Some(index) => {
+                let base = match components[..index].join("/") {
+                    base if base.is_empty() && path.starts_with('/') => "/".to_string(),
+                    base if base.is_empty() => ".".to_string(),
+                    base => base,
+                };
+                (base, Some(&components[index..]))
+            }
+            None if Path::new(&path).is_dir() => (path.clone(), None),
+            None => {
+                expanded.push(path);
+                continue;
+            }
+        };
+
+        let mut files = Vec::new();
+        walk_files(Path::new(&base), &mut files)?;
+        let mut matches = files
+            .into_iter()
+            .filter(|file| is_starlark_file(workspace, file))
+            .filter(|file| {
+                pattern.map_or(true, |pattern| {
+                    let relative = file.strip_prefix(&base).unwrap_or(file);
+                    let relative = relative
+                        .iter()
+                        .filter_map(|component| component.to_str())
+                        .collect::<Vec<_>>();
+                    match_components(pattern, &relative)
+                })
+            })
+            .map(|file| file.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            return Err(anyhow!("No Starlark files matched {:?}.", path));
+        }
+        matches.sort();
+        expanded.extend(matches);
+    }
+    Ok(expanded)
+}
+
+/// Collects the files under the given directory. Hidden directories and symlinks, such as the
+/// `bazel-*` convenience symlinks, are skipped.
+fn walk_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                walk_files(&path, files)?;
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_starlark_file(workspace: &Path, path: &Path) -> bool {
+    matches!(
+        document::dialect_and_api_context_for_workspace_path(workspace, path),
+        Some((_, Some(_)))
+    ) || matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("star" | "sky")
+    )
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|index| match_components(rest, &path[index..])),
+        Some((component, rest)) => path.split_first().map_or(false, |(name, path)| {
+            match_component(
+                &component.chars().collect::<Vec<_>>(),
+                &name.chars().collect::<Vec<_>>(),
+            ) && match_components(rest, path)
+        }),
+    }
+}
+
+fn match_component(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some(('*', rest)), _) => {
+            match_component(rest, name)
+                || name
+                    .split_first()
+                    .map_or(false, |(_, name)| match_component(pattern, name))
+        }
+        (Some(('?', rest)), Some((_, name))) => match_component(rest, name),
+        (Some((c, rest)), Some((n, name))) => c == n && match_component(rest, name),
+        _ => false,
+    }
+}
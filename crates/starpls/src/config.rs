@@ -2,6 +2,8 @@ use lsp_types::ClientCapabilities;
 
 use crate::ServerArgs;
 
+const DEFAULT_MAX_REQUEST_RETRIES: usize = 10;
+
 #[derive(Default)]
 pub(crate) struct ServerConfig {
     pub(crate) args: ServerArgs,
@@ -15,10 +17,29 @@ macro_rules! try_or_default {
 }
 
 impl ServerConfig {
+    pub(crate) fn max_request_retries(&self) -> usize {
+        self.args
+            .max_request_retries
+            .unwrap_or(DEFAULT_MAX_REQUEST_RETRIES)
+    }
+
     pub(crate) fn has_text_document_definition_link_support(&self) -> bool {
         try_or_default!(self.caps.text_document.as_ref()?.definition?.link_support)
     }
 
+    pub(crate) fn has_snippet_support(&self) -> bool {
+        try_or_default!(
+            self.caps
+                .text_document
+                .as_ref()?
+                .completion
+                .as_ref()?
+                .completion_item
+                .as_ref()?
+                .snippet_support
+        )
+    }
+
     pub(crate) fn has_insert_replace_support(&self) -> bool {
         try_or_default!(
             self.caps
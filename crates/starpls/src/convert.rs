@@ -3,7 +3,11 @@ use std::path::PathBuf;
 use anyhow::anyhow;
 use line_index::{LineIndex, WideEncoding, WideLineCol};
 use starpls_common::{Diagnostic, FileId, Severity};
-use starpls_ide::{DocumentSymbol, SymbolKind, SymbolTag};
+use starpls_ide::{
+    CallHierarchyItem, CodeAction, CodeActionKind, DocumentSymbol, FoldKind, FoldingRange,
+    InlayHint, InlayHintKind, SemanticToken, SemanticTokenKind, SemanticTokenModifier, SymbolKind,
+    SymbolTag, WorkspaceSymbol,
+};
 use starpls_syntax::{TextRange, TextSize};
 
 use crate::server::ServerSnapshot;
@@ -34,17 +38,21 @@ pub(crate) fn lsp_range_from_text_range(
     text_range: TextRange,
     line_index: &LineIndex,
 ) -> Option<lsp_types::Range> {
-    let start = line_index.to_wide(WideEncoding::Utf16, line_index.line_col(text_range.start()))?;
-    let end = line_index.to_wide(WideEncoding::Utf16, line_index.line_col(text_range.end()))?;
     Some(lsp_types::Range {
-        start: lsp_types::Position {
-            line: start.line,
-            character: start.col,
-        },
-        end: lsp_types::Position {
-            line: end.line,
-            character: end.col,
-        },
+        start: lsp_position_from_text_size(text_range.start(), line_index)?,
+        end: lsp_position_from_text_size(text_range.end(), line_index)?,
+    })
+}
+
+/// Converts a byte offset into an LSP position, whose column is measured in UTF-16 code units.
+pub(crate) fn lsp_position_from_text_size(
+    offset: TextSize,
+    line_index: &LineIndex,
+) -> Option<lsp_types::Position> {
+    let line_col = line_index.to_wide(WideEncoding::Utf16, line_index.line_col(offset))?;
+    Some(lsp_types::Position {
+        line: line_col.line,
+        character: line_col.col,
     })
 }
 
@@ -76,6 +84,38 @@ fn lsp_severity_from_native(severity: Severity) -> lsp_types::DiagnosticSeverity
     match severity {
         Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
         Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+        Severity::Hint => lsp_types::DiagnosticSeverity::HINT,
+    }
+}
+
+fn lsp_symbol_kind_from_native(kind: SymbolKind) -> lsp_types::SymbolKind {
+    match kind {
+        SymbolKind::File => lsp_types::SymbolKind::FILE,
+        SymbolKind::Module => lsp_types::SymbolKind::MODULE,
+        SymbolKind::Namespace => lsp_types::SymbolKind::NAMESPACE,
+        SymbolKind::Package => lsp_types::SymbolKind::PACKAGE,
+        SymbolKind::Class => lsp_types::SymbolKind::CLASS,
+        SymbolKind::Method => lsp_types::SymbolKind::METHOD,
+        SymbolKind::Property => lsp_types::SymbolKind::PROPERTY,
+        SymbolKind::Field => lsp_types::SymbolKind::FIELD,
+        SymbolKind::Constructor => lsp_types::SymbolKind::CONSTRUCTOR,
+        SymbolKind::Enum => lsp_types::SymbolKind::ENUM,
+        SymbolKind::Interface => lsp_types::SymbolKind::INTERFACE,
+        SymbolKind::Function => lsp_types::SymbolKind::FUNCTION,
+        SymbolKind::Variable => lsp_types::SymbolKind::VARIABLE,
+        SymbolKind::Constant => lsp_types::SymbolKind::CONSTANT,
+        SymbolKind::String => lsp_types::SymbolKind::STRING,
+        SymbolKind::Number => lsp_types::SymbolKind::NUMBER,
+        SymbolKind::Boolean => lsp_types::SymbolKind::BOOLEAN,
+        SymbolKind::Array => lsp_types::SymbolKind::ARRAY,
+        SymbolKind::Object => lsp_types::SymbolKind::OBJECT,
+        SymbolKind::Key => lsp_types::SymbolKind::KEY,
+        SymbolKind::Null => lsp_types::SymbolKind::NULL,
+        SymbolKind::EnumMember => lsp_types::SymbolKind::ENUM_MEMBER,
+        SymbolKind::Struct => lsp_types::SymbolKind::STRUCT,
+        SymbolKind::Event => lsp_types::SymbolKind::EVENT,
+        SymbolKind::Operator => lsp_types::SymbolKind::OPERATOR,
+        SymbolKind::TypeParameter => lsp_types::SymbolKind::TYPE_PARAMETER,
     }
 }
 
@@ -95,34 +135,7 @@ pub(crate) fn lsp_document_symbol_from_native(
     Some(lsp_types::DocumentSymbol {
         name,
         detail,
-        kind: match kind {
-            SymbolKind::File => lsp_types::SymbolKind::FILE,
-            SymbolKind::Module => lsp_types::SymbolKind::MODULE,
-            SymbolKind::Namespace => lsp_types::SymbolKind::NAMESPACE,
-            SymbolKind::Package => lsp_types::SymbolKind::PACKAGE,
-            SymbolKind::Class => lsp_types::SymbolKind::CLASS,
-            SymbolKind::Method => lsp_types::SymbolKind::METHOD,
-            SymbolKind::Property => lsp_types::SymbolKind::PROPERTY,
-            SymbolKind::Field => lsp_types::SymbolKind::FIELD,
-            SymbolKind::Constructor => lsp_types::SymbolKind::CONSTRUCTOR,
-            SymbolKind::Enum => lsp_types::SymbolKind::ENUM,
-            SymbolKind::Interface => lsp_types::SymbolKind::INTERFACE,
-            SymbolKind::Function => lsp_types::SymbolKind::FUNCTION,
-            SymbolKind::Variable => lsp_types::SymbolKind::VARIABLE,
-            SymbolKind::Constant => lsp_types::SymbolKind::CONSTANT,
-            SymbolKind::String => lsp_types::SymbolKind::STRING,
-            SymbolKind::Number => lsp_types::SymbolKind::NUMBER,
-            SymbolKind::Boolean => lsp_types::SymbolKind::BOOLEAN,
-            SymbolKind::Array => lsp_types::SymbolKind::ARRAY,
-            SymbolKind::Object => lsp_types::SymbolKind::OBJECT,
-            SymbolKind::Key => lsp_types::SymbolKind::KEY,
-            SymbolKind::Null => lsp_types::SymbolKind::NULL,
-            SymbolKind::EnumMember => lsp_types::SymbolKind::ENUM_MEMBER,
-            SymbolKind::Struct => lsp_types::SymbolKind::STRUCT,
-            SymbolKind::Event => lsp_types::SymbolKind::EVENT,
-            SymbolKind::Operator => lsp_types::SymbolKind::OPERATOR,
-            SymbolKind::TypeParameter => lsp_types::SymbolKind::TYPE_PARAMETER,
-        },
+        kind: lsp_symbol_kind_from_native(kind),
         tags: tags.map(|tags| {
             tags.into_iter()
                 .map(|tag| match tag {
@@ -141,3 +154,234 @@ pub(crate) fn lsp_document_symbol_from_native(
         deprecated: None,
     })
 }
+
+#[allow(deprecated)]
+pub(crate) fn lsp_symbol_information_from_native(
+    WorkspaceSymbol {
+        name, kind, range, ..
+    }: WorkspaceSymbol,
+    uri: lsp_types::Url,
+    line_index: &LineIndex,
+) -> Option<lsp_types::SymbolInformation> {
+    Some(lsp_types::SymbolInformation {
+        name,
+        kind: lsp_symbol_kind_from_native(kind),
+        tags: None,
+        deprecated: None,
+        location: lsp_types::Location {
+            uri,
+            range: lsp_range_from_text_range(range, line_index)?,
+        },
+        container_name: None,
+    })
+}
+
+pub(crate) fn lsp_call_hierarchy_item_from_native(
+    CallHierarchyItem {
+        name,
+        kind,
+        range,
+        selection_range,
+        ..
+    }: CallHierarchyItem,
+    uri: lsp_types::Url,
+    line_index: &LineIndex,
+) -> Option<lsp_types::CallHierarchyItem> {
+    Some(lsp_types::CallHierarchyItem {
+        name,
+        kind: lsp_symbol_kind_from_native(kind),
+        tags: None,
+        detail: None,
+        uri,
+        range: lsp_range_from_text_range(range, line_index)?,
+        selection_range: lsp_range_from_text_range(selection_range, line_index)?,
+        data: None,
+    })
+}
+
+pub(crate) fn lsp_code_action_from_native(
+    CodeAction { title, kind, edits }: CodeAction,
+    uri: lsp_types::Url,
+    line_index: &LineIndex,
+) -> Option<lsp_types::CodeAction> {
+    let edits = edits
+        .into_iter()
+        .map(|edit| {
+            Some(lsp_types::TextEdit {
+                range: lsp_range_from_text_range(edit.range, line_index)?,
+                new_text: edit.new_text,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(lsp_types::CodeAction {
+        title,
+        kind: Some(match kind {
+            CodeActionKind::QuickFix => lsp_types::CodeActionKind::QUICKFIX,
+            CodeActionKind::RefactorRewrite => lsp_types::CodeActionKind::REFACTOR_REWRITE,
+        }),
+        edit: Some(lsp_types::WorkspaceEdit {
+            changes: Some(std::iter::once((uri, edits)).collect()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+pub(crate) fn lsp_folding_range_from_native(
+    FoldingRange { range, kind }: FoldingRange,
+    line_index: &LineIndex,
+) -> Option<lsp_types::FoldingRange> {
+    let range = lsp_range_from_text_range(range, line_index)?;
+    Some(lsp_types::FoldingRange {
+        start_line: range.start.line,
+        start_character: None,
+        end_line: range.end.line,
+        end_character: None,
+        kind: Some(match kind {
+            FoldKind::Comment => lsp_types::FoldingRangeKind::Comment,
+            FoldKind::Region => lsp_types::FoldingRangeKind::Region,
+        }),
+        collapsed_text: None,
+    })
+}
+
+/// Converts a list of ranges, ordered from innermost to outermost, into a chain of
+/// `SelectionRange`s where each range links to the one enclosing it.
+pub(crate) fn lsp_selection_range_from_native(
+    ranges: Vec<TextRange>,
+    line_index: &LineIndex,
+) -> Option<lsp_types::SelectionRange> {
+    let mut selection_range = None;
+    for range in ranges.into_iter().rev() {
+        selection_range = Some(lsp_types::SelectionRange {
+            range: lsp_range_from_text_range(range, line_index)?,
+            parent: selection_range.map(Box::new),
+        });
+    }
+    selection_range
+}
+
+pub(crate) fn lsp_inlay_hint_from_native(
+    InlayHint {
+        position,
+        label,
+        kind,
+    }: InlayHint,
+    line_index: &LineIndex,
+) -> Option<lsp_types::InlayHint> {
+    Some(lsp_types::InlayHint {
+        position: lsp_position_from_text_size(position, line_index)?,
+        label: lsp_types::InlayHintLabel::String(label),
+        kind: Some(match kind {
+            InlayHintKind::Type => lsp_types::InlayHintKind::TYPE,
+        }),
+        text_edits: None,
+        tooltip: None,
+        padding_left: None,
+        padding_right: None,
+        data: None,
+    })
+}
+
+/// The token types reported in the semantic tokens legend. The order must match the indices
+/// produced by `lsp_semantic_tokens_from_native`.
+pub(crate) const SEMANTIC_TOKEN_TYPES: &[lsp_types::SemanticTokenType] = &[
+    lsp_types::SemanticTokenType::FUNCTION,
+    lsp_types::SemanticTokenType::PARAMETER,
+    lsp_types::SemanticTokenType::VARIABLE,
+];
+
+/// The token modifiers reported in the semantic tokens legend. Each modifier is encoded as the
+/// bit corresponding to its index.
+pub(crate) const SEMANTIC_TOKEN_MODIFIERS: &[lsp_types::SemanticTokenModifier] = &[
+    lsp_types::SemanticTokenModifier::DEFAULT_LIBRARY,
+    lsp_types::SemanticTokenModifier::READONLY,
+];
+
+pub(crate) fn lsp_semantic_tokens_from_native(
+    tokens: Vec<SemanticToken>,
+    line_index: &LineIndex,
+) -> lsp_types::SemanticTokens {
+    let mut data = Vec::with_capacity(tokens.len());
+    let mut prev_line = 0;
+    let mut prev_start = 0;
+    for token in tokens {
+        let range = match lsp_range_from_text_range(token.range, line_index) {
+            Some(range) => range,
+            None => continue,
+        };
+
+        // Positions are encoded relative to the previous token, with the start character only
+        // being relative if both tokens are on the same line.
+        let delta_line = range.start.line - prev_line;
+        let delta_start = if delta_line == 0 {
+            range.start.character - prev_start
+        } else {
+            range.start.character
+        };
+        prev_line = range.start.line;
+        prev_start = range.start.character;
+
+        let token_type = match token.kind {
+            SemanticTokenKind::Function => 0,
+            SemanticTokenKind::Parameter => 1,
+            SemanticTokenKind::Variable => 2,
+        };
+        let token_modifiers_bitset = token.modifiers.iter().fold(0, |acc, modifier| {
+            acc | match modifier {
+                SemanticTokenModifier::DefaultLibrary => 1 << 0,
+                SemanticTokenModifier::Readonly => 1 << 1,
+            }
+        });
+
+        data.push(lsp_types::SemanticToken {
+            delta_line,
+            delta_start,
+            length: range.end.character.saturating_sub(range.start.character),
+            token_type,
+            token_modifiers_bitset,
+        });
+    }
+
+    lsp_types::SemanticTokens {
+        result_id: None,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use line_index::LineIndex;
+    use starpls_syntax::{TextRange, TextSize};
+
+    use super::lsp_range_from_text_range;
+
+    fn check(text: &str, range: (u32, u32), expected: ((u32, u32), (u32, u32))) {
+        let line_index = LineIndex::new(text);
+        let range = lsp_range_from_text_range(
+            TextRange::new(TextSize::new(range.0), TextSize::new(range.1)),
+            &line_index,
+        )
+        .unwrap();
+        assert_eq!(
+            (
+                (range.start.line, range.start.character),
+                (range.end.line, range.end.character)
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_multi_byte_characters() {
+        let text = "é = \"😀\" + y\nz";
+
+        // `é` is two bytes in UTF-8, but a single UTF-16 code unit.
+        check(text, (0, 2), ((0, 0), (0, 1)));
+
+        // `😀` is four bytes in UTF-8, and a surrogate pair in UTF-16.
+        check(text, (6, 10), ((0, 5), (0, 7)));
+        check(text, (14, 15), ((0, 11), (0, 12)));
+        check(text, (16, 17), ((1, 0), (1, 1)));
+    }
+}
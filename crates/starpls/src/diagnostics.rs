@@ -10,22 +10,37 @@ pub(crate) struct DiagnosticsManager {
 }
 
 impl DiagnosticsManager {
-    pub(crate) fn set_diagnostics(&mut self, file_id: FileId, mut diagnostics: Vec<Diagnostic>) {
-        self.diagnostics
-            .entry(file_id)
-            .and_modify(|current_diagnostics| {
-                if current_diagnostics.len() != diagnostics.len()
+    pub(crate) fn set_diagnostics(&mut self, file_id: FileId, diagnostics: Vec<Diagnostic>) {
+        // Only record a change if the diagnostics actually differ from the ones that were last
+        // published. An empty set of diagnostics is still recorded for a file that previously
+        // had diagnostics, so that the client clears them.
+        let has_changed = match self.diagnostics.get(&file_id) {
+            Some(current_diagnostics) => {
+                current_diagnostics.len() != diagnostics.len()
                     || current_diagnostics.iter().zip(diagnostics.iter()).any(
                         |(current_diagnostic, diagnostic)| {
                             !is_diagnostic_equal(current_diagnostic, diagnostic)
                         },
                     )
-                {
-                    *current_diagnostics = mem::take(&mut diagnostics);
-                }
-            })
-            .or_insert(diagnostics);
-        self.files_with_changed_diagnostics.push(file_id);
+            }
+            None => true,
+        };
+
+        if has_changed {
+            self.diagnostics.insert(file_id, diagnostics);
+
+            // A file whose diagnostics change several times before they are published is only
+            // published once.
+            if !self.files_with_changed_diagnostics.contains(&file_id) {
+                self.files_with_changed_diagnostics.push(file_id);
+            }
+        }
+    }
+
+    /// Forgets the diagnostics for the given file, e.g. because it was closed. This ensures that
+    /// its diagnostics are published again if it is reopened.
+    pub(crate) fn clear_diagnostics(&mut self, file_id: FileId) {
+        self.diagnostics.remove(&file_id);
     }
 
     pub(crate) fn take_changes(&mut self) -> Vec<FileId> {
@@ -9,13 +9,15 @@ use crate::{
 
 pub(crate) struct RequestDispatcher<'a> {
     req: Option<lsp_server::Request>,
+    retries: usize,
     server: &'a Server,
 }
 
 impl<'a> RequestDispatcher<'a> {
-    pub(crate) fn new(req: lsp_server::Request, server: &'a Server) -> Self {
+    pub(crate) fn new(req: lsp_server::Request, retries: usize, server: &'a Server) -> Self {
         Self {
             req: Some(req),
+            retries,
             server,
         }
     }
@@ -34,13 +36,34 @@ impl<'a> RequestDispatcher<'a> {
         };
 
         let snapshot = self.server.snapshot();
+        let retries = self.retries;
         self.server.task_pool_handle.spawn(move || {
             let res = panic::catch_unwind(|| f(&snapshot, params));
             let response = match res {
                 Ok(res) => match res {
                     Ok(res) => lsp_server::Response::new_ok(req.id, res),
                     Err(err) => match err.downcast::<Cancelled>() {
-                        Ok(_) => return Task::Retry(req),
+                        Ok(cancelled) => {
+                            // Give up after too many retries, since constant edits could
+                            // otherwise cause the request to be retried indefinitely.
+                            if retries < snapshot.config.max_request_retries() {
+                                return Task::Retry(req, retries + 1);
+                            }
+
+                            let reason = match cancelled {
+                                Cancelled::Salsa(_) => "salsa",
+                                Cancelled::Typecheck(_) => "typecheck",
+                            };
+                            eprintln!(
+                                "server: request {} ({}) cancelled by {} after {} retries",
+                                req.id, req.method, reason, retries
+                            );
+                            lsp_server::Response::new_err(
+                                req.id,
+                                lsp_server::ErrorCode::ContentModified as i32,
+                                "content modified".to_string(),
+                            )
+                        }
                         Err(err) => lsp_server::Response::new_err(
                             req.id,
                             lsp_server::ErrorCode::RequestFailed as i32,
@@ -47,8 +47,9 @@ pub(crate) enum Task {
     DiagnosticsReady(Vec<(FileId, Vec<lsp_types::Diagnostic>)>),
     /// A request has been evaluated and its response is ready.
     ResponseReady(lsp_server::Response),
-    /// Retry a previously failed request (e.g. due to Salsa cancellation).
-    Retry(lsp_server::Request),
+    /// Retry a previously failed request (e.g. due to Salsa cancellation), along with the
+    /// number of times it has been retried so far.
+    Retry(lsp_server::Request, usize),
     /// Events from fetching external repositories.
     FetchExternalRepos(FetchExternalReposProgress),
     /// A request to fetch an external repository.
@@ -150,7 +151,12 @@ impl Server {
                 .map(|document| document.source)
             {
                 Some(DocumentSource::Editor(version)) => version,
-                _ => continue,
+                _ => {
+                    // Diagnostics computed for a file that has been closed in the meantime are
+                    // dropped, so that they are published again if the file is reopened.
+                    self.diagnostics_manager.clear_diagnostics(file_id);
+                    continue;
+                }
             };
             let diagnostics = self
                 .diagnostics_manager
@@ -194,18 +200,34 @@ impl Server {
 
     fn register_and_handle_request(&mut self, req: lsp_server::Request) {
         self.req_queue.incoming.register(req.id.clone(), ());
-        self.handle_request(req);
+        self.handle_request(req, 0);
     }
 
-    fn handle_request(&mut self, req: lsp_server::Request) {
-        RequestDispatcher::new(req, self)
+    fn handle_request(&mut self, req: lsp_server::Request, retries: usize) {
+        RequestDispatcher::new(req, retries, self)
             .on::<extensions::ShowSyntaxTree>(requests::show_syntax_tree)
             .on::<extensions::ShowHir>(requests::show_hir)
+            .on::<lsp_types::request::CallHierarchyIncomingCalls>(requests::incoming_calls)
+            .on::<lsp_types::request::CallHierarchyOutgoingCalls>(requests::outgoing_calls)
+            .on::<lsp_types::request::CallHierarchyPrepare>(requests::prepare_call_hierarchy)
+            .on::<lsp_types::request::CodeActionRequest>(requests::code_action)
+            .on::<lsp_types::request::CodeLensRequest>(requests::code_lens)
             .on::<lsp_types::request::Completion>(requests::completion)
             .on::<lsp_types::request::DocumentSymbolRequest>(requests::document_symbols)
+            .on::<lsp_types::request::FoldingRangeRequest>(requests::folding_range)
+            .on::<lsp_types::request::Formatting>(requests::formatting)
             .on::<lsp_types::request::GotoDefinition>(requests::goto_definition)
+            .on::<lsp_types::request::GotoTypeDefinition>(requests::goto_type_definition)
+            .on::<lsp_types::request::InlayHintRequest>(requests::inlay_hint)
+            .on::<lsp_types::request::RangeFormatting>(requests::range_formatting)
+            .on::<lsp_types::request::References>(requests::find_references)
             .on::<lsp_types::request::HoverRequest>(requests::hover)
+            .on::<lsp_types::request::PrepareRenameRequest>(requests::prepare_rename)
+            .on::<lsp_types::request::Rename>(requests::rename)
+            .on::<lsp_types::request::SelectionRangeRequest>(requests::selection_range)
+            .on::<lsp_types::request::SemanticTokensFullRequest>(requests::semantic_tokens_full)
             .on::<lsp_types::request::SignatureHelpRequest>(requests::signature_help)
+            .on::<lsp_types::request::WorkspaceSymbolRequest>(requests::workspace_symbol)
             .finish();
     }
 
@@ -233,7 +255,7 @@ impl Server {
             Task::ResponseReady(resp) => {
                 self.respond(resp);
             }
-            Task::Retry(req) => self.handle_request(req),
+            Task::Retry(req, retries) => self.handle_request(req, retries),
             Task::FetchExternalRepos(progress) => {
                 let token = "FetchExternalRepos".to_string();
                 let work_done = match progress {
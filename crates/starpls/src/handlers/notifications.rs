@@ -18,7 +18,23 @@ pub(crate) fn did_close_text_document(
     params: lsp_types::DidCloseTextDocumentParams,
 ) -> anyhow::Result<()> {
     let path = convert::path_buf_from_url(&params.text_document.uri)?;
-    server.document_manager.write().close(&path);
+    {
+        let mut document_manager = server.document_manager.write();
+        if let Some(file_id) = document_manager.lookup_by_path_buf(&path) {
+            server.diagnostics_manager.clear_diagnostics(file_id);
+        }
+        document_manager.close(&path);
+    }
+
+    // The client keeps showing the last published diagnostics for a closed file until they are
+    // replaced, so they are cleared explicitly.
+    server.send_notification::<lsp_types::notification::PublishDiagnostics>(
+        lsp_types::PublishDiagnosticsParams {
+            uri: params.text_document.uri,
+            diagnostics: Vec::new(),
+            version: None,
+        },
+    );
     Ok(())
 }
 
@@ -1,15 +1,16 @@
 use anyhow::Ok;
 use starpls_ide::{
     CompletionItemKind,
-    CompletionMode::{InsertText, TextEdit},
-    Edit, FilePosition,
+    CompletionMode::{InsertSnippet, InsertText, TextEdit},
+    Edit, FilePosition, FormattingOptions,
 };
+use starpls_syntax::TextRange;
 
 use crate::{
     convert::{self, path_buf_from_url},
     extensions::{ShowHirParams, ShowSyntaxTreeParams},
     server::ServerSnapshot,
-    utils::response_from_locations,
+    utils::{lsp_location_from_location_link, response_from_locations},
 };
 
 macro_rules! try_opt {
@@ -68,6 +69,206 @@ pub(crate) fn goto_definition(
     Ok(Some(resp))
 }
 
+pub(crate) fn goto_type_definition(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::request::GotoTypeDefinitionParams,
+) -> anyhow::Result<Option<lsp_types::request::GotoTypeDefinitionResponse>> {
+    let path = path_buf_from_url(&params.text_document_position_params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let pos = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.text_document_position_params.position,
+    )?);
+    let resp = response_from_locations(
+        snapshot,
+        file_id,
+        snapshot
+            .analysis_snapshot
+            .goto_type_definition(FilePosition { file_id, pos })?
+            .unwrap_or_default()
+            .into_iter(),
+    );
+    Ok(Some(resp))
+}
+
+pub(crate) fn find_references(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::ReferenceParams,
+) -> anyhow::Result<Option<Vec<lsp_types::Location>>> {
+    let path = path_buf_from_url(&params.text_document_position.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let pos = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.text_document_position.position,
+    )?);
+    let locations = try_opt!(snapshot.analysis_snapshot.find_references(
+        FilePosition { file_id, pos },
+        params.context.include_declaration,
+    )?);
+    Ok(Some(
+        locations
+            .into_iter()
+            .flat_map(|location| lsp_location_from_location_link(snapshot, location))
+            .collect(),
+    ))
+}
+
+pub(crate) fn prepare_rename(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::TextDocumentPositionParams,
+) -> anyhow::Result<Option<lsp_types::PrepareRenameResponse>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let pos = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.position,
+    )?);
+    let range = try_opt!(snapshot
+        .analysis_snapshot
+        .prepare_rename(FilePosition { file_id, pos })??);
+    Ok(convert::lsp_range_from_text_range(range, line_index)
+        .map(lsp_types::PrepareRenameResponse::Range))
+}
+
+pub(crate) fn rename(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::RenameParams,
+) -> anyhow::Result<Option<lsp_types::WorkspaceEdit>> {
+    let uri = params.text_document_position.text_document.uri;
+    let path = path_buf_from_url(&uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let pos = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.text_document_position.position,
+    )?);
+    let edits = try_opt!(snapshot
+        .analysis_snapshot
+        .rename(FilePosition { file_id, pos }, params.new_name)??);
+    let edits = edits
+        .into_iter()
+        .flat_map(|edit| {
+            Some(lsp_types::TextEdit {
+                range: convert::lsp_range_from_text_range(edit.range, line_index)?,
+                new_text: edit.new_text,
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(Some(lsp_types::WorkspaceEdit {
+        changes: Some(std::iter::once((uri, edits)).collect()),
+        ..Default::default()
+    }))
+}
+
+pub(crate) fn prepare_call_hierarchy(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::CallHierarchyPrepareParams,
+) -> anyhow::Result<Option<Vec<lsp_types::CallHierarchyItem>>> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let path = path_buf_from_url(&uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let pos = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.text_document_position_params.position,
+    )?);
+    Ok(snapshot
+        .analysis_snapshot
+        .prepare_call_hierarchy(FilePosition { file_id, pos })?
+        .map(|items| {
+            items
+                .into_iter()
+                .filter_map(|item| {
+                    convert::lsp_call_hierarchy_item_from_native(item, uri.clone(), line_index)
+                })
+                .collect()
+        }))
+}
+
+pub(crate) fn incoming_calls(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::CallHierarchyIncomingCallsParams,
+) -> anyhow::Result<Option<Vec<lsp_types::CallHierarchyIncomingCall>>> {
+    let uri = params.item.uri;
+    let path = path_buf_from_url(&uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let pos = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.item.selection_range.start,
+    )?);
+    Ok(snapshot
+        .analysis_snapshot
+        .incoming_calls(FilePosition { file_id, pos })?
+        .map(|calls| {
+            calls
+                .into_iter()
+                .filter_map(|call| {
+                    Some(lsp_types::CallHierarchyIncomingCall {
+                        from: convert::lsp_call_hierarchy_item_from_native(
+                            call.from,
+                            uri.clone(),
+                            line_index,
+                        )?,
+                        from_ranges: call
+                            .from_ranges
+                            .into_iter()
+                            .filter_map(|range| {
+                                convert::lsp_range_from_text_range(range, line_index)
+                            })
+                            .collect(),
+                    })
+                })
+                .collect()
+        }))
+}
+
+pub(crate) fn outgoing_calls(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::CallHierarchyOutgoingCallsParams,
+) -> anyhow::Result<Option<Vec<lsp_types::CallHierarchyOutgoingCall>>> {
+    let uri = params.item.uri;
+    let path = path_buf_from_url(&uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let pos = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.item.selection_range.start,
+    )?);
+    Ok(snapshot
+        .analysis_snapshot
+        .outgoing_calls(FilePosition { file_id, pos })?
+        .map(|calls| {
+            calls
+                .into_iter()
+                .filter_map(|call| {
+                    Some(lsp_types::CallHierarchyOutgoingCall {
+                        to: convert::lsp_call_hierarchy_item_from_native(
+                            call.to,
+                            uri.clone(),
+                            line_index,
+                        )?,
+                        from_ranges: call
+                            .from_ranges
+                            .into_iter()
+                            .filter_map(|range| {
+                                convert::lsp_range_from_text_range(range, line_index)
+                            })
+                            .collect(),
+                    })
+                })
+                .collect()
+        }))
+}
+
 pub(crate) fn completion(
     snapshot: &ServerSnapshot,
     params: lsp_types::CompletionParams,
@@ -92,9 +293,15 @@ pub(crate) fn completion(
             .into_iter()
             .flat_map(|item| {
                 let sort_text = Some(item.sort_text());
+                let mut insert_text_format = None;
                 let (insert_text, text_edit) = match item.mode {
                     Some(mode) => match mode {
                         InsertText(text) => (Some(text), None),
+                        InsertSnippet(text) if snapshot.config.has_snippet_support() => {
+                            insert_text_format = Some(lsp_types::InsertTextFormat::SNIPPET);
+                            (Some(text), None)
+                        }
+                        InsertSnippet(_) => (None, None),
                         TextEdit(edit) => (
                             None,
                             Some(match edit {
@@ -143,8 +350,10 @@ pub(crate) fn completion(
                         CompletionItemKind::Folder => lsp_types::CompletionItemKind::FOLDER,
                         CompletionItemKind::Constant => lsp_types::CompletionItemKind::CONSTANT,
                     }),
+                    detail: item.detail,
                     sort_text,
                     insert_text,
+                    insert_text_format,
                     text_edit,
                     filter_text: item.filter_text,
                     ..Default::default()
@@ -161,6 +370,7 @@ pub(crate) fn hover(
 ) -> anyhow::Result<Option<lsp_types::Hover>> {
     let path = path_buf_from_url(&params.text_document_position_params.text_document.uri)?;
     let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
     let pos = try_opt!(convert::text_size_from_lsp_position(
         snapshot,
         file_id,
@@ -174,7 +384,9 @@ pub(crate) fn hover(
                 kind: lsp_types::MarkupKind::Markdown,
                 value: hover.contents.value,
             }),
-            range: None,
+            range: hover
+                .range
+                .and_then(|range| convert::lsp_range_from_text_range(range, line_index)),
         }))
 }
 
@@ -235,6 +447,243 @@ pub(crate) fn document_symbols(
         }))
 }
 
+pub(crate) fn folding_range(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::FoldingRangeParams,
+) -> anyhow::Result<Option<Vec<lsp_types::FoldingRange>>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    Ok(snapshot
+        .analysis_snapshot
+        .folding_ranges(file_id)?
+        .map(|ranges| {
+            ranges
+                .into_iter()
+                .filter_map(|range| convert::lsp_folding_range_from_native(range, line_index))
+                .collect()
+        }))
+}
+
+pub(crate) fn formatting(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::DocumentFormattingParams,
+) -> anyhow::Result<Option<Vec<lsp_types::TextEdit>>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let options = FormattingOptions {
+        tab_size: params.options.tab_size,
+        insert_spaces: params.options.insert_spaces,
+    };
+    Ok(snapshot
+        .analysis_snapshot
+        .format(file_id, options)?
+        .map(|edits| {
+            edits
+                .into_iter()
+                .flat_map(|edit| {
+                    Some(lsp_types::TextEdit {
+                        range: convert::lsp_range_from_text_range(edit.range, line_index)?,
+                        new_text: edit.new_text,
+                    })
+                })
+                .collect()
+        }))
+}
+
+pub(crate) fn range_formatting(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::DocumentRangeFormattingParams,
+) -> anyhow::Result<Option<Vec<lsp_types::TextEdit>>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let start = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.range.start,
+    )?);
+    let end = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.range.end,
+    )?);
+    if start > end {
+        return Ok(None);
+    }
+
+    let options = FormattingOptions {
+        tab_size: params.options.tab_size,
+        insert_spaces: params.options.insert_spaces,
+    };
+    Ok(snapshot
+        .analysis_snapshot
+        .format_range(file_id, TextRange::new(start, end), options)?
+        .map(|edits| {
+            edits
+                .into_iter()
+                .flat_map(|edit| {
+                    Some(lsp_types::TextEdit {
+                        range: convert::lsp_range_from_text_range(edit.range, line_index)?,
+                        new_text: edit.new_text,
+                    })
+                })
+                .collect()
+        }))
+}
+
+pub(crate) fn inlay_hint(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::InlayHintParams,
+) -> anyhow::Result<Option<Vec<lsp_types::InlayHint>>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let start = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.range.start,
+    )?);
+    let end = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.range.end,
+    )?);
+    Ok(snapshot
+        .analysis_snapshot
+        .inlay_hints(file_id, Some(TextRange::new(start, end)))?
+        .map(|hints| {
+            hints
+                .into_iter()
+                .filter_map(|hint| convert::lsp_inlay_hint_from_native(hint, line_index))
+                .collect()
+        }))
+}
+
+pub(crate) fn code_lens(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::CodeLensParams,
+) -> anyhow::Result<Option<Vec<lsp_types::CodeLens>>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    Ok(snapshot
+        .analysis_snapshot
+        .code_lenses(file_id)?
+        .map(|lenses| {
+            lenses
+                .into_iter()
+                .filter_map(|lens| {
+                    Some(lsp_types::CodeLens {
+                        range: convert::lsp_range_from_text_range(lens.range, line_index)?,
+                        // The lens is informational only, so its command doesn't do anything.
+                        command: Some(lsp_types::Command {
+                            title: lens.title,
+                            command: String::new(),
+                            arguments: None,
+                        }),
+                        data: None,
+                    })
+                })
+                .collect()
+        }))
+}
+
+pub(crate) fn selection_range(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::SelectionRangeParams,
+) -> anyhow::Result<Option<Vec<lsp_types::SelectionRange>>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let mut positions = Vec::with_capacity(params.positions.len());
+    for pos in params.positions {
+        positions.push(try_opt!(convert::text_size_from_lsp_position(
+            snapshot, file_id, pos
+        )?));
+    }
+    Ok(snapshot
+        .analysis_snapshot
+        .selection_ranges(file_id, positions)?
+        .map(|ranges| {
+            ranges
+                .into_iter()
+                .filter_map(|ranges| convert::lsp_selection_range_from_native(ranges, line_index))
+                .collect()
+        }))
+}
+
+pub(crate) fn semantic_tokens_full(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::SemanticTokensParams,
+) -> anyhow::Result<Option<lsp_types::SemanticTokensResult>> {
+    let path = path_buf_from_url(&params.text_document.uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let tokens = try_opt!(snapshot.analysis_snapshot.semantic_tokens(file_id)?);
+    Ok(Some(lsp_types::SemanticTokensResult::Tokens(
+        convert::lsp_semantic_tokens_from_native(tokens, line_index),
+    )))
+}
+
+pub(crate) fn workspace_symbol(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::WorkspaceSymbolParams,
+) -> anyhow::Result<Option<lsp_types::WorkspaceSymbolResponse>> {
+    let symbols = snapshot.analysis_snapshot.workspace_symbols(params.query)?;
+    let document_manager = snapshot.document_manager.read();
+    let mut infos = Vec::with_capacity(symbols.len());
+    for symbol in symbols {
+        let line_index = match snapshot.analysis_snapshot.line_index(symbol.file_id)? {
+            Some(line_index) => line_index,
+            None => continue,
+        };
+        let uri = match lsp_types::Url::from_file_path(
+            document_manager.lookup_by_file_id(symbol.file_id),
+        ) {
+            Ok(uri) => uri,
+            Err(_) => continue,
+        };
+        infos.extend(convert::lsp_symbol_information_from_native(
+            symbol, uri, line_index,
+        ));
+    }
+    Ok(Some(lsp_types::WorkspaceSymbolResponse::Flat(infos)))
+}
+
+pub(crate) fn code_action(
+    snapshot: &ServerSnapshot,
+    params: lsp_types::CodeActionParams,
+) -> anyhow::Result<Option<lsp_types::CodeActionResponse>> {
+    let uri = params.text_document.uri;
+    let path = path_buf_from_url(&uri)?;
+    let file_id = try_opt!(snapshot.document_manager.read().lookup_by_path_buf(&path));
+    let line_index = try_opt!(snapshot.analysis_snapshot.line_index(file_id)?);
+    let start = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.range.start,
+    )?);
+    let end = try_opt!(convert::text_size_from_lsp_position(
+        snapshot,
+        file_id,
+        params.range.end,
+    )?);
+    let actions = try_opt!(snapshot
+        .analysis_snapshot
+        .code_actions(file_id, TextRange::new(start, end))?);
+    Ok(Some(
+        actions
+            .into_iter()
+            .filter_map(|action| {
+                convert::lsp_code_action_from_native(action, uri.clone(), line_index)
+            })
+            .map(lsp_types::CodeActionOrCommand::CodeAction)
+            .collect(),
+    ))
+}
+
 fn to_markup_doc(doc: String) -> lsp_types::Documentation {
     lsp_types::Documentation::MarkupContent(lsp_types::MarkupContent {
         kind: lsp_types::MarkupKind::Markdown,
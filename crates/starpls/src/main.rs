@@ -1,9 +1,13 @@
-use check::run_check;
+use check::{run_check, OutputFormat};
 use clap::{Args, Parser, Subcommand};
 use lsp_server::Connection;
 use lsp_types::{
-    CompletionOptions, HoverProviderCapability, OneOf, ServerCapabilities, SignatureHelpOptions,
-    TextDocumentSyncCapability, TextDocumentSyncKind,
+    CallHierarchyServerCapability, CodeActionProviderCapability, CodeLensOptions,
+    CompletionOptions, FoldingRangeProviderCapability, HoverProviderCapability, OneOf,
+    RenameOptions, SelectionRangeProviderCapability, SemanticTokensFullOptions,
+    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities,
+    ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TypeDefinitionProviderCapability,
 };
 
 mod check;
@@ -32,11 +36,17 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     Check {
-        /// Paths to typecheck.
+        /// Paths to typecheck. Directories and glob patterns, e.g. `pkg/**/*.bzl`, are expanded
+        /// to the Starlark files that they contain.
         paths: Vec<String>,
         /// Path to the Bazel output base.
         #[clap(long = "output_base")]
         output_base: Option<String>,
+        /// Format to print diagnostics in.
+        #[clap(long = "format", value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        #[command(flatten)]
+        args: ServerArgs,
     },
     Server(ServerArgs),
 }
@@ -46,18 +56,42 @@ pub(crate) struct ServerArgs {
     /// Path to the Bazel binary.
     #[clap(long = "bazel_path")]
     bazel_path: Option<String>,
+    /// Path to a `builtin.pb` file describing the Bazel builtins. Defaults to the bundled builtins.
+    #[clap(long = "builtins_path")]
+    builtins_path: Option<String>,
     /// Infer attributes on a rule implementation function's context parameter.
     #[clap(long = "experimental_infer_ctx_attributes", default_value_t = false)]
     infer_ctx_attributes: bool,
     #[clap(long = "experimental_use_code_flow_analysis", default_value_t = false)]
     use_code_flow_analysis: bool,
+    /// Disallow merging dicts with the `|` operator, for Starlark implementations that don't
+    /// support it.
+    #[clap(long = "disallow_dict_union", default_value_t = false)]
+    disallow_dict_union: bool,
+    /// Allow comparing values with the `is` and `is not` operators, for Starlark implementations
+    /// that support them.
+    #[clap(long = "allow_identity_operators", default_value_t = false)]
+    allow_identity_operators: bool,
+    /// Maximum number of times a request is retried after being cancelled by a change before
+    /// responding with a `ContentModified` error. Defaults to 10.
+    #[clap(long = "max_request_retries")]
+    max_request_retries: Option<usize>,
+    /// Maximum depth of nested expressions to infer types for. Deeper expressions are inferred as
+    /// `Unknown`. Defaults to 512.
+    #[clap(long = "max_inference_depth")]
+    max_inference_depth: Option<usize>,
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Some(Commands::Check { paths, output_base }) => run_check(paths, output_base),
+        Some(Commands::Check {
+            paths,
+            output_base,
+            format,
+            args,
+        }) => run_check(paths, output_base, format, args),
         Some(Commands::Server(args)) => run_server(args),
         None => run_server(Default::default()),
     }
@@ -72,13 +106,38 @@ fn run_server(args: ServerArgs) -> anyhow::Result<()> {
     // Initialize the connection with server capabilities. For now, this consists
     // only of `TextDocumentSyncKind.Full`.
     let server_capabilities = serde_json::to_value(&ServerCapabilities {
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
         completion_provider: Some(CompletionOptions {
             trigger_characters: Some(make_trigger_characters(COMPLETION_TRIGGER_CHARACTERS)),
             ..Default::default()
         }),
         definition_provider: Some(OneOf::Left(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
         document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
         hover_provider: Some(HoverProviderCapability::Simple(true)),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: Default::default(),
+        })),
+        selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: SemanticTokensLegend {
+                    token_types: convert::SEMANTIC_TOKEN_TYPES.to_vec(),
+                    token_modifiers: convert::SEMANTIC_TOKEN_MODIFIERS.to_vec(),
+                },
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..Default::default()
+            },
+        )),
         signature_help_provider: Some(SignatureHelpOptions {
             trigger_characters: Some(make_trigger_characters(SIGNATURE_HELP_TRIGGER_CHARACTERS)),
             ..Default::default()
@@ -86,6 +145,8 @@ fn run_server(args: ServerArgs) -> anyhow::Result<()> {
         text_document_sync: Some(TextDocumentSyncCapability::Kind(
             TextDocumentSyncKind::INCREMENTAL,
         )),
+        type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
         ..Default::default()
     })?;
     let initialize_params = serde_json::from_value(connection.initialize(server_capabilities)?)?;
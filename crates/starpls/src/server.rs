@@ -11,7 +11,7 @@ use rustc_hash::FxHashSet;
 use starpls_bazel::{
     build_language::decode_rules,
     client::{BazelCLI, BazelClient},
-    decode_builtins, APIContext, Builtins,
+    decode_builtins, load_builtins, APIContext, Builtins,
 };
 use starpls_common::{Dialect, FileId, FileInfo};
 use starpls_ide::{Analysis, AnalysisSnapshot, Change, InferenceOptions};
@@ -61,8 +61,20 @@ impl Server {
         let task_pool_handle = TaskPoolHandle::new(task_pool_receiver, task_pool);
         let mut has_bazel_init_err = false;
 
-        // Load Bazel builtins from the specified file.
-        let builtins = match load_bazel_builtins() {
+        // Load Bazel builtins from the specified file, falling back to the bundled builtins.
+        let custom_builtins =
+            config
+                .args
+                .builtins_path
+                .as_ref()
+                .and_then(|path| match load_builtins(path) {
+                    Ok(builtins) => Some(builtins),
+                    Err(err) => {
+                        eprintln!("server: failed to load builtins from {}, {}", path, err);
+                        None
+                    }
+                });
+        let builtins = match custom_builtins.map_or_else(load_bazel_builtins, Ok) {
             Ok(builtins) => builtins,
             Err(err) => {
                 eprintln!("server: failed to load builtins, {}", err);
@@ -173,8 +185,11 @@ impl Server {
         let mut analysis = Analysis::new(
             Arc::new(loader),
             InferenceOptions {
+                allow_identity_operators: config.args.allow_identity_operators,
+                disallow_dict_union: config.args.disallow_dict_union,
                 infer_ctx_attributes: config.args.infer_ctx_attributes,
                 use_code_flow_analysis: config.args.use_code_flow_analysis,
+                max_inference_depth: config.args.max_inference_depth,
             },
         );
 
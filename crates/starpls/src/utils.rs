@@ -67,39 +67,6 @@ where
         _ => return Vec::<lsp_types::Location>::new().into(),
     };
 
-    // let get_line_index = |file_id| snapshot.analysis_snapshot.line_index(file_id);
-    let to_lsp_location = |location: LocationLink| -> Option<lsp_types::Location> {
-        let location = match location {
-            LocationLink::Local {
-                target_range,
-                target_file_id,
-                ..
-            } => {
-                let target_line_index = snapshot
-                    .analysis_snapshot
-                    .line_index(target_file_id)
-                    .ok()??;
-                let range = convert::lsp_range_from_text_range(target_range, target_line_index);
-                lsp_types::Location {
-                    uri: lsp_types::Url::from_file_path(
-                        snapshot
-                            .document_manager
-                            .read()
-                            .lookup_by_file_id(target_file_id),
-                    )
-                    .ok()?,
-                    range: range?,
-                }
-            }
-            LocationLink::External { target_path, .. } => lsp_types::Location {
-                uri: lsp_types::Url::from_file_path(target_path).ok()?,
-                range: Default::default(),
-            },
-        };
-
-        Some(location)
-    };
-
     let to_lsp_location_link = |location: LocationLink| -> Option<lsp_types::LocationLink> {
         let location_link = match location {
             LocationLink::Local {
@@ -150,8 +117,43 @@ where
             .into()
     } else {
         locations
-            .flat_map(to_lsp_location)
+            .flat_map(|location| lsp_location_from_location_link(snapshot, location))
             .collect::<Vec<_>>()
             .into()
     }
 }
+
+pub(crate) fn lsp_location_from_location_link(
+    snapshot: &ServerSnapshot,
+    location: LocationLink,
+) -> Option<lsp_types::Location> {
+    let location = match location {
+        LocationLink::Local {
+            target_range,
+            target_file_id,
+            ..
+        } => {
+            let target_line_index = snapshot
+                .analysis_snapshot
+                .line_index(target_file_id)
+                .ok()??;
+            let range = convert::lsp_range_from_text_range(target_range, target_line_index);
+            lsp_types::Location {
+                uri: lsp_types::Url::from_file_path(
+                    snapshot
+                        .document_manager
+                        .read()
+                        .lookup_by_file_id(target_file_id),
+                )
+                .ok()?,
+                range: range?,
+            }
+        }
+        LocationLink::External { target_path, .. } => lsp_types::Location {
+            uri: lsp_types::Url::from_file_path(target_path).ok()?,
+            range: Default::default(),
+        },
+    };
+
+    Some(location)
+}
@@ -40,6 +40,7 @@ pub const BUILTINS_TYPES_DENY_LIST: &[&str] = &[
     "bool",
     "bytes",
     "builtin_function_or_method",
+    "depset",
     "dict",
     "float",
     "function",
@@ -19,6 +19,7 @@ pub struct FileRange {
 /// A severity level for diagnostic messages.
 #[derive(Clone, Debug)]
 pub enum Severity {
+    Hint,
     Warning,
     Error,
 }
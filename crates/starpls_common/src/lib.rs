@@ -2,7 +2,8 @@ use std::{fmt::Debug, path::PathBuf};
 
 use starpls_bazel::APIContext;
 use starpls_syntax::{
-    line_index as syntax_line_index, parse_module, LineIndex, Module, ParseTree, SyntaxNode,
+    line_index as syntax_line_index, parse_module, LineIndex, Module, ParseTree, SyntaxKind,
+    SyntaxNode, TextRange, TextSize,
 };
 
 pub use crate::diagnostics::{Diagnostic, Diagnostics, FileRange, Severity};
@@ -153,9 +154,51 @@ pub fn parse(db: &dyn Db, file: File) -> Parse {
             },
         )
     });
+
+    // The lexer accepts indentation that mixes tabs and spaces by counting each tab as four
+    // spaces, which might not match how the indentation is displayed.
+    for range in mixed_indentation_ranges(&parse.syntax()) {
+        Diagnostics::push(
+            db,
+            Diagnostic {
+                message: "Indentation mixes tabs and spaces".to_string(),
+                range: FileRange {
+                    file_id: file.id(db),
+                    range,
+                },
+                severity: Severity::Warning,
+            },
+        )
+    }
+
     Parse::new(db, file, parse)
 }
 
+/// Finds the leading whitespace of the non-blank lines that contain both tabs and spaces.
+fn mixed_indentation_ranges(root: &SyntaxNode) -> Vec<TextRange> {
+    root.descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| match token.kind() {
+            SyntaxKind::INDENT | SyntaxKind::DEDENT => true,
+            SyntaxKind::WHITESPACE => token
+                .prev_token()
+                .map_or(true, |prev| prev.kind() == SyntaxKind::NEWLINE),
+            _ => false,
+        })
+        .filter(|token| {
+            token
+                .next_token()
+                .map_or(false, |next| next.kind() != SyntaxKind::NEWLINE)
+        })
+        .filter_map(|token| {
+            let text = token.text();
+            let indentation = &text[..text.find('\n').unwrap_or(text.len())];
+            (indentation.contains(' ') && indentation.contains('\t'))
+                .then(|| TextRange::at(token.text_range().start(), TextSize::of(indentation)))
+        })
+        .collect()
+}
+
 #[salsa::tracked]
 struct LineIndexResult {
     #[return_ref]
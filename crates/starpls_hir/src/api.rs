@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use starpls_common::{parse, Diagnostic, Diagnostics, File, InFile};
+use starpls_common::{parse, Diagnostic, Diagnostics, File, FileRange, InFile};
 use starpls_syntax::{
     ast::{self, AstNode, AstPtr, SyntaxNodePtr},
     TextSize,
@@ -11,9 +11,10 @@ use crate::{
     def::{
         self,
         resolver::Resolver,
-        scope::{self, module_scopes, ParameterDef},
+        scope::{self, module_scopes, ExecutionScopeId, ParameterDef},
         Function as HirDefFunction, LoadItemId, Stmt,
     },
+    display::DisplayWithDb,
     module, source_map,
     typeck::{
         self, builtins::BuiltinFunction, intrinsics::IntrinsicFunction, resolve_type_ref, with_tcx,
@@ -135,6 +136,22 @@ impl<'a> Semantics<'a> {
         })
     }
 
+    /// Finds the range of the declaration that a name reference resolves to, i.e. the variable,
+    /// function, parameter, or load item that defines it. Builtins don't have a declaration.
+    pub fn resolve_def_name(&self, file: File, name_ref: &ast::NameRef) -> Option<FileRange> {
+        let def = self
+            .scope_for_expr(file, &ast::Expression::cast(name_ref.syntax().clone())?)?
+            .resolve_name(&Name::from_ast_node(name_ref.clone()))
+            .into_iter()
+            .next()?;
+        let def_file = def.file(self.db)?;
+        let ptr = def.syntax_node_ptr(self.db, def_file)?;
+        Some(FileRange {
+            file_id: def_file.id(self.db),
+            range: ptr.text_range(),
+        })
+    }
+
     pub fn def_for_load_item(&self, load_item: &LoadItem) -> Option<InFile<ScopeDef>> {
         let load_stmt = load_item.load_stmt(self.db)?;
         let loaded_file = self.resolve_load_stmt(load_item.file, &load_stmt)?;
@@ -236,6 +253,22 @@ impl ScopeDef {
             _ => true,
         }
     }
+
+    /// Returns the file containing this definition, or `None` for builtins.
+    pub fn file(&self, db: &dyn Db) -> Option<File> {
+        match self {
+            ScopeDef::Callable(Callable(CallableInner::HirDef(func))) => Some(func.file(db)),
+            ScopeDef::Variable(Variable {
+                id: Some((file, _)),
+            }) => Some(*file),
+            ScopeDef::Parameter(Param(ParamInner::Param {
+                parent: Some(parent),
+                ..
+            })) => Some(parent.file(db)),
+            ScopeDef::LoadItem(LoadItem { file, .. }) => Some(*file),
+            _ => None,
+        }
+    }
 }
 
 impl From<scope::ScopeDef> for ScopeDef {
@@ -299,6 +332,14 @@ impl SemanticsScope<'_> {
         }
         defs
     }
+
+    /// Returns whether the given name resolves to a declaration at the top level of the module.
+    pub fn resolves_to_module_global(&self, name: &Name) -> bool {
+        matches!(
+            self.resolver.resolve_name(name),
+            Some((ExecutionScopeId::Module, _))
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -329,6 +370,25 @@ impl Type {
         self.ty.kind() == &TyKind::Unknown
     }
 
+    pub fn is_unbound(&self) -> bool {
+        self.ty.kind() == &TyKind::Unbound
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.ty.kind() == &TyKind::None
+    }
+
+    /// Returns whether this type is a union containing `None`, e.g. `string | None`.
+    pub fn is_optional(&self, _db: &dyn Db) -> bool {
+        self.ty.is_union() && self.ty.contains(&Ty::none())
+    }
+
+    /// Strips `None` from this type if it is optional, e.g. `string | None` becomes `string`.
+    /// Other types are returned as is.
+    pub fn unwrap_optional(&self, _db: &dyn Db) -> Type {
+        self.ty.clone().narrow_none(false).into()
+    }
+
     pub fn is_user_defined_function(&self) -> bool {
         matches!(self.ty.kind(), TyKind::Function(_))
     }
@@ -340,6 +400,25 @@ impl Type {
         }
     }
 
+    /// Renders the parameters and return type of a callable type as a one-line signature, e.g.
+    /// `(x: int, y = 1) -> string`. Returns `None` if this type isn't callable.
+    pub fn signature_string(&self, db: &dyn Db) -> Option<String> {
+        let ret_ty = self.ty.ret_ty(db)?;
+        let mut labels = Vec::new();
+
+        // Rules and tags only accept keyword arguments.
+        if matches!(self.ty.kind(), TyKind::Rule(_) | TyKind::Tag(_)) {
+            labels.push("*".to_string());
+        }
+        labels.extend(
+            self.ty
+                .params(db)?
+                .map(|(param, ty)| param.label(db, &ty.into())),
+        );
+
+        Some(format!("({}) -> {}", labels.join(", "), ret_ty.display(db)))
+    }
+
     pub fn doc(&self, db: &dyn Db) -> Option<String> {
         match self.ty.kind() {
             TyKind::BuiltinFunction(func) => Some(func.doc(db).clone()),
@@ -442,6 +521,33 @@ impl Type {
             _ => None,
         }
     }
+
+    /// Returns the location that declares this type, e.g. the `provider()` call for a custom
+    /// provider or its instances, the `struct()` call for an inline struct, or the `def` statement
+    /// for a function. Builtin types don't have a declaration, so `None` is returned for them.
+    pub fn definition(&self, db: &dyn Db) -> Option<FileRange> {
+        let ty = self.ty.clone().narrow_none(false);
+        let expr = match ty.kind() {
+            TyKind::Provider(Provider::Custom(provider))
+            | TyKind::ProviderInstance(Provider::Custom(provider))
+            | TyKind::ProviderRawConstructor(_, Provider::Custom(provider)) => {
+                provider.call_expr.clone()
+            }
+            TyKind::Struct(Some(DefStruct::Inline { call_expr, .. })) => call_expr.clone(),
+            TyKind::Function(func) => {
+                return Some(FileRange {
+                    file_id: func.file(db).id(db),
+                    range: func.ptr(db).text_range(),
+                })
+            }
+            _ => return None,
+        };
+        let ptr = source_map(db, expr.file).expr_map_back.get(&expr.value)?;
+        Some(FileRange {
+            file_id: expr.file.id(db),
+            range: ptr.syntax_node_ptr().text_range(),
+        })
+    }
 }
 
 impl From<Ty> for Type {
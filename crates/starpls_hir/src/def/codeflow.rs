@@ -2,11 +2,12 @@ use either::Either;
 use id_arena::{Arena, Id};
 use rustc_hash::FxHashMap;
 use starpls_common::File;
+use starpls_syntax::ast::{BinaryOp, CmpOp, IdentityOp, UnaryOp};
 
 use crate::{
     def::{
         scope::{module_scopes, ExecutionScopeId, ScopeHirId, Scopes},
-        CompClause, Expr, Stmt, StmtId,
+        CompClause, Expr, Literal, Stmt, StmtId,
     },
     lower, Db, ExprId, Module, Name,
 };
@@ -32,6 +33,14 @@ pub(crate) enum FlowNode {
     Loop {
         antecedents: Vec<FlowNodeId>,
     },
+    /// Narrows the type of `name` based on the condition guarding the current branch, e.g.
+    /// `if x != None:`. If `is_none` is true, then `name` is known to be `None` in the branch,
+    /// otherwise it is known to be not `None`.
+    Narrow {
+        name: Name,
+        is_none: bool,
+        antecedent: FlowNodeId,
+    },
     Unreachable,
 }
 
@@ -112,21 +121,23 @@ impl<'a> CodeFlowLowerCtx<'a> {
                 let post_if_node = self.new_flow_node(FlowNode::Branch {
                     antecedents: Vec::new(),
                 });
+                self.curr_node = self.narrow_for_condition(pre_if_node, *test, true);
                 self.lower_stmts(if_stmts);
                 self.push_antecedent(post_if_node, self.curr_node);
+                let pre_else_node = self.narrow_for_condition(pre_if_node, *test, false);
                 match elif_or_else_stmts {
                     Some(Either::Left(elif_stmt)) => {
-                        self.curr_node = pre_if_node;
+                        self.curr_node = pre_else_node;
                         self.lower_stmt(*elif_stmt);
                         self.push_antecedent(post_if_node, self.curr_node);
                     }
                     Some(Either::Right(else_stmts)) => {
-                        self.curr_node = pre_if_node;
+                        self.curr_node = pre_else_node;
                         self.lower_stmts(&else_stmts);
                         self.push_antecedent(post_if_node, self.curr_node);
                     }
                     _ => {
-                        self.push_antecedent(post_if_node, pre_if_node);
+                        self.push_antecedent(post_if_node, pre_else_node);
                     }
                 }
 
@@ -216,6 +227,26 @@ impl<'a> CodeFlowLowerCtx<'a> {
                 self.lower_comp_clauses(comp_clauses);
                 self.lower_expr(*expr);
             }
+            Expr::If {
+                if_expr,
+                test,
+                else_expr,
+            } => {
+                // Each arm of a conditional expression is narrowed by the condition the same
+                // way as the branches of an `if` statement.
+                self.lower_expr(*test);
+                let pre_if_node = self.curr_node;
+                let post_if_node = self.new_flow_node(FlowNode::Branch {
+                    antecedents: Vec::new(),
+                });
+                self.curr_node = self.narrow_for_condition(pre_if_node, *test, true);
+                self.lower_expr(*if_expr);
+                self.push_antecedent(post_if_node, self.curr_node);
+                self.curr_node = self.narrow_for_condition(pre_if_node, *test, false);
+                self.lower_expr(*else_expr);
+                self.push_antecedent(post_if_node, self.curr_node);
+                self.curr_node = post_if_node;
+            }
             expr => expr.walk_child_exprs(|expr| {
                 self.lower_expr(expr);
             }),
@@ -266,6 +297,68 @@ impl<'a> CodeFlowLowerCtx<'a> {
         }
     }
 
+    /// Allocates a `Narrow` node for the branch taken when `test` evaluates to `sense`, if `test`
+    /// checks a name against `None`. Otherwise, `antecedent` is returned as is.
+    fn narrow_for_condition(
+        &mut self,
+        antecedent: FlowNodeId,
+        test: ExprId,
+        sense: bool,
+    ) -> FlowNodeId {
+        if antecedent == self.unreachable_node {
+            return antecedent;
+        }
+        match self.none_check(test, sense) {
+            Some((name, is_none)) => self.new_flow_node(FlowNode::Narrow {
+                name,
+                is_none,
+                antecedent,
+            }),
+            None => antecedent,
+        }
+    }
+
+    /// Determines whether `test` evaluating to `sense` implies that a name is or isn't `None`.
+    /// For example, `if x:` implies that `x` is not `None` in the `if` branch, but nothing about
+    /// `x` in the `else` branch, since `x` could also be some other falsy value.
+    fn none_check(&self, test: ExprId, sense: bool) -> Option<(Name, bool)> {
+        match &self.module[test] {
+            Expr::Name { name } => sense.then(|| (name.clone(), false)),
+            Expr::Paren { expr } => self.none_check(*expr, sense),
+            Expr::Unary {
+                op: Some(UnaryOp::Not),
+                expr,
+            } => self.none_check(*expr, !sense),
+            Expr::Binary {
+                lhs,
+                rhs,
+                op: Some(op @ (BinaryOp::Cmp(CmpOp::Eq | CmpOp::Ne) | BinaryOp::IdentityOp(_))),
+            } => {
+                let name = match (&self.module[*lhs], &self.module[*rhs]) {
+                    (
+                        Expr::Name { name },
+                        Expr::Literal {
+                            literal: Literal::None,
+                        },
+                    )
+                    | (
+                        Expr::Literal {
+                            literal: Literal::None,
+                        },
+                        Expr::Name { name },
+                    ) => name,
+                    _ => return None,
+                };
+                let is_eq = matches!(
+                    op,
+                    BinaryOp::Cmp(CmpOp::Eq) | BinaryOp::IdentityOp(IdentityOp::Is)
+                );
+                Some((name.clone(), is_eq == sense))
+            }
+            _ => None,
+        }
+    }
+
     fn new_flow_node(&mut self, data: FlowNode) -> FlowNodeId {
         self.result.flow_nodes.alloc(data)
     }
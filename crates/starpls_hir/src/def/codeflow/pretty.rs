@@ -50,7 +50,7 @@ impl<'a> CodeFlowGraphPrettyCtx<'a> {
             wln!(&mut self.result, "{}data: {:?}", self.indent, flow_node);
             w!(&mut self.result, "{}antecedents: [", self.indent);
             match flow_node {
-                FlowNode::Assign { antecedent, .. } => {
+                FlowNode::Assign { antecedent, .. } | FlowNode::Narrow { antecedent, .. } => {
                     self.result.push_str(&self.format_flow_node_id(*antecedent));
                 }
                 FlowNode::Branch { antecedents } | FlowNode::Loop { antecedents } => {
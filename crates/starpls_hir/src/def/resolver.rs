@@ -101,6 +101,23 @@ impl<'a> Resolver<'a> {
         Some((first_execution_scope, defs))
     }
 
+    /// Returns whether the resolver's scope is inside the body of a function.
+    pub(crate) fn is_in_function_body(&self) -> bool {
+        self.scopes()
+            .any(|scope| matches!(scope.execution_scope, ExecutionScopeId::Def(_)))
+    }
+
+    /// Returns whether the given name is declared in a scope belonging to an execution scope other
+    /// than the given one, e.g. at the module level or in an enclosing function.
+    pub(crate) fn is_declared_outside_execution_scope(
+        &self,
+        name: &Name,
+        execution_scope: ExecutionScopeId,
+    ) -> bool {
+        self.scopes()
+            .any(|scope| scope.execution_scope != execution_scope && scope.defs.contains_key(name))
+    }
+
     pub(crate) fn resolve_name_in_prelude_or_builtins(&self, name: &Name) -> Option<Vec<ScopeDef>> {
         // Fall back to prelude, and then to the builtins scope.
         let mut defs = None;
@@ -115,43 +132,7 @@ impl<'a> Resolver<'a> {
                 .map(|export| vec![export.into()])
         }
 
-        defs.or_else(|| {
-            intrinsic_functions(self.db)
-                .functions(self.db)
-                .get(name)
-                .copied()
-                .map(|func| vec![ScopeDef::IntrinsicFunction(func)])
-        })
-        .or_else(|| self.resolve_name_in_builtin_globals(name))
-    }
-
-    fn resolve_name_in_builtin_globals(&self, name: &Name) -> Option<Vec<ScopeDef>> {
-        let api_context = self.file.api_context(self.db)?;
-        let globals = builtin_globals(self.db, self.file.dialect(self.db));
-        let resolve_in_api_globals = |api_globals: &APIGlobals| {
-            api_globals
-                .functions
-                .get(name.as_str())
-                .copied()
-                .map(|func| vec![ScopeDef::BuiltinFunction(func)])
-                .or_else(|| {
-                    api_globals
-                        .variables
-                        .get(name.as_str())
-                        .cloned()
-                        .map(|type_ref| vec![ScopeDef::BuiltinVariable(type_ref)])
-                })
-        };
-
-        if api_context == APIContext::Repo {
-            return resolve_in_api_globals(globals.repo_globals(self.db));
-        }
-
-        resolve_in_api_globals(globals.bzl_globals(self.db)).or_else(|| match api_context {
-            APIContext::Module => resolve_in_api_globals(globals.bzlmod_globals(self.db)),
-            APIContext::Workspace => resolve_in_api_globals(globals.workspace_globals(self.db)),
-            _ => None,
-        })
+        defs.or_else(|| resolve_builtin_name(self.db, self.file, name))
     }
 
     pub(crate) fn names(&self) -> FxHashMap<Name, ScopeDef> {
@@ -365,3 +346,43 @@ pub(crate) struct SymbolDef<'a> {
     pub(crate) execution_scope: ExecutionScopeId,
     pub(crate) def: &'a ScopeDef,
 }
+
+/// Resolves a name to a Starlark intrinsic, or to a builtin global available in the file's API
+/// context.
+pub(crate) fn resolve_builtin_name(db: &dyn Db, file: File, name: &Name) -> Option<Vec<ScopeDef>> {
+    intrinsic_functions(db)
+        .functions(db)
+        .get(name)
+        .copied()
+        .map(|func| vec![ScopeDef::IntrinsicFunction(func)])
+        .or_else(|| resolve_name_in_builtin_globals(db, file, name))
+}
+
+fn resolve_name_in_builtin_globals(db: &dyn Db, file: File, name: &Name) -> Option<Vec<ScopeDef>> {
+    let api_context = file.api_context(db)?;
+    let globals = builtin_globals(db, file.dialect(db));
+    let resolve_in_api_globals = |api_globals: &APIGlobals| {
+        api_globals
+            .functions
+            .get(name.as_str())
+            .copied()
+            .map(|func| vec![ScopeDef::BuiltinFunction(func)])
+            .or_else(|| {
+                api_globals
+                    .variables
+                    .get(name.as_str())
+                    .cloned()
+                    .map(|type_ref| vec![ScopeDef::BuiltinVariable(type_ref)])
+            })
+    };
+
+    if api_context == APIContext::Repo {
+        return resolve_in_api_globals(globals.repo_globals(db));
+    }
+
+    resolve_in_api_globals(globals.bzl_globals(db)).or_else(|| match api_context {
+        APIContext::Module => resolve_in_api_globals(globals.bzlmod_globals(db)),
+        APIContext::Workspace => resolve_in_api_globals(globals.workspace_globals(db)),
+        _ => None,
+    })
+}
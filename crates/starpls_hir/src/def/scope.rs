@@ -2,11 +2,18 @@ use std::collections::{hash_map::Entry, VecDeque};
 
 use either::Either;
 use id_arena::{Arena, Id};
-use rustc_hash::FxHashMap;
-use starpls_common::{Diagnostic, Diagnostics, File, FileRange, Severity};
+use rustc_hash::{FxHashMap, FxHashSet};
+use starpls_common::{parse, Diagnostic, Diagnostics, File, FileRange, Severity};
+use starpls_syntax::{
+    ast::{self, AstNode},
+    TextRange,
+};
 
 use crate::{
-    def::{CompClause, Expr, ExprId, Function, LoadItem, LoadItemId, Param, ParamId, Stmt, StmtId},
+    def::{
+        resolver::resolve_builtin_name, CompClause, Expr, ExprId, Function, Literal, LoadItem,
+        LoadItemId, Param, ParamId, Stmt, StmtId,
+    },
     lower,
     typeck::{builtins::BuiltinFunction, intrinsics::IntrinsicFunction, TypeRef},
     Db, Module, ModuleInfo, ModuleSourceMap, Name,
@@ -112,6 +119,8 @@ pub(crate) struct Scopes {
     pub(crate) scopes: Arena<Scope>,
     pub(crate) scopes_by_hir_id: FxHashMap<ScopeHirId, ScopeId>,
     pub(crate) scopes_by_execution_scope_id: FxHashMap<ExecutionScopeId, ScopeId>,
+    /// The scopes in which earlier parameters are visible, i.e. those of parameter default values.
+    pub(crate) param_default_scopes: FxHashSet<ScopeId>,
 }
 
 struct DeferredScope {
@@ -138,6 +147,7 @@ impl Scopes {
                 scopes: Default::default(),
                 scopes_by_hir_id: Default::default(),
                 scopes_by_execution_scope_id: Default::default(),
+                param_default_scopes: Default::default(),
             },
             curr_execution_scope: ExecutionScopeId::Module,
         }
@@ -179,6 +189,13 @@ impl Scopes {
             .copied()
     }
 
+    /// Returns whether the given expression is part of a parameter's default value that follows
+    /// other parameters, and so may refer to them.
+    pub(crate) fn is_in_param_default(&self, expr: ExprId) -> bool {
+        self.scope_for_hir_id(expr)
+            .map_or(false, |scope| self.param_default_scopes.contains(&scope))
+    }
+
     pub(crate) fn scope_chain(&self, scope: Option<ScopeId>) -> impl Iterator<Item = ScopeId> + '_ {
         std::iter::successors(scope, |scope| self.scopes[*scope].parent)
     }
@@ -233,10 +250,12 @@ impl ScopeCollector<'_> {
             self.collect_stmts_defer(&data.stmts, scope);
         }
 
+        self.check_unused_locals();
         self.scopes
     }
 
     fn collect_stmts_defer(&mut self, stmts: &Box<[StmtId]>, mut current: ScopeId) -> ScopeId {
+        self.check_unreachable_stmts(stmts, current);
         let mut deferred = VecDeque::new();
         for stmt in stmts.iter().copied() {
             self.collect_stmt(&mut deferred, stmt, &mut current);
@@ -257,11 +276,330 @@ impl ScopeCollector<'_> {
         stmts: &Box<[StmtId]>,
         current: &mut ScopeId,
     ) {
+        self.check_unreachable_stmts(stmts, *current);
         for stmt in stmts.iter().copied() {
             self.collect_stmt(deferred, stmt, current);
         }
     }
 
+    /// Reports the statements following the first unconditional `return`, `break`, `continue`,
+    /// or `fail()` in a block as unreachable. Nested blocks are checked separately when they are
+    /// collected.
+    fn check_unreachable_stmts(&self, stmts: &[StmtId], current: ScopeId) {
+        let first_unreachable = match stmts
+            .iter()
+            .position(|stmt| self.is_terminating_stmt(*stmt, current))
+        {
+            Some(index) => index + 1,
+            None => return,
+        };
+
+        let range_for_stmt = |stmt: &StmtId| {
+            self.source_map
+                .stmt_map_back
+                .get(stmt)
+                .map(|ptr| ptr.syntax_node_ptr().text_range())
+        };
+
+        let range = match (
+            stmts.get(first_unreachable).and_then(range_for_stmt),
+            stmts.last().and_then(range_for_stmt),
+        ) {
+            (Some(start), Some(end)) => start.cover(end),
+            _ => return,
+        };
+
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                message: "Code is unreachable".to_string(),
+                severity: Severity::Warning,
+                range: FileRange {
+                    file_id: self.file.id(self.db),
+                    range,
+                },
+            },
+        );
+    }
+
+    /// Reports `if` conditions that are literals, e.g. `if True:`, since they always evaluate
+    /// to the same value.
+    fn check_constant_condition(&self, test: ExprId) {
+        let value = match &self.module[test] {
+            Expr::Literal {
+                literal: Literal::Bool(value),
+            } => *value,
+            Expr::Literal {
+                literal: Literal::None,
+            } => false,
+            Expr::Literal {
+                literal: Literal::Int(value),
+            } => *value != 0,
+            _ => return,
+        };
+
+        let range = match self.source_map.expr_map_back.get(&test) {
+            Some(ptr) => ptr.syntax_node_ptr().text_range(),
+            None => return,
+        };
+
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                message: format!(
+                    "Condition always evaluates to {}",
+                    if value { "True" } else { "False" }
+                ),
+                severity: Severity::Warning,
+                range: FileRange {
+                    file_id: self.file.id(self.db),
+                    range,
+                },
+            },
+        );
+    }
+
+    /// Reports declarations that shadow builtins, e.g. `list = []`, since the builtin can no
+    /// longer be referenced from the enclosing scope.
+    fn check_builtin_shadowing(&self, name: &Name, range: TextRange) {
+        if resolve_builtin_name(self.db, self.file, name).is_none() {
+            return;
+        }
+
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                message: format!("\"{}\" shadows a builtin", name.as_str()),
+                severity: Severity::Warning,
+                range: FileRange {
+                    file_id: self.file.id(self.db),
+                    range,
+                },
+            },
+        );
+    }
+
+    /// Reports local variables and parameters of functions that are never read. A name counts as
+    /// read if any name expression in the function, including in nested functions, resolves to
+    /// one of its declarations. Names starting with `_` are exempt by convention.
+    fn check_unused_locals(&self) {
+        // The target of an augmented assignment, e.g. `x += 1`, also reads the variable.
+        let augmented_targets = self
+            .module
+            .stmts
+            .iter()
+            .filter_map(|(_, stmt)| match stmt {
+                Stmt::Assign {
+                    lhs, op: Some(_), ..
+                } => Some(*lhs),
+                _ => None,
+            })
+            .collect::<FxHashSet<_>>();
+        let decls = self
+            .scopes
+            .scopes
+            .iter()
+            .flat_map(|(_, scope)| scope.defs.values().flatten())
+            .filter_map(|def| match def {
+                ScopeDef::Variable(VariableDef { expr, .. }) => Some(*expr),
+                _ => None,
+            })
+            .collect::<FxHashSet<_>>();
+
+        let mut used = FxHashSet::default();
+        for (expr, hir_expr) in self.module.exprs.iter() {
+            let name = match hir_expr {
+                Expr::Name { name } => name,
+                _ => continue,
+            };
+            if decls.contains(&expr) && !augmented_targets.contains(&expr) {
+                continue;
+            }
+            if let Some(scope) = self
+                .scopes
+                .scope_chain(self.scopes.scope_for_hir_id(expr))
+                .map(|scope| &self.scopes.scopes[scope])
+                .find(|scope| scope.defs.contains_key(name))
+            {
+                used.insert((scope.execution_scope, name));
+            }
+        }
+
+        let root = parse(self.db, self.file).syntax(self.db);
+        let mut unused = Vec::new();
+        for (_, scope) in self.scopes.scopes.iter() {
+            if !matches!(scope.execution_scope, ExecutionScopeId::Def(_)) {
+                continue;
+            }
+            for (name, defs) in scope.defs.iter() {
+                if name.as_str().starts_with('_') || used.contains(&(scope.execution_scope, name)) {
+                    continue;
+                }
+                for def in defs {
+                    match def {
+                        ScopeDef::Variable(VariableDef { expr, .. }) => {
+                            if let Some(ptr) = self.source_map.expr_map_back.get(expr) {
+                                unused.push((
+                                    ptr.syntax_node_ptr().text_range(),
+                                    Severity::Warning,
+                                    format!("Local variable \"{}\" is never used", name.as_str()),
+                                ));
+                            }
+                        }
+                        // Parameters are part of the function's signature, so they're only hinted.
+                        ScopeDef::Parameter(ParameterDef {
+                            index,
+                            func: Some(func),
+                        }) => {
+                            // Parameters are also declared in the enclosing scope for the default
+                            // values of later parameters. Only the declarations in the function's
+                            // own scope are reported.
+                            let param = func.params(self.db).get(*index);
+                            if param
+                                .and_then(|param| self.module.param_to_def_stmt.get(param))
+                                .map(|stmt| ExecutionScopeId::Def(*stmt))
+                                != Some(scope.execution_scope)
+                            {
+                                continue;
+                            }
+                            if let Some(name_node) = func
+                                .params(self.db)
+                                .get(*index)
+                                .and_then(|param| self.source_map.param_map_back.get(param))
+                                .map(|ptr| ptr.to_node(&root))
+                                .and_then(|param| {
+                                    param.syntax().children().find_map(ast::Name::cast)
+                                })
+                            {
+                                unused.push((
+                                    name_node.syntax().text_range(),
+                                    Severity::Hint,
+                                    format!("Parameter \"{}\" is never used", name.as_str()),
+                                ));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        unused.sort_by_key(|(range, _, _)| range.start());
+        for (range, severity, message) in unused {
+            Diagnostics::push(
+                self.db,
+                Diagnostic {
+                    message,
+                    severity,
+                    range: FileRange {
+                        file_id: self.file.id(self.db),
+                        range,
+                    },
+                },
+            );
+        }
+    }
+
+    /// Reports functions that return a value on some paths but implicitly return `None` on
+    /// others, either with a bare `return` or by reaching the end of their body. Functions that
+    /// only ever return `None` aren't reported.
+    fn check_missing_returns(&self, stmts: &[StmtId], current: ScopeId, range: TextRange) {
+        let mut returns_value = false;
+        let mut has_bare_return = false;
+        let mut stack = stmts.to_vec();
+        while let Some(stmt) = stack.pop() {
+            match &self.module.stmts[stmt] {
+                Stmt::Return { expr: Some(expr) } => {
+                    returns_value |= !matches!(
+                        &self.module[*expr],
+                        Expr::Literal {
+                            literal: Literal::None
+                        }
+                    );
+                }
+                Stmt::Return { expr: None } => has_bare_return = true,
+                Stmt::If {
+                    if_stmts,
+                    elif_or_else_stmts,
+                    ..
+                } => {
+                    stack.extend(if_stmts.iter().copied());
+                    match elif_or_else_stmts {
+                        Some(Either::Left(elif_stmt)) => stack.push(*elif_stmt),
+                        Some(Either::Right(else_stmts)) => stack.extend(else_stmts.iter().copied()),
+                        None => {}
+                    }
+                }
+                Stmt::For { stmts, .. } => stack.extend(stmts.iter().copied()),
+                _ => {}
+            }
+        }
+
+        if !returns_value || (!has_bare_return && self.is_terminating_block(stmts, current)) {
+            return;
+        }
+
+        Diagnostics::push(
+            self.db,
+            Diagnostic {
+                message: "Not all code paths return a value".to_string(),
+                severity: Severity::Warning,
+                range: FileRange {
+                    file_id: self.file.id(self.db),
+                    range,
+                },
+            },
+        );
+    }
+
+    fn is_terminating_stmt(&self, stmt: StmtId, current: ScopeId) -> bool {
+        match &self.module.stmts[stmt] {
+            Stmt::Return { .. } | Stmt::Break | Stmt::Continue => true,
+            Stmt::Expr { expr } => self.is_fail_call(*expr, current),
+
+            // An `if` statement only terminates the enclosing block if all of its branches do.
+            Stmt::If {
+                if_stmts,
+                elif_or_else_stmts,
+                ..
+            } => {
+                self.is_terminating_block(if_stmts, current)
+                    && match elif_or_else_stmts {
+                        Some(Either::Left(elif_stmt)) => {
+                            self.is_terminating_stmt(*elif_stmt, current)
+                        }
+                        Some(Either::Right(else_stmts)) => {
+                            self.is_terminating_block(else_stmts, current)
+                        }
+                        None => false,
+                    }
+            }
+            _ => false,
+        }
+    }
+
+    fn is_terminating_block(&self, stmts: &[StmtId], current: ScopeId) -> bool {
+        stmts
+            .iter()
+            .any(|stmt| self.is_terminating_stmt(*stmt, current))
+    }
+
+    fn is_fail_call(&self, expr: ExprId, current: ScopeId) -> bool {
+        let callee = match &self.module[expr] {
+            Expr::Call { callee, .. } => *callee,
+            _ => return false,
+        };
+
+        // Make sure that `fail` hasn't been shadowed by a user-defined name.
+        match &self.module[callee] {
+            Expr::Name { name } if name.as_str() == "fail" => !self
+                .scopes
+                .scope_chain(Some(current))
+                .any(|scope| self.scopes.scopes[scope].defs.contains_key(name)),
+            _ => false,
+        }
+    }
+
     fn collect_stmt(
         &mut self,
         deferred: &mut VecDeque<FunctionData>,
@@ -270,7 +608,17 @@ impl ScopeCollector<'_> {
     ) {
         match &self.module.stmts[stmt] {
             Stmt::Def { func, stmts } => {
-                self.collect_params(func.params(self.db), *current);
+                let root = parse(self.db, self.file).syntax(self.db);
+                if let Some(name) = func
+                    .ptr(self.db)
+                    .try_to_node(&root)
+                    .and_then(ast::DefStmt::cast)
+                    .and_then(|stmt| stmt.name())
+                {
+                    self.check_builtin_shadowing(func.name(self.db), name.syntax().text_range());
+                    self.check_missing_returns(stmts, *current, name.syntax().text_range());
+                }
+                self.collect_params(*func, *current);
                 *current = self.alloc_scope(*current);
                 self.scopes.add_decl(
                     *current,
@@ -289,6 +637,7 @@ impl ScopeCollector<'_> {
                 test,
                 elif_or_else_stmts,
             } => {
+                self.check_constant_condition(*test);
                 self.collect_expr(*test, *current, None);
                 self.collect_stmts(deferred, if_stmts, current);
                 match elif_or_else_stmts {
@@ -352,6 +701,9 @@ impl ScopeCollector<'_> {
             // Possible assignment targets: NAME, LIST, TUPLE, PAREN, DOT, INDEX, SLICE.
             match &self.module[expr] {
                 Expr::Name { name } => {
+                    if let Some(ptr) = self.source_map.expr_map_back.get(&expr) {
+                        self.check_builtin_shadowing(name, ptr.syntax_node_ptr().text_range());
+                    }
                     self.scopes.add_decl(
                         current,
                         name.clone(),
@@ -492,15 +844,73 @@ impl ScopeCollector<'_> {
         }
     }
 
-    fn collect_params(&mut self, params: &Box<[ParamId]>, current: ScopeId) {
-        for param in params.iter().copied() {
+    /// Collects the default values of a function's parameters. Default values are evaluated in
+    /// the enclosing scope, but may also refer to earlier parameters, e.g. `b = a` in
+    /// `def f(a, b = a)`, so each parameter is declared in a new scope for the ones that follow.
+    fn collect_params(&mut self, func: Function, current: ScopeId) {
+        let params = func.params(self.db);
+        let mut scope = current;
+        for (index, param) in params.iter().copied().enumerate() {
             let param = &self.module[param];
-            match param {
-                Param::Simple {
-                    default: Some(expr),
-                    ..
-                } => self.collect_expr(*expr, current, None),
-                _ => {}
+            if let Param::Simple {
+                default: Some(expr),
+                ..
+            } = param
+            {
+                self.check_later_param_refs(*expr, &params[index + 1..], current);
+                self.collect_expr(*expr, scope, None);
+            }
+            scope = self.alloc_scope(scope);
+            self.scopes.param_default_scopes.insert(scope);
+            self.scopes.add_decl(
+                scope,
+                param.name().clone(),
+                ScopeDef::Parameter(ParameterDef {
+                    index,
+                    func: Some(func),
+                }),
+            );
+        }
+    }
+
+    /// Reports references in a parameter's default value to the parameters that follow it, since
+    /// these aren't bound yet when the default value is evaluated. Names that also resolve in the
+    /// enclosing scope, e.g. `c` in `c = 5` followed by `def f(b = c, c = 1)`, refer to the
+    /// enclosing declaration instead and aren't reported.
+    fn check_later_param_refs(&self, default: ExprId, later_params: &[ParamId], current: ScopeId) {
+        let mut stack = vec![default];
+        while let Some(expr) = stack.pop() {
+            match &self.module[expr] {
+                Expr::Name { name }
+                    if later_params
+                        .iter()
+                        .any(|param| self.module[*param].name() == name)
+                        && !self
+                            .scopes
+                            .scope_chain(Some(current))
+                            .any(|scope| self.scopes.scopes[scope].defs.contains_key(name))
+                        && resolve_builtin_name(self.db, self.file, name).is_none() =>
+                {
+                    let range = match self.source_map.expr_map_back.get(&expr) {
+                        Some(ptr) => ptr.syntax_node_ptr().text_range(),
+                        None => continue,
+                    };
+                    Diagnostics::push(
+                        self.db,
+                        Diagnostic {
+                            message: format!(
+                                "Default value cannot refer to parameter \"{}\", which is declared later",
+                                name.as_str()
+                            ),
+                            severity: Severity::Error,
+                            range: FileRange {
+                                file_id: self.file.id(self.db),
+                                range,
+                            },
+                        },
+                    );
+                }
+                hir_expr => hir_expr.walk_child_exprs(|expr| stack.push(expr)),
             }
         }
     }
@@ -516,6 +926,12 @@ impl ScopeCollector<'_> {
     }
 
     fn alloc_scope(&mut self, parent: ScopeId) -> ScopeId {
-        self.scopes.alloc_scope(self.curr_execution_scope, parent)
+        let scope = self.scopes.alloc_scope(self.curr_execution_scope, parent);
+        // Scopes nested in a default value, e.g. of comprehensions, can also see earlier
+        // parameters.
+        if self.scopes.param_default_scopes.contains(&parent) {
+            self.scopes.param_default_scopes.insert(scope);
+        }
+        scope
     }
 }
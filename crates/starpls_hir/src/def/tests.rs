@@ -4,12 +4,13 @@ use starpls_bazel::{
     env::{make_build_builtins, make_bzl_builtins},
     APIContext,
 };
-use starpls_common::{Db as _, Dialect, FileId, FileInfo};
+use starpls_common::{parse, Db as _, Dialect, FileId, FileInfo};
+use starpls_syntax::ast::{self, AstNode};
 use starpls_test_util::parse_fixture;
 
 use crate::{
-    def::resolver::Resolver, test_database::TestDatabase, typeck::intrinsics::intrinsic_functions,
-    Db as _,
+    def::resolver::Resolver, diagnostics_for_file, test_database::TestDatabase,
+    typeck::intrinsics::intrinsic_functions, Db as _, Semantics,
 };
 
 fn check_scope(fixture: &str, expected: &[&str]) {
@@ -75,6 +76,22 @@ fn check_scope_full(fixture: &str, expected: &[&str], prelude: Option<&str>) {
     assert_eq!(expected, &actual[..]);
 }
 
+fn check_diagnostics(input: &str, expected: &[&str]) {
+    let mut test_db: TestDatabase = Default::default();
+    let file = test_db.create_file(FileId(0), Dialect::Standard, None, input.to_string());
+    let actual = diagnostics_for_file(&test_db, file)
+        .map(|diagnostic| {
+            format!(
+                "{:?}..{:?} {}",
+                diagnostic.range.range.start(),
+                diagnostic.range.range.end(),
+                diagnostic.message
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(expected, &actual[..]);
+}
+
 #[test]
 fn smoke_test() {
     check_scope(
@@ -233,3 +250,280 @@ def f():
         ),
     )
 }
+
+#[test]
+fn test_mixed_indentation() {
+    check_diagnostics(
+        "\ndef f(x):\n\t  print(x)\n \t\n  \tprint(x)\n",
+        &[
+            "11..14 Indentation mixes tabs and spaces",
+            "26..29 Indentation mixes tabs and spaces",
+        ],
+    );
+}
+
+#[test]
+fn test_unreachable_stmts() {
+    check_diagnostics(
+        r#"
+def f(x):
+    if x:
+        return 1
+    else:
+        pass
+    y = 2
+    return y
+    z = 3
+    print(z)
+"#,
+        &["88..106 Code is unreachable"],
+    );
+    check_diagnostics(
+        r#"
+def f(x):
+    if x:
+        return 1
+    elif x > 1:
+        fail("bad")
+    else:
+        return 2
+    z = 3
+"#,
+        &[
+            "105..110 Code is unreachable",
+            "105..106 Local variable \"z\" is never used",
+        ],
+    );
+    check_diagnostics(
+        r#"
+for x in range(5):
+    if x:
+        break
+    continue
+    print(x)
+"#,
+        &["61..69 Code is unreachable"],
+    );
+}
+
+#[test]
+fn test_unreachable_stmts_shadowed_fail() {
+    check_diagnostics(
+        r#"
+def fail(msg):
+    pass
+
+def f():
+    fail("bad")
+    return 1
+"#,
+        &[
+            "5..9 \"fail\" shadows a builtin",
+            "10..13 Parameter \"msg\" is never used",
+        ],
+    );
+}
+
+#[test]
+fn test_param_default_later_param() {
+    check_diagnostics(
+        r#"
+def f(a, b = c, c = 1):
+    return a + b + c
+
+def g():
+    def h(x, y = x):
+        return x + y
+    return h
+"#,
+        &["14..15 Default value cannot refer to parameter \"c\", which is declared later"],
+    );
+}
+
+#[test]
+fn test_param_default_later_param_resolved() {
+    check_diagnostics(
+        r#"
+c = 5
+
+def f(a, b = c, c = 1):
+    return a + b + c
+"#,
+        &[],
+    );
+}
+
+#[test]
+fn test_missing_returns() {
+    check_diagnostics(
+        r#"
+def f(x):
+    if x:
+        return 1
+
+def g(x):
+    if x:
+        return 1
+    return
+
+def h(x):
+    if x:
+        return 1
+    fail("bad")
+
+def i(x):
+    if x:
+        return None
+
+def j(x):
+    for y in x:
+        return y
+"#,
+        &[
+            "5..6 Not all code paths return a value",
+            "43..44 Not all code paths return a value",
+            "187..188 Not all code paths return a value",
+        ],
+    );
+}
+
+#[test]
+fn test_constant_conditions() {
+    check_diagnostics(
+        r#"
+if True:
+    pass
+elif None:
+    pass
+if x:
+    pass
+"#,
+        &[
+            "4..8 Condition always evaluates to True",
+            "24..28 Condition always evaluates to False",
+        ],
+    );
+}
+
+#[test]
+fn test_builtin_shadowing() {
+    check_diagnostics(
+        r#"
+list = []
+def print():
+    pass
+for len in range(3):
+    pass
+def f(x):
+    str = x
+    ok = [dict for dict in x]
+"#,
+        &[
+            "1..5 \"list\" shadows a builtin",
+            "15..20 \"print\" shadows a builtin",
+            "37..40 \"len\" shadows a builtin",
+            "77..80 \"str\" shadows a builtin",
+            "104..108 \"dict\" shadows a builtin",
+            "77..80 Local variable \"str\" is never used",
+            "89..91 Local variable \"ok\" is never used",
+        ],
+    );
+}
+
+#[test]
+fn test_unused_locals() {
+    check_diagnostics(
+        r#"
+def f(a, _b, *args, **kwargs):
+    x = 1
+    y = 2
+    y += 1
+    z = 3
+    for i, _ in enumerate(args):
+        print(i)
+    for _unused in kwargs:
+        pass
+    def g(c):
+        return x + c
+    return g
+"#,
+        &[
+            "7..8 Parameter \"a\" is never used",
+            "67..68 Local variable \"z\" is never used",
+        ],
+    );
+}
+
+#[test]
+fn test_resolve_def_name() {
+    let mut test_db: TestDatabase = Default::default();
+    let text = r#"
+load(":a.bzl", "a")
+x = 1
+def f(y):
+    return [a, x, y, f, len]
+"#;
+    let file = test_db.create_file(FileId(0), Dialect::Standard, None, text.to_string());
+    let sema = Semantics::new(&test_db);
+    let list_expr = parse(&test_db, file)
+        .syntax(&test_db)
+        .descendants()
+        .find_map(ast::ListExpr::cast)
+        .unwrap();
+
+    // Each declaration is shown by its first line.
+    let actual = list_expr
+        .elements()
+        .filter_map(|expr| ast::NameRef::cast(expr.syntax().clone()))
+        .map(|name_ref| {
+            let decl = sema
+                .resolve_def_name(file, &name_ref)
+                .map(|range| text[range.range].lines().next().unwrap().to_string());
+            format!("{}: {:?}", name_ref.syntax().text(), decl)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actual,
+        &[
+            "a: Some(\"\\\"a\\\"\")",
+            "x: Some(\"x\")",
+            "y: Some(\"y\")",
+            "f: Some(\"def f(y):\")",
+            "len: None",
+        ]
+    );
+}
+
+#[test]
+fn test_signature_string() {
+    let mut test_db: TestDatabase = Default::default();
+    let text = r#"
+def f(x, y = 1, *args, **kwargs):
+    pass
+
+[f, len, 1]
+"#;
+    let file = test_db.create_file(FileId(0), Dialect::Standard, None, text.to_string());
+    let sema = Semantics::new(&test_db);
+    let list_expr = parse(&test_db, file)
+        .syntax(&test_db)
+        .descendants()
+        .find_map(ast::ListExpr::cast)
+        .unwrap();
+
+    let actual = list_expr
+        .elements()
+        .map(|expr| {
+            sema.type_of_expr(file, &expr)
+                .and_then(|ty| ty.signature_string(&test_db))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actual,
+        &[
+            Some("(x, y: int, *args, **kwargs) -> Unknown".to_string()),
+            Some("(x0: Any) -> int".to_string()),
+            None,
+        ]
+    );
+}
@@ -5,7 +5,7 @@ use crate::{
     module,
     typeck::{
         builtins::BuiltinFunctionParam, intrinsics::IntrinsicFunctionParam, resolve_type_ref,
-        Protocol, RuleKind, Tuple, TyKind, TypeRef,
+        Protocol, RuleKind, Struct, Tuple, TyKind, TypeRef,
     },
     Db, Name, Ty, Type,
 };
@@ -127,6 +127,16 @@ impl DisplayWithDb for TyKind {
                 value_ty.fmt(db, f)?;
                 return f.write_char(']');
             }
+            TyKind::Set(ty) => {
+                f.write_str("set[")?;
+                ty.fmt(db, f)?;
+                return f.write_char(']');
+            }
+            TyKind::Depset(ty) => {
+                f.write_str("depset[")?;
+                ty.fmt(db, f)?;
+                return f.write_char(']');
+            }
             TyKind::Range => "range",
             TyKind::Function(func) => {
                 let module = module(db, func.file(db));
@@ -269,6 +279,18 @@ impl DisplayWithDb for TyKind {
             TyKind::Union(tys) => {
                 return delimited(db, f, tys, " | ");
             }
+            TyKind::Struct(Some(Struct::Inline { fields, .. })) => {
+                f.write_str("struct(")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str(name.as_str())?;
+                    f.write_str(" = ")?;
+                    ty.clone().normalize().fmt(db, f)?;
+                }
+                return f.write_char(')');
+            }
             TyKind::Struct(_) => "struct",
             TyKind::Attribute(_) => "Attribute",
             TyKind::Rule(rule) => match rule.kind {
@@ -54,12 +54,15 @@ pub struct Jar(
     typeck::builtins::common_attributes_query,
     typeck::intrinsics::Intrinsics,
     typeck::intrinsics::IntrinsicClass,
+    typeck::intrinsics::IntrinsicClassInstance,
     typeck::intrinsics::IntrinsicFieldTypes,
     typeck::intrinsics::IntrinsicFunction,
     typeck::intrinsics::IntrinsicFunctions,
     typeck::intrinsics::intrinsic_types,
     typeck::intrinsics::intrinsic_field_types,
     typeck::intrinsics::intrinsic_functions,
+    typeck::intrinsics::SubstitutedFieldTypes,
+    typeck::intrinsics::substituted_field_types,
 );
 
 pub trait Db: salsa::DbWithJar<Jar> + starpls_common::Db {
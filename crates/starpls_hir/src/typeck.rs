@@ -17,20 +17,21 @@ use starpls_syntax::ast::SyntaxNodePtr;
 use crate::{
     def::{
         codeflow::FlowNodeId, scope::ExecutionScopeId, ExprId, Function, LiteralString, LoadItemId,
-        LoadStmt, Param as HirDefParam, ParamId,
+        LoadStmt, Module, ModuleSourceMap, Param as HirDefParam, ParamId,
     },
-    module, source_map,
+    display::DisplayWithDb,
+    lower, module, source_map,
     typeck::{
         builtins::{
             builtin_types, common_attributes_query, BuiltinFunction, BuiltinFunctionParam,
             BuiltinProvider, BuiltinType,
         },
         intrinsics::{
-            intrinsic_field_types, intrinsic_types, IntrinsicClass, IntrinsicFunction,
-            IntrinsicFunctionParam, Intrinsics,
+            intrinsic_types, substituted_field_types, IntrinsicClass, IntrinsicClassInstance,
+            IntrinsicFunction, IntrinsicFunctionParam, Intrinsics,
         },
     },
-    Db, Name,
+    Db, ModuleInfo, Name, Type,
 };
 
 mod call;
@@ -113,6 +114,15 @@ impl Cancelled {
             },
         }
     }
+
+    /// Resumes unwinding with this cancellation, e.g. after it was caught to release a lock.
+    /// The unwind can be caught again with `catch`.
+    pub fn throw(self) -> ! {
+        match self {
+            Cancelled::Salsa(cancelled) => panic::resume_unwind(Box::new(cancelled)),
+            Cancelled::Typecheck(cancelled) => cancelled.throw(),
+        }
+    }
 }
 
 impl std::fmt::Display for Cancelled {
@@ -144,10 +154,21 @@ impl std::error::Error for Cancelled {}
 
 #[derive(Clone, Debug, Default)]
 pub struct InferenceOptions {
+    /// Allow comparing values with `is` and `is not`. Neither Bazel nor the Starlark
+    /// specification support them, but some Starlark implementations do.
+    pub allow_identity_operators: bool,
+    /// Disallow merging dicts with `|`. Both Bazel and the Starlark specification support it,
+    /// but some older Starlark implementations don't.
+    pub disallow_dict_union: bool,
     pub infer_ctx_attributes: bool,
     pub use_code_flow_analysis: bool,
+    /// The maximum depth of nested expressions to infer types for, to avoid overflowing the stack
+    /// on deeply nested, e.g. generated, code. Defaults to `DEFAULT_MAX_INFERENCE_DEPTH`.
+    pub max_inference_depth: Option<usize>,
 }
 
+pub(crate) const DEFAULT_MAX_INFERENCE_DEPTH: usize = 512;
+
 #[derive(Default)]
 struct SharedState {
     cancelled: AtomicCell<bool>,
@@ -379,10 +400,10 @@ impl Ty {
             })
         });
 
-        // Build the substitution for lists and dicts.
+        // Build the substitution for lists, dicts, sets, and depsets.
         let mut subst = Substitution::new();
         match self.kind() {
-            TyKind::List(ty) => {
+            TyKind::List(ty) | TyKind::Set(ty) | TyKind::Depset(ty) => {
                 subst.args.push(ty.clone());
             }
             TyKind::Dict(key_ty, value_ty, _) => {
@@ -392,10 +413,11 @@ impl Ty {
             _ => {}
         }
 
-        let types = intrinsic_field_types(db, class)
+        let instance = IntrinsicClassInstance::new(db, class, subst);
+        let types = substituted_field_types(db, instance)
             .field_tys(db)
             .iter()
-            .map(move |binders| binders.substitute(&subst));
+            .cloned();
         fields.zip(types)
     }
 
@@ -590,6 +612,31 @@ impl Ty {
         TyKind::Dict(key_ty, value_ty, known_keys).intern()
     }
 
+    pub(crate) fn set(ty: Ty) -> Ty {
+        TyKind::Set(ty).intern()
+    }
+
+    pub(crate) fn depset(ty: Ty) -> Ty {
+        TyKind::Depset(ty).intern()
+    }
+
+    /// Narrows a union containing `None` to either `None` or its remaining members, e.g. when
+    /// the union is guarded by `if x != None:`. Other types are returned as is.
+    pub(crate) fn narrow_none(self, is_none: bool) -> Ty {
+        if !self.is_union() || !self.contains(&Ty::none()) {
+            return self;
+        }
+        if is_none {
+            Ty::none()
+        } else {
+            Ty::union(
+                self.union_members()
+                    .filter(|ty| !matches!(ty.kind(), TyKind::None))
+                    .cloned(),
+            )
+        }
+    }
+
     pub(crate) fn union(tys: impl Iterator<Item = Ty>) -> Ty {
         let mut unique_tys = smallvec![];
 
@@ -641,6 +688,50 @@ impl Ty {
         self.kind() == &TyKind::Unbound
     }
 
+    /// Returns whether this type is a union of two or more types.
+    ///
+    /// ```ignore
+    /// assert!(Ty::union([Ty::int(), Ty::none()].into_iter()).is_union());
+    /// // A union of a single distinct type collapses to that type.
+    /// assert!(!Ty::union([Ty::int(), Ty::int()].into_iter()).is_union());
+    /// ```
+    pub fn is_union(&self) -> bool {
+        matches!(self.kind(), TyKind::Union(_))
+    }
+
+    /// Returns the members of this type if it is a union. Any other type is treated as a union
+    /// with itself as the single member.
+    ///
+    /// ```ignore
+    /// let ty = Ty::union([Ty::int(), Ty::none()].into_iter());
+    /// assert_eq!(ty.union_members().cloned().collect::<Vec<_>>(), [Ty::int(), Ty::none()]);
+    /// assert_eq!(Ty::int().union_members().cloned().collect::<Vec<_>>(), [Ty::int()]);
+    /// ```
+    pub fn union_members(&self) -> impl Iterator<Item = &Ty> {
+        match self.kind() {
+            TyKind::Union(tys) => tys.iter(),
+            _ => std::slice::from_ref(self).iter(),
+        }
+    }
+
+    /// Returns whether every member of `other` is also a member of this type. Literal types are
+    /// compared as their non-literal counterparts, since unions store them that way.
+    ///
+    /// ```ignore
+    /// let ty = Ty::union([Ty::int(), Ty::none()].into_iter());
+    /// assert!(ty.contains(&Ty::none()));
+    /// assert!(ty.contains(&ty));
+    /// assert!(ty.contains(&TyKind::Int(Some(1)).intern()));
+    /// assert!(!ty.contains(&Ty::string()));
+    /// ```
+    pub fn contains(&self, other: &Ty) -> bool {
+        other.union_members().all(|other| {
+            let other = other.clone().normalize();
+            self.union_members()
+                .any(|ty| Ty::eq(&ty.clone().normalize(), &other))
+        })
+    }
+
     pub(crate) fn is_possibly_unbound(&self) -> bool {
         match self.kind() {
             TyKind::Union(tys) => tys.iter().any(|ty| ty.is_possibly_unbound()),
@@ -649,9 +740,29 @@ impl Ty {
         }
     }
 
+    /// Returns the type of the elements produced by iterating over this type, if it is iterable.
+    pub(crate) fn iterable_element_ty(&self) -> Option<Ty> {
+        Some(match self.kind() {
+            TyKind::List(ty)
+            | TyKind::Set(ty)
+            | TyKind::Depset(ty)
+            | TyKind::Tuple(Tuple::Variable(ty))
+            | TyKind::Dict(ty, _, _)
+            | TyKind::Protocol(Protocol::Iterable(ty) | Protocol::Sequence(ty)) => ty.clone(),
+            TyKind::Tuple(Tuple::Simple(tys)) => Ty::union(tys.iter().cloned()),
+            TyKind::Range | TyKind::BytesElems => Ty::int(),
+            TyKind::StringElems => Ty::string(),
+            TyKind::Any => Ty::any(),
+            TyKind::Unknown => Ty::unknown(),
+            _ => return None,
+        })
+    }
+
     pub(crate) fn substitute(&self, args: &[Ty]) -> Ty {
         match self.kind() {
             TyKind::List(ty) => Ty::list(ty.substitute(args)),
+            TyKind::Set(ty) => Ty::set(ty.substitute(args)),
+            TyKind::Depset(ty) => Ty::depset(ty.substitute(args)),
             TyKind::Tuple(tup) => match tup {
                 Tuple::Simple(tys) => TyKind::Tuple(Tuple::Simple(
                     tys.iter().map(|ty| ty.substitute(args)).collect(),
@@ -667,7 +778,14 @@ impl Ty {
             TyKind::IntrinsicFunction(data, subst) => {
                 TyKind::IntrinsicFunction(*data, subst.substitute(args)).intern()
             }
-            TyKind::BoundVar(index) => args[*index].clone(),
+            TyKind::Protocol(Protocol::Iterable(ty)) => {
+                TyKind::Protocol(Protocol::Iterable(ty.substitute(args))).intern()
+            }
+            TyKind::Protocol(Protocol::Sequence(ty)) => {
+                TyKind::Protocol(Protocol::Sequence(ty.substitute(args))).intern()
+            }
+            TyKind::Union(tys) => Ty::union(tys.iter().map(|ty| ty.substitute(args))),
+            TyKind::BoundVar(index) => args.get(*index).cloned().unwrap_or_else(Ty::unknown),
             _ => self.clone(),
         }
     }
@@ -702,7 +820,7 @@ impl Ty {
         }
     }
 
-    fn normalize(self) -> Ty {
+    pub(crate) fn normalize(self) -> Ty {
         match self.kind() {
             TyKind::Bool(_) => Ty::bool(),
             TyKind::Int(_) => Ty::int(),
@@ -969,6 +1087,49 @@ impl Param {
             })
         })
     }
+
+    /// Renders this parameter as it appears in a signature, e.g. `*args: int` or `x: string = ""`.
+    /// `ty` is the type returned alongside this parameter by `Type::params`.
+    pub fn label(&self, db: &dyn Db, ty: &Type) -> String {
+        let mut s = String::new();
+        if self.is_args_list(db) {
+            s.push('*');
+        } else if self.is_kwargs_dict(db) {
+            s.push_str("**");
+        }
+
+        match self.name(db) {
+            Some(name) if !name.is_missing() && !name.as_str().is_empty() => {
+                s.push_str(name.as_str());
+
+                let ty = if self.is_args_list(db) {
+                    ty.variable_tuple_element_ty()
+                } else if self.is_kwargs_dict(db) {
+                    ty.dict_value_ty()
+                } else {
+                    Some(ty.clone())
+                };
+
+                match ty {
+                    Some(ty) if !ty.is_unknown() => {
+                        let _ = write!(&mut s, ": {}", ty.display(db));
+                    }
+                    _ => {}
+                }
+
+                match self.default_value(db) {
+                    Some(default_value) if !default_value.is_empty() => {
+                        s.push_str(" = ");
+                        s.push_str(&default_value);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        s
+    }
 }
 
 enum ProviderParams<I1, I2> {
@@ -1224,6 +1385,12 @@ pub(crate) enum TyKind {
     Tuple(Tuple),
     /// A mapping of keys to values.
     Dict(Ty, Ty, Option<Arc<DictLiteral>>),
+    /// An unordered collection of unique elements, e.g. `set[string]`.
+    /// Not part of the Bazel dialect, but supported by some Starlark implementations.
+    Set(Ty),
+    /// A Bazel depset (https://bazel.build/rules/lib/builtins/depset).
+    /// Use this instead of the `depset` type defined in `builtin.pb`.
+    Depset(Ty),
     /// An iterable and indexable sequence of numbers. Obtained from
     /// the `range()` function.
     Range,
@@ -1387,6 +1554,8 @@ impl Rule {
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct CustomProvider {
+    /// The `provider()` call that declares this provider.
+    pub(crate) call_expr: InFile<ExprId>,
     pub(crate) name: Option<Name>,
     pub(crate) doc: Option<LiteralString>,
     pub(crate) fields: Option<(Option<InFile<ExprId>>, Box<[ProviderField]>)>,
@@ -1452,6 +1621,8 @@ impl TyKind {
             TyKind::Bytes => intrinsics.bytes_base_class(db),
             TyKind::List(_) => intrinsics.list_base_class(db),
             TyKind::Dict(_, _, _) => intrinsics.dict_base_class(db),
+            TyKind::Set(_) => intrinsics.set_base_class(db),
+            TyKind::Depset(_) => intrinsics.depset_base_class(db),
             _ => return None,
         })
     }
@@ -1505,7 +1676,16 @@ impl Substitution {
     }
 
     pub(crate) fn substitute(&self, args: &[Ty]) -> Self {
-        let args = self.args.iter().map(|ty| ty.substitute(args)).collect();
+        // Bound variables past the provided arguments, e.g. ones introduced by a method rather
+        // than by its class, are left free so that they can be inferred from call arguments.
+        let args = self
+            .args
+            .iter()
+            .map(|ty| match ty.kind() {
+                TyKind::BoundVar(index) if *index >= args.len() => ty.clone(),
+                _ => ty.substitute(args),
+            })
+            .collect();
         Self { args }
     }
 }
@@ -1538,7 +1718,15 @@ impl GlobalCtxt {
     }
 
     pub fn cancel(&self) -> CancelGuard {
-        CancelGuard::new(self)
+        CancelGuard::new(self, None)
+    }
+
+    /// Like `cancel`, but only discards the inference results for the given files and the
+    /// files that depend on them once the guard is dropped. This should only be used when
+    /// the contents of existing files change, since adding files can affect how `load`
+    /// statements are resolved.
+    pub fn cancel_for_files(&self, files: Vec<File>) -> CancelGuard {
+        CancelGuard::new(self, Some(files))
     }
 
     pub fn with_tcx<F, T>(&self, db: &dyn Db, mut f: F) -> T
@@ -1546,13 +1734,27 @@ impl GlobalCtxt {
         F: FnMut(&mut TyCtxt) -> T + std::panic::UnwindSafe,
     {
         let mut cx = self.cx.lock();
+
+        // If inference is cancelled, this flag is left set to indicate that the inference
+        // results might be incomplete. This includes cancellations that `f` catches itself,
+        // which are detected by the pending change still being in progress.
+        let incomplete = std::mem::replace(&mut cx.incomplete, true);
         let mut tcx = TyCtxt {
             db,
             cx: &mut cx,
             intrinsics: intrinsic_types(db),
             shared_state: Arc::clone(&self.shared_state),
+            lowered: None,
+            pending_assigns: Vec::new(),
+            skipped_assigns: 0,
+            provisional_loops: Vec::new(),
+            inference_depth: 0,
         };
-        f(&mut tcx)
+        let res = f(&mut tcx);
+        if !self.shared_state.cancelled.load() {
+            cx.incomplete = incomplete;
+        }
+        res
     }
 }
 
@@ -1574,7 +1776,7 @@ pub(crate) struct CodeFlowCacheKey {
 #[allow(unused)]
 #[derive(Default)]
 pub(crate) struct InferenceCtxt {
-    pub(crate) diagnostics: Vec<Diagnostic>,
+    pub(crate) diagnostics: FxHashMap<File, Vec<Diagnostic>>,
     pub(crate) resolved_load_stmts: FxHashMap<FileLoadStmt, Option<File>>,
     pub(crate) load_resolution_stack: Vec<(File, LoadStmt)>,
     pub(crate) type_of_expr: FxHashMap<FileExprId, Ty>,
@@ -1582,17 +1784,72 @@ pub(crate) struct InferenceCtxt {
     pub(crate) type_of_param: FxHashMap<FileParamId, Ty>,
     pub(crate) source_assign_done: FxHashSet<FileExprId>,
     pub(crate) flow_node_type_cache: FxHashMap<CodeFlowCacheKey, Option<Ty>>,
+    incomplete: bool,
+}
+
+impl InferenceCtxt {
+    /// Discards the inference results for the given files, as well as for all files that
+    /// directly or indirectly load them. Results for all other files are kept.
+    fn invalidate_files(&mut self, files: Vec<File>) {
+        // Partial results from cancelled inference can't be attributed to a specific file, so
+        // everything is discarded.
+        if self.incomplete {
+            *self = Default::default();
+            return;
+        }
+
+        let mut dependents: FxHashMap<File, Vec<File>> = FxHashMap::default();
+        for (load_stmt, loaded_file) in self.resolved_load_stmts.iter() {
+            if let Some(loaded_file) = loaded_file {
+                dependents
+                    .entry(*loaded_file)
+                    .or_default()
+                    .push(load_stmt.file);
+            }
+        }
+
+        let mut invalidated = FxHashSet::default();
+        let mut stack = files;
+        while let Some(file) = stack.pop() {
+            if invalidated.insert(file) {
+                stack.extend(dependents.get(&file).into_iter().flatten().copied());
+            }
+        }
+
+        self.diagnostics
+            .retain(|file, _| !invalidated.contains(file));
+        self.resolved_load_stmts
+            .retain(|load_stmt, _| !invalidated.contains(&load_stmt.file));
+        self.load_resolution_stack.clear();
+        self.type_of_expr
+            .retain(|expr, _| !invalidated.contains(&expr.file));
+        self.type_of_load_item
+            .retain(|load_item, _| !invalidated.contains(&load_item.file));
+        self.type_of_param
+            .retain(|param, _| !invalidated.contains(&param.file));
+        self.source_assign_done
+            .retain(|expr| !invalidated.contains(&expr.file));
+        self.flow_node_type_cache
+            .retain(|key, _| !invalidated.contains(&key.file));
+    }
 }
 
 pub struct CancelGuard<'a> {
     gcx: &'a GlobalCtxt,
     cx: &'a Mutex<InferenceCtxt>,
+    /// The files affected by the pending change. If `None`, all inference results are
+    /// discarded when the guard is dropped.
+    changed_files: Option<Vec<File>>,
 }
 
 impl<'a> CancelGuard<'a> {
-    fn new(gcx: &'a GlobalCtxt) -> Self {
+    fn new(gcx: &'a GlobalCtxt, changed_files: Option<Vec<File>>) -> Self {
         gcx.shared_state.cancelled.store(true);
-        Self { gcx, cx: &gcx.cx }
+        Self {
+            gcx,
+            cx: &gcx.cx,
+            changed_files,
+        }
     }
 }
 
@@ -1600,7 +1857,10 @@ impl Drop for CancelGuard<'_> {
     fn drop(&mut self) {
         let mut cx = self.cx.lock();
         self.gcx.shared_state.cancelled.store(false);
-        *cx = Default::default();
+        match self.changed_files.take() {
+            Some(files) => cx.invalidate_files(files),
+            None => *cx = Default::default(),
+        }
     }
 }
 
@@ -1609,6 +1869,42 @@ pub struct TyCtxt<'a> {
     cx: &'a mut InferenceCtxt,
     intrinsics: Intrinsics,
     shared_state: Arc<SharedState>,
+    /// The lowered module of the file that was most recently looked up. Inference mostly visits
+    /// expressions from a single file at a time, so this saves repeatedly going through salsa.
+    lowered: Option<(File, ModuleInfo)>,
+    /// The assignments whose sources are currently being inferred. These are skipped when
+    /// they're reached again through a loop, e.g. from the `acc` in `acc = acc + [x]`.
+    pending_assigns: Vec<FileExprId>,
+    /// The number of times a pending assignment was skipped.
+    skipped_assigns: usize,
+    /// Loops whose types were inferred without one of their pending assignments. Their cached
+    /// types are discarded once there are no more pending assignments.
+    provisional_loops: Vec<CodeFlowCacheKey>,
+    /// The number of expressions whose types are currently being inferred.
+    inference_depth: usize,
+}
+
+impl<'a> TyCtxt<'a> {
+    fn lower(&mut self, file: File) -> ModuleInfo {
+        match self.lowered {
+            Some((lowered_file, info)) if lowered_file == file => info,
+            _ => {
+                let info = lower(self.db, file);
+                self.lowered = Some((file, info));
+                info
+            }
+        }
+    }
+
+    pub(crate) fn module(&mut self, file: File) -> &'a Module {
+        let db = self.db;
+        self.lower(file).module(db)
+    }
+
+    pub(crate) fn source_map(&mut self, file: File) -> &'a ModuleSourceMap {
+        let db = self.db;
+        self.lower(file).source_map(db)
+    }
 }
 
 struct TypeRefResolver<'a> {
@@ -1632,12 +1928,15 @@ impl<'a> TypeRefResolver<'a> {
                 "Any" => types.any.clone(),
                 "Unknown" | "unknown" => types.unknown.clone(),
                 "None" | "NoneType" => types.none.clone(),
+                "Never" => types.never.clone(),
                 "bool" => types.bool.clone(),
                 "int" => types.int.clone(),
                 "float" => types.float.clone(),
                 "string" => types.string.clone(),
                 "bytes" => types.bytes.clone(),
                 "list" => self.resolve_single_arg_type_constructor(args, TyKind::List),
+                "set" => self.resolve_single_arg_type_constructor(args, TyKind::Set),
+                "depset" => self.resolve_single_arg_type_constructor(args, TyKind::Depset),
                 "dict" => {
                     args.as_ref()
                         .and_then(|args| {
@@ -1746,6 +2045,10 @@ impl<'a> TypeRefResolver<'a> {
     }
 }
 
+/// Lowers a `TypeRef` into its corresponding `Ty`. Names are resolved against the intrinsic
+/// types and then the builtin types; unresolved names lower to `Unknown` and are reported through
+/// the returned list of errors. The resulting types are interned, so identical type references
+/// share the same `Ty`.
 pub(crate) fn resolve_type_ref(db: &dyn Db, type_ref: &TypeRef) -> (Ty, Vec<String>) {
     TypeRefResolver { db, errors: vec![] }.resolve_type_ref(type_ref)
 }
@@ -1756,6 +2059,37 @@ pub(crate) fn resolve_type_ref_opt(db: &dyn Db, type_ref: Option<TypeRef>) -> Ty
         .unwrap_or_else(|| Ty::unknown())
 }
 
+/// Infers the types of the bound variables in `param_ty` by matching it against `arg_ty`, the type
+/// of the corresponding argument. Bound variables that have already been bound, e.g. by the
+/// receiver of a method call, are left unchanged.
+pub(crate) fn unify_tys(param_ty: &Ty, arg_ty: &Ty, bindings: &mut [Option<Ty>]) {
+    match (param_ty.kind(), arg_ty.kind()) {
+        (TyKind::BoundVar(index), _) => {
+            if let Some(binding @ None) = bindings.get_mut(*index) {
+                *binding = Some(arg_ty.clone());
+            }
+        }
+        (TyKind::List(param_ty), TyKind::List(arg_ty))
+        | (TyKind::Set(param_ty), TyKind::Set(arg_ty))
+        | (TyKind::Depset(param_ty), TyKind::Depset(arg_ty)) => {
+            unify_tys(param_ty, arg_ty, bindings)
+        }
+        (
+            TyKind::Dict(key_param_ty, value_param_ty, _),
+            TyKind::Dict(key_arg_ty, value_arg_ty, _),
+        ) => {
+            unify_tys(key_param_ty, key_arg_ty, bindings);
+            unify_tys(value_param_ty, value_arg_ty, bindings);
+        }
+        (TyKind::Protocol(Protocol::Iterable(param_ty) | Protocol::Sequence(param_ty)), _) => {
+            if let Some(arg_ty) = arg_ty.iterable_element_ty() {
+                unify_tys(param_ty, &arg_ty, bindings);
+            }
+        }
+        _ => {}
+    }
+}
+
 // TODO(withered-magic): This function currently assumes that all types are covariant in their arguments.
 pub(crate) fn assign_tys(db: &dyn Db, source: &Ty, target: &Ty) -> bool {
     use Protocol::*;
@@ -1764,13 +2098,17 @@ pub(crate) fn assign_tys(db: &dyn Db, source: &Ty, target: &Ty) -> bool {
     // are always valid to avoid confusion.
     match (source.kind(), target.kind()) {
         (TyKind::Any | TyKind::Unknown, _) | (_, TyKind::Any | TyKind::Unknown) => true,
+        // `Never` is produced by expressions that never return, e.g. `fail()`, and can be
+        // assigned to any type.
+        (TyKind::Never, _) => true,
+        (_, TyKind::Never) => false,
         (
             TyKind::List(source),
             TyKind::List(target) | TyKind::Protocol(Iterable(target) | Sequence(target)),
         )
-        | (TyKind::Protocol(Sequence(source)), TyKind::List(target)) => {
-            assign_tys(db, source, target)
-        }
+        | (TyKind::Protocol(Sequence(source)), TyKind::List(target))
+        | (TyKind::Set(source), TyKind::Set(target) | TyKind::Protocol(Iterable(target)))
+        | (TyKind::Depset(source), TyKind::Depset(target)) => assign_tys(db, source, target),
         (
             TyKind::Tuple(tuple),
             TyKind::Protocol(Iterable(target) | Sequence(target))
@@ -1795,6 +2133,9 @@ pub(crate) fn assign_tys(db: &dyn Db, source: &Ty, target: &Ty) -> bool {
         (TyKind::Dict(key_source, value_source, _), TyKind::Dict(key_target, value_target, _)) => {
             assign_tys(db, key_source, key_target) && assign_tys(db, value_source, value_target)
         }
+        (_, TyKind::Protocol(Iterable(target))) if source.iterable_element_ty().is_some() => source
+            .iterable_element_ty()
+            .map_or(false, |source| assign_tys(db, &source, target)),
         (TyKind::String(_), TyKind::BuiltinType(ty, _))
         | (TyKind::BuiltinType(ty, _), TyKind::String(_))
             if ty.name(db).as_str() == "Label" =>
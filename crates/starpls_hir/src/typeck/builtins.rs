@@ -15,9 +15,9 @@ use starpls_syntax::ast::{self, AstNode};
 use crate::{
     def::{
         resolver::{Export, Resolver},
-        Argument,
+        Argument, Expr, Literal,
     },
-    source_map,
+    module, source_map,
     typeck::{
         Attribute, AttributeKind, CustomProvider, ModuleExtension, Provider, ProviderField,
         Rule as TyRule, RuleKind, Struct, TagClass, Tuple,
@@ -161,15 +161,15 @@ impl BuiltinFunction {
                 let mut doc = None;
                 let mut has_init = false;
                 for (arg, ty) in args {
-                    if let Argument::Keyword { name, .. } = arg {
+                    if let Argument::Keyword { name, expr } = arg {
                         match name.as_str() {
                             "doc" => {
                                 if let TyKind::String(Some(s)) = ty.kind() {
                                     doc = Some(s.clone());
                                 }
                             }
-                            "fields" => {
-                                if let TyKind::Dict(_, _, Some(lit)) = ty.kind() {
+                            "fields" => match ty.kind() {
+                                TyKind::Dict(_, _, Some(lit)) => {
                                     fields = Some((
                                         lit.expr.clone(),
                                         lit.known_keys
@@ -195,7 +195,31 @@ impl BuiltinFunction {
                                             .collect(),
                                     ));
                                 }
-                            }
+                                // Fields can also be given as a list of names, without docs.
+                                TyKind::List(_) => {
+                                    let module = module(db, file);
+                                    if let Expr::List { exprs } = &module[*expr] {
+                                        fields = Some((
+                                            None,
+                                            exprs
+                                                .iter()
+                                                .filter_map(|expr| match &module[*expr] {
+                                                    Expr::Literal {
+                                                        literal: Literal::String(name),
+                                                    } if !name.value(db).is_empty() => {
+                                                        Some(ProviderField {
+                                                            name: Name::from_str(&name.value(db)),
+                                                            doc: None,
+                                                        })
+                                                    }
+                                                    _ => None,
+                                                })
+                                                .collect(),
+                                        ));
+                                    }
+                                }
+                                _ => {}
+                            },
                             "init" => {
                                 has_init = true;
                             }
@@ -243,6 +267,10 @@ impl BuiltinFunction {
                         .unwrap_or_default();
 
                     let provider = Provider::Custom(Arc::new(CustomProvider {
+                        call_expr: InFile {
+                            file,
+                            value: call_expr,
+                        },
                         name: provider_name,
                         doc,
                         fields,
@@ -266,6 +294,10 @@ impl BuiltinFunction {
                         .as_ref()
                         .map(|name| Name::from_str(name.text()));
                     TyKind::Provider(Provider::Custom(Arc::new(CustomProvider {
+                        call_expr: InFile {
+                            file,
+                            value: call_expr,
+                        },
                         name,
                         doc,
                         fields,
@@ -440,6 +472,38 @@ impl BuiltinFunction {
                 TyKind::ModuleExtension(Arc::new(ModuleExtension { doc, tag_classes }))
             }
 
+            (None, "depset") => {
+                // Infer the element type from the `direct` and `transitive` arguments. Unknown
+                // element types (e.g. from empty lists) are ignored.
+                let mut elem_tys = Vec::new();
+                for (arg, ty) in args {
+                    let elem_ty = match arg {
+                        Argument::Simple { .. } => ty.iterable_element_ty(),
+                        Argument::Keyword { name, .. } if name.as_str() == "direct" => {
+                            ty.iterable_element_ty()
+                        }
+                        Argument::Keyword { name, .. } if name.as_str() == "transitive" => {
+                            ty.iterable_element_ty().and_then(|ty| match ty.kind() {
+                                TyKind::Depset(ty) => Some(ty.clone()),
+                                _ => None,
+                            })
+                        }
+                        _ => None,
+                    };
+                    if let Some(elem_ty) = elem_ty {
+                        if !matches!(elem_ty.kind(), TyKind::Any | TyKind::Unknown) {
+                            elem_tys.push(elem_ty);
+                        }
+                    }
+                }
+
+                return Some(Ty::depset(if elem_tys.is_empty() {
+                    Ty::unknown()
+                } else {
+                    Ty::union(elem_tys.into_iter())
+                }));
+            }
+
             (None, "use_extension") => {
                 let mut next_string_arg = || {
                     args.next().and_then(|(arg, ty)| match (arg, ty.kind()) {
@@ -80,8 +80,9 @@ impl Slots {
                 }
                 Argument::UnpackedList { expr } => {
                     // Mark all unfilled positional slots as well as the "*args" slot as being
-                    // provided by this argument.
-                    for slot in self.slots.iter_mut() {
+                    // provided by this argument. If this is the active argument, the active slot
+                    // is the first slot that it fills.
+                    for (slot_index, slot) in self.slots.iter_mut().enumerate() {
                         match slot {
                             Slot::Positional {
                                 provider: provider @ SlotProvider::Missing,
@@ -90,12 +91,20 @@ impl Slots {
                                 provider: provider @ SlotProvider::Missing,
                                 positional: true,
                                 ..
-                            } => *provider = SlotProvider::ArgsList(*expr, arg_index),
+                            } => {
+                                if Some(arg_index) == active_arg {
+                                    active_slot.get_or_insert(slot_index);
+                                }
+                                *provider = SlotProvider::ArgsList(*expr, arg_index)
+                            }
                             Slot::ArgsList {
                                 providers,
                                 bare: false,
                                 ..
                             } => {
+                                if Some(arg_index) == active_arg {
+                                    active_slot.get_or_insert(slot_index);
+                                }
                                 providers.push(SlotProvider::ArgsList(*expr, arg_index));
                             }
                             _ => {}
@@ -153,13 +162,17 @@ impl Slots {
                 }
                 Argument::UnpackedDict { expr } => {
                     // Mark all keyword slots as well as the "**kwargs" slot as being provided by
-                    // this argument.
-                    for slot in self.slots.iter_mut() {
+                    // this argument. If this is the active argument, the active slot is the
+                    // "**kwargs" slot, since it can't be narrowed down to a specific keyword.
+                    for (slot_index, slot) in self.slots.iter_mut().enumerate() {
                         match slot {
                             Slot::Keyword { provider, .. } => {
                                 *provider = SlotProvider::KwargsDict(*expr, arg_index)
                             }
                             Slot::KwargsDict { providers } => {
+                                if Some(arg_index) == active_arg {
+                                    active_slot.get_or_insert(slot_index);
+                                }
                                 providers.push(SlotProvider::KwargsDict(*expr, arg_index))
                             }
                             _ => {}
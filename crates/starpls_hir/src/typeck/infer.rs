@@ -1,18 +1,22 @@
-use std::sync::Arc;
+use std::{iter, panic::AssertUnwindSafe, sync::Arc};
 
+use rustc_hash::{FxHashMap, FxHashSet};
 use starpls_common::{line_index, parse, Diagnostic, File, FileRange, InFile, Severity};
 use starpls_syntax::{
-    ast::{self, ArithOp, AstNode, AstPtr, BinaryOp, BitwiseOp, UnaryOp},
-    TextRange,
+    ast::{self, ArithOp, AstNode, AstPtr, BinaryOp, BitwiseOp, CmpOp, UnaryArithOp, UnaryOp},
+    TextRange, T,
 };
 
 use crate::{
     def::{
         codeflow::{code_flow_graph, CodeFlowGraph, FlowNode, FlowNodeId},
         resolver::{Export, Resolver},
-        scope::{ExecutionScopeId, LoadItemDef, ParameterDef, ScopeDef, ScopeHirId, VariableDef},
-        Argument, Expr, ExprId, Literal, LiteralString, LoadItem, LoadItemId, LoadStmt, Param,
-        ParamId, Stmt,
+        scope::{
+            module_scopes, ExecutionScopeId, LoadItemDef, ParameterDef, ScopeDef, ScopeHirId,
+            VariableDef,
+        },
+        Argument, CompClause, Expr, ExprId, Literal, LiteralString, LoadItem, LoadItemId, LoadStmt,
+        Param, ParamId, Stmt,
     },
     display::DisplayWithDb,
     module, source_map,
@@ -20,29 +24,71 @@ use crate::{
         assign_tys,
         builtins::builtin_types,
         call::{Slot, SlotProvider, Slots},
-        intrinsics::{IntrinsicFunctionParam, IntrinsicTypes},
-        resolve_type_ref, resolve_type_ref_opt, CodeFlowCacheKey, DictLiteral, FileExprId,
-        FileLoadItemId, FileLoadStmt, FileParamId, Protocol, Provider, RuleKind, Struct,
-        Substitution, Tuple, Ty, TyCtxt, TyData, TyKind, TypeRef, TypecheckCancelled,
+        intrinsics::{IntrinsicFunctionKind, IntrinsicFunctionParam, IntrinsicTypes},
+        resolve_type_ref, resolve_type_ref_opt, unify_tys, Cancelled, CodeFlowCacheKey,
+        DictLiteral, FileExprId, FileLoadItemId, FileLoadStmt, FileParamId, Protocol, Provider,
+        RuleKind, Struct, Substitution, Tuple, Ty, TyCtxt, TyData, TyKind, TypeRef,
+        TypecheckCancelled, DEFAULT_MAX_INFERENCE_DEPTH,
     },
     Name,
 };
 
 impl TyCtxt<'_> {
     pub fn infer_all_exprs(&mut self, file: File) {
-        for (expr, _) in module(self.db, file).exprs.iter() {
+        for (expr, _) in self.module(file).exprs.iter() {
             self.infer_expr(file, expr);
         }
     }
 
+    /// Like `infer_all_exprs`, but returns an error instead of unwinding if inference is
+    /// cancelled partway through. The diagnostics collected so far for `file`, and for every
+    /// other file that gained diagnostics while inferring it, e.g. through `load` statements,
+    /// are discarded, so that callers never publish a partial set.
+    ///
+    /// `TyCtxt` isn't `UnwindSafe`, since an unwind can leave its caches partially updated.
+    /// Catching the unwind here is still sound because a cancelled context is marked as
+    /// incomplete, and its results are thrown away once the pending change is applied. The
+    /// bookkeeping for in-progress inference is reset, so that the context can still be used
+    /// afterwards.
+    pub fn try_infer_all_exprs(&mut self, file: File) -> Result<(), Cancelled> {
+        let diagnostic_counts = self
+            .cx
+            .diagnostics
+            .iter()
+            .map(|(file, diagnostics)| (*file, diagnostics.len()))
+            .collect::<FxHashMap<_, _>>();
+        let res = Cancelled::catch(AssertUnwindSafe(|| self.infer_all_exprs(file)));
+        if res.is_err() {
+            let touched_files = self
+                .cx
+                .diagnostics
+                .iter()
+                .filter(|(file, diagnostics)| {
+                    diagnostic_counts.get(*file) != Some(&diagnostics.len())
+                })
+                .map(|(file, _)| *file)
+                .collect::<Vec<_>>();
+            for touched_file in touched_files.into_iter().chain(iter::once(file)) {
+                self.cx.diagnostics.remove(&touched_file);
+            }
+
+            self.cx.load_resolution_stack.clear();
+            self.pending_assigns.clear();
+            self.skipped_assigns = 0;
+            self.provisional_loops.clear();
+            self.inference_depth = 0;
+        }
+        res
+    }
+
     pub fn infer_all_params(&mut self, file: File) {
-        for (param, _) in module(self.db, file).params.iter() {
+        for (param, _) in self.module(file).params.iter() {
             self.infer_param(file, param);
         }
     }
 
     pub fn infer_all_load_items(&mut self, file: File) {
-        let module = module(self.db, file);
+        let module = self.module(file);
 
         for stmt in module.top_level.iter().copied() {
             if let Stmt::Load { load_stmt, items } = &module.stmts[stmt] {
@@ -59,11 +105,10 @@ impl TyCtxt<'_> {
         let module = module(self.db, file);
         self.cx
             .diagnostics
-            .iter()
+            .get(&file)
+            .into_iter()
+            .flatten()
             .filter(|diagnostic| {
-                if diagnostic.range.file_id != file.id(self.db) {
-                    return false;
-                }
                 let start_line = line_index.line_col(diagnostic.range.range.start()).line;
                 let end_line = line_index.line_col(diagnostic.range.range.end()).line;
                 (start_line..=end_line)
@@ -89,18 +134,48 @@ impl TyCtxt<'_> {
             return ty;
         }
 
+        let max_depth = self
+            .shared_state
+            .options
+            .max_inference_depth
+            .unwrap_or(DEFAULT_MAX_INFERENCE_DEPTH);
+        if self.inference_depth >= max_depth {
+            let ty = self.add_expr_diagnostic_warning_ty(
+                file,
+                expr,
+                "Expression is too deeply nested to infer its type",
+            );
+            return self.set_expr_type(file, expr, ty);
+        }
+
+        self.inference_depth += 1;
+        let ty = self.infer_expr_inner(file, expr);
+        self.inference_depth -= 1;
+        ty
+    }
+
+    fn infer_expr_inner(&mut self, file: File, expr: ExprId) -> Ty {
         self.unwind_if_cancelled();
 
         let db = self.db;
-        let curr_module = module(db, file);
+        let curr_module = self.module(file);
         let ty = match &curr_module[expr] {
             Expr::Name { name } => {
                 let ty = self
                     .infer_name_expr(file, expr, name)
                     .unwrap_or_else(|| self.unbound_ty());
 
-                // Report unbound and possibly unbound variables.
-                if ty.is_unbound() {
+                // A reference in a loop body may have been inferred again while inferring its own
+                // loop, e.g. the `acc` in `acc = acc + [x]`. The earlier result is kept, since
+                // other expressions, and their diagnostics, were already inferred from it.
+                if let Some(ty) = self.cx.type_of_expr.get(&FileExprId::new(file, expr)) {
+                    return ty.clone();
+                }
+
+                // Report unbound and possibly unbound variables. Function bodies may refer to
+                // names that are declared later on in the module scope, since these are only
+                // looked up once the function is called.
+                if ty.is_unbound() && !self.is_module_global_in_function_body(file, expr, name) {
                     self.add_expr_diagnostic_error(
                         file,
                         expr,
@@ -121,7 +196,23 @@ impl TyCtxt<'_> {
                 TyKind::List(self.get_common_type(file, exprs.iter().cloned(), self.unknown_ty()))
                     .intern()
             }
-            Expr::ListComp { expr, .. } => TyKind::List(self.infer_expr(file, *expr)).intern(),
+            Expr::ListComp { expr, comp_clauses } => {
+                // Bind the loop variables before inferring the element expression. Literal
+                // element types are widened, just as with list literals.
+                self.infer_comp_clauses(file, comp_clauses);
+                Ty::list(self.infer_expr(file, *expr).normalize())
+            }
+            Expr::DictComp {
+                entry,
+                comp_clauses,
+            } => {
+                self.infer_comp_clauses(file, comp_clauses);
+                Ty::dict(
+                    self.infer_expr(file, entry.key).normalize(),
+                    self.infer_expr(file, entry.value).normalize(),
+                    None,
+                )
+            }
             Expr::Dict { entries } => {
                 let key_ty = match entries.len() {
                     0 => Ty::unknown(),
@@ -134,11 +225,14 @@ impl TyCtxt<'_> {
                     }
                     _ => Ty::union(entries.iter().map(|entry| self.infer_expr(file, entry.key))),
                 };
-                let value_ty = self.get_common_type(
-                    file,
-                    entries.iter().map(|entry| entry.value),
-                    self.unknown_ty(),
-                );
+                let value_ty = match entries.len() {
+                    0 => Ty::unknown(),
+                    _ => Ty::union(
+                        entries
+                            .iter()
+                            .map(|entry| self.infer_expr(file, entry.value)),
+                    ),
+                };
 
                 // Determine the list of known string keys from the entries.
                 let known_keys = entries
@@ -161,11 +255,6 @@ impl TyCtxt<'_> {
                 )
                 .intern()
             }
-            Expr::DictComp { entry, .. } => {
-                let key_ty = self.infer_expr(file, entry.key);
-                let value_ty = self.infer_expr(file, entry.value);
-                TyKind::Dict(key_ty, value_ty, None).intern()
-            }
             Expr::Literal { literal } => match literal {
                 Literal::Int(x) => TyKind::Int(i64::try_from(*x).ok()).intern(),
                 Literal::Float => self.float_ty(),
@@ -190,62 +279,42 @@ impl TyCtxt<'_> {
                 field,
             } => {
                 let receiver_ty = self.infer_expr(file, *dot_expr);
-                match receiver_ty.kind() {
-                    TyKind::Unknown
-                    | TyKind::Unbound
-                    | TyKind::Any
-                    | TyKind::ProviderInstance(Provider::Custom(_)) => self.unknown_ty(),
-                    _ => {
-                        if field.is_missing() {
-                            return self.unknown_ty();
-                        }
-
-                        receiver_ty
-                            .fields(db)
-                            .and_then(|mut fields| {
-                                fields.find_map(|(f, ty)| {
-                                    if &f.name(db) == field {
-                                        Some(ty.clone())
-                                    } else {
-                                        None
-                                    }
-                                })
-                            })
-                            .unwrap_or_else(|| {
-                                match receiver_ty.kind() {
-                                    TyKind::Struct(Some(Struct::FieldSignature { ty })) => {
-                                        return ty.clone()
-                                    }
-                                    TyKind::Struct(Some(Struct::Attributes { attrs })) => {
-                                        return attrs
-                                            .iter()
-                                            .find_map(|(name, attr)| {
-                                                if name == field {
-                                                    Some(attr.resolved_ty())
-                                                } else {
-                                                    None
-                                                }
-                                            })
-                                            .unwrap_or_else(|| self.unknown_ty());
-                                    }
-                                    TyKind::Struct(_) | TyKind::ProviderInstance(_) => {
-                                        return self.unknown_ty()
-                                    }
-                                    _ => {}
-                                }
+                if field.is_missing() {
+                    return self.set_expr_type(file, expr, self.unknown_ty());
+                }
 
-                                self.add_expr_diagnostic_warning_ty(
-                                    file,
-                                    expr,
-                                    format!(
-                                        "Cannot access field \"{}\" for type \"{}\"",
-                                        field.as_str(),
-                                        receiver_ty.display(db)
-                                    ),
-                                )
-                            })
+                // For a union containing `None`, e.g. an optional parameter, the access is checked
+                // against the other members, but the receiver might be `None` at runtime.
+                let non_none_ty = receiver_ty.clone().narrow_none(false);
+                if receiver_ty.is_union()
+                    && receiver_ty.contains(&Ty::none())
+                    && !matches!(non_none_ty.kind(), TyKind::Unknown | TyKind::Any)
+                {
+                    if let Some(ty) = self.field_ty(&non_none_ty, field) {
+                        let message = match self.receiver_display(file, *dot_expr) {
+                            Some(receiver) => format!("\"{}\" may be None here", receiver),
+                            None => format!(
+                                "Cannot access field \"{}\" of a value that may be None",
+                                field.as_str()
+                            ),
+                        };
+                        self.add_expr_diagnostic_warning(file, *dot_expr, message);
+                        return self.set_expr_type(file, expr, ty);
                     }
                 }
+
+                match self.field_ty(&receiver_ty, field) {
+                    Some(ty) => ty,
+                    None => self.add_expr_diagnostic_warning_ty(
+                        file,
+                        expr,
+                        format!(
+                            "Cannot access field \"{}\" for type \"{}\"",
+                            field.as_str(),
+                            receiver_ty.display(db)
+                        ),
+                    ),
+                }
             }
             Expr::Index { lhs, index } => {
                 let lhs_ty = self.infer_expr(file, *lhs);
@@ -259,18 +328,22 @@ impl TyCtxt<'_> {
                     TyKind::Tuple(Tuple::Variable(ty)) => (&int_ty, ty, "tuple"),
                     TyKind::Tuple(Tuple::Simple(tys)) => {
                         let return_ty = match index_ty.kind() {
-                            TyKind::Int(Some(x)) => match tys.get(*x as usize) {
-                                Some(ty) => ty.clone(),
-                                None => self.add_expr_diagnostic_error_ty(
-                                    file,
-                                    expr,
-                                    format!(
-                                        "Index {} is out of range for type {}",
-                                        x,
-                                        lhs_ty.display(db)
+                            TyKind::Int(Some(x)) => {
+                                // Negative indices count from the end of the tuple.
+                                let index = if *x < 0 { *x + tys.len() as i64 } else { *x };
+                                match usize::try_from(index).ok().and_then(|index| tys.get(index)) {
+                                    Some(ty) => ty.clone(),
+                                    None => self.add_expr_diagnostic_error_ty(
+                                        file,
+                                        expr,
+                                        format!(
+                                            "Index {} is out of range for type {}",
+                                            x,
+                                            lhs_ty.display(db)
+                                        ),
                                     ),
-                                ),
-                            },
+                                }
+                            }
                             TyKind::Int(None) => Ty::union(tys.iter().cloned()),
                             _ => self.add_expr_diagnostic_error_ty(
                                 file,
@@ -286,7 +359,28 @@ impl TyCtxt<'_> {
                     }
                     TyKind::List(ty) => (&int_ty, ty, "list"),
                     TyKind::Dict(key_ty, value_ty, _) => (key_ty, value_ty, "dict"),
-                    TyKind::String(_) => (&int_ty, &string_ty, "string"),
+                    TyKind::String(value) => {
+                        // Indices into string literals can be checked against their length.
+                        // Negative indices count from the end of the string. The length is
+                        // measured in bytes, since Bazel and starlark-go index strings by their
+                        // UTF-8 bytes rather than by characters.
+                        if let (Some(value), TyKind::Int(Some(x))) = (value, index_ty.kind()) {
+                            let len = value.value(db).len() as i64;
+                            if *x >= len || *x < -len {
+                                let return_ty = self.add_expr_diagnostic_error_ty(
+                                    file,
+                                    expr,
+                                    format!(
+                                        "Index {} is out of range for type \"{}\"",
+                                        x,
+                                        lhs_ty.display(db)
+                                    ),
+                                );
+                                return self.set_expr_type(file, expr, return_ty);
+                            }
+                        }
+                        (&int_ty, &string_ty, "string")
+                    }
                     TyKind::Bytes => (&int_ty, &int_ty, "bytes"),
                     TyKind::Range => (&int_ty, &int_ty, "range"),
                     kind => {
@@ -323,7 +417,13 @@ impl TyCtxt<'_> {
                     }
                 };
 
-                if assign_tys(db, &index_ty, target) {
+                // Integers may also be used to look up float keys, since equal numbers are
+                // considered to be the same key.
+                if assign_tys(db, &index_ty, target)
+                    || (name == "dict"
+                        && matches!(index_ty.kind(), TyKind::Int(_))
+                        && assign_tys(db, &self.float_ty(), target))
+                {
                     value.clone()
                 } else {
                     self.add_expr_diagnostic_warning_ty(
@@ -453,20 +553,7 @@ impl TyCtxt<'_> {
                             }
                         }
 
-                        // Emit diagnostic for missing parameters.
-                        if !missing_params.is_empty() {
-                            let mut message = String::from("Argument missing for parameter(s) ");
-                            for (i, name) in missing_params.into_iter().enumerate() {
-                                if i > 0 {
-                                    message.push_str(", ");
-                                }
-                                message.push('"');
-                                message.push_str(name.as_str());
-                                message.push('"');
-                            }
-
-                            self.add_expr_diagnostic_error(file, expr, message);
-                        }
+                        self.add_missing_params_diagnostics(file, expr, missing_params);
 
                         func.ret_type_ref(db)
                             .map(|type_ref| resolve_type_ref(db, &type_ref).0)
@@ -476,20 +563,78 @@ impl TyCtxt<'_> {
                         let params = func.params(db);
                         let mut slots: Slots = params[..].into();
                         let errors = slots.assign_args(&args, None).0;
+                        let slots = slots.into_inner();
 
                         for error in errors {
                             self.add_expr_diagnostic_error(file, error.expr, error.message);
                         }
 
-                        // Validate argument types.
-                        for (param, slot) in params.iter().zip(slots.into_inner()) {
-                            let param_ty = match param {
-                                IntrinsicFunctionParam::Positional { ty, .. }
-                                | IntrinsicFunctionParam::Keyword { ty, .. }
-                                | IntrinsicFunctionParam::ArgsList { ty } => ty.clone(),
-                                IntrinsicFunctionParam::KwargsDict => self.any_ty(),
+                        let param_ty = |param: &IntrinsicFunctionParam| match param {
+                            IntrinsicFunctionParam::Positional { ty, .. }
+                            | IntrinsicFunctionParam::Keyword { ty, .. }
+                            | IntrinsicFunctionParam::ArgsList { ty } => ty.clone(),
+                            IntrinsicFunctionParam::KwargsDict => Ty::any(),
+                        };
+
+                        // Bound variables that weren't already fixed by the substitution, e.g. by the
+                        // receiver of a method call, are inferred from the types of the arguments.
+                        let mut bindings = (0..func.num_vars(db))
+                            .map(|index| {
+                                subst
+                                    .args
+                                    .get(index)
+                                    .filter(|ty| !matches!(ty.kind(), TyKind::BoundVar(_)))
+                                    .cloned()
+                            })
+                            .collect::<Vec<_>>();
+                        for (param, slot) in params.iter().zip(slots.iter()) {
+                            let providers = match slot {
+                                Slot::Positional { provider } | Slot::Keyword { provider, .. } => {
+                                    std::slice::from_ref(provider)
+                                }
+                                Slot::ArgsList { providers, .. }
+                                | Slot::KwargsDict { providers } => &providers[..],
+                            };
+                            for provider in providers {
+                                if let SlotProvider::Single(_, index) = provider {
+                                    unify_tys(&param_ty(param), &arg_tys[*index], &mut bindings);
+                                }
                             }
-                            .substitute(&subst.args);
+                        }
+                        // An omitted optional argument is implicitly `None`, e.g. the default of
+                        // `dict.get`.
+                        for (param, slot) in params.iter().zip(slots.iter()) {
+                            if let (
+                                IntrinsicFunctionParam::Positional { ty, optional: true },
+                                Slot::Positional {
+                                    provider: SlotProvider::Missing,
+                                },
+                            ) = (param, slot)
+                            {
+                                if let TyKind::BoundVar(index) = ty.kind() {
+                                    if let Some(binding @ None) = bindings.get_mut(*index) {
+                                        *binding = Some(self.none_ty());
+                                    }
+                                }
+                            }
+                        }
+                        let subst_args = bindings
+                            .into_iter()
+                            .map(|binding| binding.unwrap_or_else(|| self.unknown_ty()))
+                            .collect::<Vec<_>>();
+
+                        let is_setdefault_without_default = func.kind(db)
+                            == IntrinsicFunctionKind::DictSetdefault
+                            && matches!(
+                                slots.get(1),
+                                Some(Slot::Positional {
+                                    provider: SlotProvider::Missing
+                                })
+                            );
+
+                        // Validate argument types.
+                        for (param, slot) in params.iter().zip(slots) {
+                            let param_ty = param_ty(param).substitute(&subst_args);
 
                             let mut validate_provider = |provider| match provider {
                                 SlotProvider::Missing => {
@@ -524,8 +669,18 @@ impl TyCtxt<'_> {
                             }
                         }
 
-                        func.maybe_unique_ret_type(db, args_with_ty)
-                            .unwrap_or_else(|| func.ret_ty(db).substitute(&subst.args))
+                        if func.kind(db) == IntrinsicFunctionKind::Getattr {
+                            self.infer_getattr_call(file, args, &arg_tys)
+                        } else if is_setdefault_without_default {
+                            // Without a default, a missing key is inserted with the value `None`.
+                            Ty::union(
+                                [func.ret_ty(db).substitute(&subst_args), self.none_ty()]
+                                    .into_iter(),
+                            )
+                        } else {
+                            func.maybe_unique_ret_type(db, args_with_ty)
+                                .unwrap_or_else(|| func.ret_ty(db).substitute(&subst_args))
+                        }
                     }
                     TyKind::BuiltinFunction(func) => {
                         let params = func.params(db);
@@ -570,19 +725,25 @@ impl TyCtxt<'_> {
                             }
                         }
 
-                        // Emit diagnostic for missing parameters.
-                        if !missing_params.is_empty() {
-                            let mut message = String::from("Argument missing for parameter(s) ");
-                            for (i, name) in missing_params.into_iter().enumerate() {
-                                if i > 0 {
-                                    message.push_str(", ");
+                        self.add_missing_params_diagnostics(file, expr, missing_params);
+
+                        // Repeating a field in a call to `struct` silently overrides the earlier value.
+                        if func.parent_type(db).is_none() && func.name(db).as_str() == "struct" {
+                            let mut seen_fields = FxHashSet::default();
+                            for arg in args.iter() {
+                                if let Argument::Keyword { name, expr } = arg {
+                                    if !seen_fields.insert(name) {
+                                        self.add_expr_diagnostic_warning(
+                                            file,
+                                            *expr,
+                                            format!(
+                                                "Field \"{}\" is assigned more than once",
+                                                name.as_str()
+                                            ),
+                                        );
+                                    }
                                 }
-                                message.push('"');
-                                message.push_str(name.as_str());
-                                message.push('"');
                             }
-
-                            self.add_expr_diagnostic_error(file, expr, message);
                         }
 
                         func.maybe_unique_ret_type(self, file, expr, args_with_ty)
@@ -688,9 +849,11 @@ impl TyCtxt<'_> {
                         self.none_ty()
                     }
                     TyKind::Unknown | TyKind::Any | TyKind::Unbound => self.unknown_ty(),
+                    // Every other kind of type is not callable. The diagnostic is reported on the
+                    // callee rather than the entire call expression.
                     _ => self.add_expr_diagnostic_warning_ty(
                         file,
-                        expr,
+                        *callee,
                         format!("Type \"{}\" is not callable", callee_ty.display(db).alt()),
                     ),
                 }
@@ -707,7 +870,21 @@ impl TyCtxt<'_> {
                 test,
                 else_expr,
             } => {
-                self.infer_expr(file, *test);
+                // Functions are always truthy, so using one as a condition is most likely
+                // a missing call.
+                let test_ty = self.infer_expr(file, *test);
+                if matches!(
+                    test_ty.kind(),
+                    TyKind::Function(_)
+                        | TyKind::BuiltinFunction(_)
+                        | TyKind::IntrinsicFunction(_, _)
+                ) {
+                    self.add_expr_diagnostic_warning(
+                        file,
+                        *test,
+                        "Condition is a function and is always true; did you mean to call it?",
+                    );
+                }
                 Ty::union(
                     [
                         self.infer_expr(file, *if_expr),
@@ -744,7 +921,7 @@ impl TyCtxt<'_> {
                     TyKind::Bytes => self.bytes_ty(),
                     TyKind::Tuple(Tuple::Simple(tys)) => Ty::union(tys.iter().cloned()),
                     TyKind::Tuple(Tuple::Variable(ty)) => Ty::list(ty.clone()),
-                    TyKind::Range => Ty::list(self.int_ty()),
+                    TyKind::Range => self.range_ty(),
                     TyKind::List(ty) | TyKind::Protocol(Protocol::Sequence(ty)) => {
                         Ty::list(ty.clone())
                     }
@@ -762,6 +939,22 @@ impl TyCtxt<'_> {
         self.set_expr_type(file, expr, ty)
     }
 
+    fn infer_comp_clauses(&mut self, file: File, comp_clauses: &[CompClause]) {
+        for comp_clause in comp_clauses.iter() {
+            match comp_clause {
+                CompClause::For { iterable, targets } => {
+                    self.infer_expr(file, *iterable);
+                    for target in targets.iter() {
+                        self.infer_expr(file, *target);
+                    }
+                }
+                CompClause::If { test } => {
+                    self.infer_expr(file, *test);
+                }
+            }
+        }
+    }
+
     fn infer_unary_expr(&mut self, file: File, parent: ExprId, expr: ExprId, op: UnaryOp) -> Ty {
         let ty = self.infer_expr(file, expr);
         match self.check_unary_expr(&ty, op) {
@@ -780,6 +973,11 @@ impl TyCtxt<'_> {
 
     fn check_unary_expr(&self, ty: &Ty, op: UnaryOp) -> Result<Ty, ()> {
         Ok(match (op, ty.kind()) {
+            // Integer literals with a sign stay literals, e.g. so that `x[-1]` can be checked.
+            (UnaryOp::Arith(UnaryArithOp::Add), TyKind::Int(Some(_))) => ty.clone(),
+            (UnaryOp::Arith(UnaryArithOp::Sub), TyKind::Int(Some(x))) => {
+                TyKind::Int(x.checked_neg()).intern()
+            }
             (UnaryOp::Arith(_) | UnaryOp::Inv, TyKind::Int(_)) => self.int_ty(),
             (UnaryOp::Arith(_), TyKind::Float) => self.float_ty(),
             (UnaryOp::Not, _) => self.bool_ty(),
@@ -804,8 +1002,18 @@ impl TyCtxt<'_> {
         op: BinaryOp,
     ) -> Ty {
         let db = self.db;
+        let lhs_expr = lhs;
+        let rhs_expr = rhs;
         let lhs = self.infer_expr(file, lhs);
         let rhs = self.infer_expr(file, rhs);
+
+        // Identity comparisons evaluate to a `bool` regardless of their operands, so they're
+        // checked before bailing out on unknown operand types.
+        if let BinaryOp::IdentityOp(_) = op {
+            self.check_identity_comparison(file, parent, &lhs, &rhs, op);
+            return self.bool_ty();
+        }
+
         let lhs_kind = lhs.kind();
         let rhs_kind = rhs.kind();
         let mut unknown = || {
@@ -841,7 +1049,10 @@ impl TyCtxt<'_> {
                 }
                 (TyKind::String(_), TyKind::String(_), ArithOp::Add)
                 | (TyKind::String(_), _, ArithOp::Mod) => self.string_ty(), // concatenation, string interpolcation
-                (TyKind::Bytes, TyKind::Bytes, ArithOp::Add) => self.bytes_ty(), // concatenation
+                // concatenation, bytes interpolation
+                (TyKind::Bytes, TyKind::Bytes, ArithOp::Add) | (TyKind::Bytes, _, ArithOp::Mod) => {
+                    self.bytes_ty()
+                }
                 (
                     TyKind::List(ty1)
                     | TyKind::Protocol(Protocol::Sequence(ty1) | Protocol::Iterable(ty1)),
@@ -860,25 +1071,59 @@ impl TyCtxt<'_> {
                 | (TyKind::Float, TyKind::Float, _) => self.float_ty(),
                 _ => unknown(),
             },
+            BinaryOp::Bitwise(op @ (BitwiseOp::Shl | BitwiseOp::Shr)) => {
+                match (lhs_kind, rhs_kind) {
+                    (TyKind::Int(_), TyKind::Int(_)) => self.int_ty(),
+                    _ => self.add_expr_diagnostic_warning_ty(
+                        file,
+                        parent,
+                        format!(
+                            "Operator \"{}\" requires \"int\" operands, got \"{}\" and \"{}\"",
+                            op,
+                            lhs_kind.display(db),
+                            rhs_kind.display(db)
+                        ),
+                    ),
+                }
+            }
             BinaryOp::Bitwise(op) => match (lhs_kind, rhs_kind, op) {
                 (TyKind::Int(_), TyKind::Int(_), _) => self.int_ty(),
                 (
                     TyKind::Dict(lhs_key_ty, lhs_value_ty, _),
                     TyKind::Dict(rhs_key_ty, rhs_value_ty, _),
                     BitwiseOp::Or,
-                ) => Ty::dict(
+                ) if !self.shared_state.options.disallow_dict_union => Ty::dict(
                     Ty::union([lhs_key_ty.clone(), rhs_key_ty.clone()].into_iter()),
                     Ty::union([lhs_value_ty.clone(), rhs_value_ty.clone()].into_iter()),
                     None,
                 ),
-                _ => unknown(),
+                (TyKind::Dict(_, _, _), TyKind::Dict(_, _, _), BitwiseOp::Or) => self
+                    .add_expr_diagnostic_warning_ty(
+                        file,
+                        parent,
+                        "Merging dicts with \"|\" is not supported in this dialect",
+                    ),
+                _ => self.add_expr_diagnostic_warning_ty(
+                    file,
+                    parent,
+                    format!(
+                        "Operator \"{}\" requires \"int\" operands, got \"{}\" and \"{}\"",
+                        op,
+                        lhs_kind.display(db),
+                        rhs_kind.display(db)
+                    ),
+                ),
             },
+            // `and` and `or` evaluate to one of their operands. Operands that never return, e.g.
+            // calls to `fail()`, drop out of the resulting union.
+            BinaryOp::Logic(_) => Ty::union([lhs.clone(), rhs.clone()].into_iter()),
             BinaryOp::MemberOp(_) => {
                 if !matches!(
                     rhs_kind,
                     TyKind::List(_)
                         | TyKind::Tuple(_)
                         | TyKind::Dict(_, _, _)
+                        | TyKind::Set(_)
                         | TyKind::String(_)
                         | TyKind::Bytes
                         | TyKind::Protocol(Protocol::Sequence(_))
@@ -894,13 +1139,266 @@ impl TyCtxt<'_> {
                             rhs_kind.display(db)
                         ),
                     );
+                    return self.bool_ty();
+                }
+
+                // Membership in a dict tests its keys, and membership in a string tests its
+                // substrings.
+                let (expected_ty, description) = match rhs_kind {
+                    TyKind::Dict(key_ty, _, _) => (key_ty.clone(), "a key"),
+                    TyKind::String(_) => (self.string_ty(), "a substring"),
+                    TyKind::Bytes => (
+                        Ty::union([self.bytes_ty(), self.int_ty()].into_iter()),
+                        "an element",
+                    ),
+                    _ => match rhs.iterable_element_ty() {
+                        Some(ty) => (ty, "an element"),
+                        None => return self.bool_ty(),
+                    },
+                };
+                if !assign_tys(db, &lhs, &expected_ty) {
+                    self.add_expr_diagnostic_warning(
+                        file,
+                        parent,
+                        format!(
+                            "Type \"{}\" can never be {} of type \"{}\"",
+                            lhs.display(db),
+                            description,
+                            rhs.display(db)
+                        ),
+                    );
                 }
                 self.bool_ty()
             }
+            BinaryOp::Cmp(op) => {
+                self.check_constant_comparison(file, parent, lhs_expr, rhs_expr, op);
+                self.bool_ty()
+            }
             _ => self.bool_ty(),
         }
     }
 
+    /// Reports `is` and `is not` comparisons in dialects that don't support them. Otherwise,
+    /// since identity is only meaningful for singletons, a comparison is reported unless one of
+    /// its operands may be `None` or a `bool`.
+    fn check_identity_comparison(
+        &mut self,
+        file: File,
+        parent: ExprId,
+        lhs: &Ty,
+        rhs: &Ty,
+        op: BinaryOp,
+    ) {
+        if !self.shared_state.options.allow_identity_operators {
+            self.add_expr_diagnostic_error(
+                file,
+                parent,
+                format!("Operator \"{}\" is not supported in this dialect", op),
+            );
+            return;
+        }
+
+        let may_be_singleton = |ty: &Ty| {
+            ty.union_members().any(|ty| {
+                matches!(
+                    ty.kind(),
+                    TyKind::None | TyKind::Bool(_) | TyKind::Any | TyKind::Unknown
+                )
+            })
+        };
+        if !may_be_singleton(lhs) && !may_be_singleton(rhs) {
+            self.add_expr_diagnostic_warning(
+                file,
+                parent,
+                format!(
+                    "Operator \"{}\" requires a \"None\" or \"bool\" operand, got \"{}\" and \"{}\"",
+                    op,
+                    lhs.display(self.db),
+                    rhs.display(self.db)
+                ),
+            );
+        }
+    }
+
+    /// Reports comparisons whose result is known ahead of time, i.e. comparisons of an
+    /// expression with itself (`x == x`) and comparisons of `len()` against negative bounds
+    /// (`len(x) < 0`). Self-comparisons of values that may be `Any`, `Unknown` or `float` are
+    /// never reported, since values of these types may be NaN, for which `x == x` is False.
+    fn check_constant_comparison(
+        &mut self,
+        file: File,
+        parent: ExprId,
+        lhs: ExprId,
+        rhs: ExprId,
+        op: CmpOp,
+    ) {
+        let value = if self.is_same_expr(file, lhs, rhs) {
+            let ty = self.infer_expr(file, lhs);
+            if ty.union_members().any(|ty| {
+                matches!(
+                    ty.kind(),
+                    TyKind::Any | TyKind::Unknown | TyKind::Unbound | TyKind::Float
+                )
+            }) {
+                return;
+            }
+            matches!(op, CmpOp::Eq | CmpOp::Le | CmpOp::Ge)
+        } else if self.is_len_call(file, lhs)
+            && matches!(self.infer_expr(file, rhs).kind(), TyKind::Int(Some(0)))
+        {
+            match op {
+                CmpOp::Lt => false,
+                CmpOp::Ge => true,
+                _ => return,
+            }
+        } else {
+            return;
+        };
+
+        self.add_expr_diagnostic_warning(
+            file,
+            parent,
+            format!(
+                "Expression always evaluates to {}",
+                if value { "True" } else { "False" }
+            ),
+        );
+    }
+
+    /// Determines whether two expressions are structurally identical names or field accesses,
+    /// e.g. `x` and `x`, or `x.y` and `x.y`.
+    fn is_same_expr(&self, file: File, lhs: ExprId, rhs: ExprId) -> bool {
+        let module = module(self.db, file);
+        match (&module[lhs], &module[rhs]) {
+            (Expr::Name { name: lhs_name }, Expr::Name { name: rhs_name }) => lhs_name == rhs_name,
+            (
+                Expr::Dot {
+                    expr: lhs_expr,
+                    field: lhs_field,
+                },
+                Expr::Dot {
+                    expr: rhs_expr,
+                    field: rhs_field,
+                },
+            ) => lhs_field == rhs_field && self.is_same_expr(file, *lhs_expr, *rhs_expr),
+            (Expr::Paren { expr: lhs_expr }, _) => self.is_same_expr(file, *lhs_expr, rhs),
+            (_, Expr::Paren { expr: rhs_expr }) => self.is_same_expr(file, lhs, *rhs_expr),
+            _ => false,
+        }
+    }
+
+    /// Returns the type of the given field of a value, or `None` if the value is known not to
+    /// have the field.
+    fn field_ty(&mut self, receiver_ty: &Ty, field: &Name) -> Option<Ty> {
+        let db = self.db;
+        match receiver_ty.kind() {
+            TyKind::Unknown | TyKind::Unbound | TyKind::Any => return Some(self.unknown_ty()),
+            // Without declared fields, a custom provider's instances can have any field.
+            TyKind::ProviderInstance(Provider::Custom(provider)) if provider.fields.is_none() => {
+                return Some(self.unknown_ty())
+            }
+            _ => {}
+        }
+
+        if let Some(ty) = receiver_ty.fields(db).and_then(|mut fields| {
+            fields.find_map(|(f, ty)| {
+                if &f.name(db) == field {
+                    Some(ty.clone())
+                } else {
+                    None
+                }
+            })
+        }) {
+            return Some(ty);
+        }
+
+        match receiver_ty.kind() {
+            TyKind::Struct(Some(Struct::FieldSignature { ty })) => Some(ty.clone()),
+            TyKind::Struct(Some(Struct::Attributes { attrs })) => Some(
+                attrs
+                    .iter()
+                    .find_map(|(name, attr)| {
+                        if name == field {
+                            Some(attr.resolved_ty())
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap_or_else(|| self.unknown_ty()),
+            ),
+            // The fields of inline structs are known, unless some of them were passed by
+            // unpacking a dict, e.g. `struct(**kwargs)`.
+            TyKind::Struct(Some(Struct::Inline { call_expr, .. }))
+                if !self.has_unpacked_dict_arg(*call_expr) =>
+            {
+                None
+            }
+            TyKind::ProviderInstance(Provider::Custom(_)) => None,
+            TyKind::Struct(_) | TyKind::ProviderInstance(_) => Some(self.unknown_ty()),
+            _ => None,
+        }
+    }
+
+    /// Renders a receiver made up of names and field accesses, e.g. `ctx.attr.dep`, for use in
+    /// diagnostics.
+    fn receiver_display(&mut self, file: File, expr: ExprId) -> Option<String> {
+        match &self.module(file)[expr] {
+            Expr::Name { name } => Some(name.to_string()),
+            Expr::Dot { expr, field } if !field.is_missing() => self
+                .receiver_display(file, *expr)
+                .map(|receiver| format!("{}.{}", receiver, field.as_str())),
+            _ => None,
+        }
+    }
+
+    /// Infers the type of a `getattr(x, name[, default])` call. When `name` is a string literal,
+    /// this is the type of the field on `x`, or the type of `default` if `x` doesn't have the
+    /// field.
+    fn infer_getattr_call(&mut self, file: File, args: &[Argument], arg_tys: &[Ty]) -> Ty {
+        let db = self.db;
+        let positional_args = args
+            .iter()
+            .zip(arg_tys.iter())
+            .filter_map(|(arg, ty)| match arg {
+                Argument::Simple { expr } => Some((*expr, ty)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let (receiver_ty, name_expr, name_ty) = match &positional_args[..] {
+            [(_, receiver_ty), (name_expr, name_ty), ..] => (*receiver_ty, *name_expr, *name_ty),
+            _ => return self.any_ty(),
+        };
+        let field = match name_ty.kind() {
+            TyKind::String(Some(field)) => Name::from_str(&field.value(db)),
+            _ => return self.any_ty(),
+        };
+
+        match (self.field_ty(receiver_ty, &field), positional_args.get(2)) {
+            (Some(ty), _) => ty,
+            (None, Some((_, default_ty))) => (*default_ty).clone(),
+            (None, None) => self.add_expr_diagnostic_warning_ty(
+                file,
+                name_expr,
+                format!(
+                    "Cannot access field \"{}\" for type \"{}\"",
+                    field.as_str(),
+                    receiver_ty.display(db)
+                ),
+            ),
+        }
+    }
+
+    fn is_len_call(&mut self, file: File, expr: ExprId) -> bool {
+        let callee = match &self.module(file)[expr] {
+            Expr::Call { callee, .. } => *callee,
+            _ => return false,
+        };
+        match self.infer_expr(file, callee).kind() {
+            TyKind::IntrinsicFunction(func, _) => func.name(self.db).as_str() == "len",
+            _ => false,
+        }
+    }
+
     fn infer_assign(
         &mut self,
         file: File,
@@ -931,8 +1429,23 @@ impl TyCtxt<'_> {
         if self.cx.source_assign_done.contains(&key) {
             return;
         }
+        if self.pending_assigns.contains(&key) {
+            self.skipped_assigns += 1;
+            return;
+        }
+
+        self.pending_assigns.push(key);
         self.infer_source_expr_assign_inner(file, source, expected_ty);
+        self.pending_assigns.pop();
         self.cx.source_assign_done.insert(key);
+
+        // Now that every assignment has been inferred, loops that skipped any of them can be
+        // inferred again from scratch.
+        if self.pending_assigns.is_empty() {
+            for key in self.provisional_loops.drain(..) {
+                self.cx.flow_node_type_cache.remove(&key);
+            }
+        }
     }
 
     fn infer_source_expr_assign_inner(
@@ -944,7 +1457,7 @@ impl TyCtxt<'_> {
         // Find the parent assignment node. This can be either an assignment statement (`x = 0`), a `for` statement (`for x in 1, 2, 3`), or
         // a for comp clause in a list/dict comprehension (`[x + 1 for x in [1, 2, 3]]`).
         let db = self.db;
-        let source_map = source_map(db, file);
+        let source_map = self.source_map(file);
         let source_ptr = match source_map.expr_map_back.get(&source) {
             Some(ptr) => ptr,
             _ => return,
@@ -965,7 +1478,7 @@ impl TyCtxt<'_> {
         if let Some(node) = ast::AssignStmt::cast(parent.clone()) {
             let ptr = AstPtr::new(&ast::Statement::Assign(node.clone()));
             let expected_ty = expected_ty.or_else(|| {
-                match &module(db, file)[*source_map.stmt_map.get(&ptr).unwrap()] {
+                match &self.module(file)[*source_map.stmt_map.get(&ptr).unwrap()] {
                     Stmt::Assign { type_ref, .. } => type_ref.as_ref().and_then(|type_ref| {
                         let (expected_ty, errors) = resolve_type_ref(db, &type_ref.0);
                         if errors.is_empty() {
@@ -997,11 +1510,11 @@ impl TyCtxt<'_> {
 
         // Handle assignments in "for" statements and comphrehensions.
         // e.g. `for x in 1, 2, 3` or `[x*y for x in range(5) for y in range(5)]`
-        let targets = ast::ForStmt::cast(parent.clone())
-            .and_then(|stmt| stmt.targets())
-            .or_else(|| {
-                ast::CompClauseFor::cast(parent).and_then(|comp_clause| comp_clause.targets())
-            });
+        let for_stmt = ast::ForStmt::cast(parent.clone());
+        let is_for_stmt = for_stmt.is_some();
+        let targets = for_stmt.and_then(|stmt| stmt.targets()).or_else(|| {
+            ast::CompClauseFor::cast(parent).and_then(|comp_clause| comp_clause.targets())
+        });
 
         let targets = match targets {
             Some(targets) => targets
@@ -1012,35 +1525,120 @@ impl TyCtxt<'_> {
             None => return,
         };
 
+        if is_for_stmt {
+            self.check_loop_variable_shadowing(file, &targets);
+        }
+
+        // Strings and bytes are iterated like their `.elems()`, and dicts yield their keys.
         let sub_ty = match source_ty.kind() {
-            TyKind::List(ty) | TyKind::Tuple(Tuple::Variable(ty)) => ty.clone(),
-            TyKind::Tuple(Tuple::Simple(tys)) => Ty::union(tys.iter().cloned()),
-            TyKind::Dict(key_ty, _, _) => key_ty.clone(),
-            TyKind::Any => self.any_ty(),
-            TyKind::Range => self.int_ty(),
-            TyKind::StringElems => self.string_ty(),
-            TyKind::BytesElems => self.int_ty(),
-            TyKind::Unknown => self.unknown_ty(),
-            _ => {
-                self.add_expr_diagnostic_warning(
+            TyKind::String(_) => self.string_ty(),
+            TyKind::Bytes => self.int_ty(),
+            _ => match source_ty.iterable_element_ty() {
+                Some(ty) => ty,
+                None => {
+                    self.add_expr_diagnostic_warning(
+                        file,
+                        source,
+                        format!("Type \"{}\" is not iterable", source_ty.display(db)),
+                    );
+                    for expr in targets.iter() {
+                        self.assign_expr_unknown_rec(file, *expr);
+                    }
+                    return;
+                }
+            },
+        };
+        // The targets are destructured like the left-hand side of an assignment, so nested
+        // patterns like `for (a, b), c in pairs` are handled recursively.
+        match &targets[..] {
+            [target] => self.assign_expr_source_ty(file, source, *target, sub_ty, None),
+            _ => self.assign_exprs_source_ty(file, source, &targets, sub_ty),
+        }
+    }
+
+    /// Reports the variables bound by a `for` statement that shadow a binding from an enclosing
+    /// function or the module, e.g. the `x` in `for x in ...` inside a function when the module
+    /// also declares `x`. Names starting with `_` are exempt.
+    fn check_loop_variable_shadowing(&mut self, file: File, targets: &[ExprId]) {
+        let mut exprs = targets.to_vec();
+        while let Some(expr) = exprs.pop() {
+            let name = match &self.module(file)[expr] {
+                Expr::Name { name } => name.clone(),
+                Expr::List { exprs: elems } | Expr::Tuple { exprs: elems } => {
+                    exprs.extend(elems.iter().copied());
+                    continue;
+                }
+                Expr::Paren { expr } => {
+                    exprs.push(*expr);
+                    continue;
+                }
+                _ => continue,
+            };
+            if name.is_missing() || name.as_str().starts_with('_') {
+                continue;
+            }
+
+            let resolver = Resolver::new_for_expr_execution_scope(self.db, file, expr);
+            let is_shadowing =
+                resolver
+                    .execution_scope_for_expr(expr)
+                    .map_or(false, |execution_scope| {
+                        resolver.is_declared_outside_execution_scope(&name, execution_scope)
+                    });
+            if is_shadowing {
+                self.add_expr_diagnostic_hint(
                     file,
-                    source,
-                    format!("Type \"{}\" is not iterable", source_ty.display(db)),
+                    expr,
+                    format!(
+                        "Loop variable \"{}\" shadows an outer binding",
+                        name.as_str()
+                    ),
                 );
-                for expr in targets.iter() {
-                    self.assign_expr_unknown_rec(file, *expr);
-                }
-                return;
             }
+        }
+    }
+
+    /// Infers the type of a reference to an earlier parameter from a parameter's default value,
+    /// e.g. the `a` in `def f(a, b = a)`. These parameters are declared in the function's
+    /// enclosing execution scope, but are only visible from the default values that follow them.
+    fn infer_earlier_param_ref(&mut self, file: File, expr: ExprId, name: &Name) -> Option<Ty> {
+        if !module_scopes(self.db, file)
+            .scopes(self.db)
+            .is_in_param_default(expr)
+        {
+            return None;
+        }
+
+        let resolver = Resolver::new_for_expr(self.db, file, expr);
+        let (execution_scope, mut defs) = resolver.resolve_name(name)?;
+        let param = match defs.next()?.def {
+            ScopeDef::Parameter(ParameterDef {
+                func: Some(func),
+                index,
+            }) => func.params(self.db)[*index],
+            _ => return None,
         };
-        if targets.len() == 1 {
-            self.assign_expr_source_ty(file, targets[0], targets[0], sub_ty, None);
-        } else {
-            self.assign_exprs_source_ty(file, source, &targets, sub_ty);
+        let def_stmt = *self.module(file).param_to_def_stmt.get(&param)?;
+        if execution_scope == ExecutionScopeId::Def(def_stmt) {
+            return None;
         }
+        Some(self.infer_param(file, param))
+    }
+
+    fn is_module_global_in_function_body(&self, file: File, expr: ExprId, name: &Name) -> bool {
+        let resolver = Resolver::new_for_expr_execution_scope(self.db, file, expr);
+        resolver.is_in_function_body()
+            && matches!(
+                resolver.resolve_name(name),
+                Some((ExecutionScopeId::Module, _))
+            )
     }
 
     fn infer_name_expr(&mut self, file: File, expr: ExprId, name: &Name) -> Option<Ty> {
+        if let Some(ty) = self.infer_earlier_param_ref(file, expr, name) {
+            return Some(ty);
+        }
+
         let resolver = Resolver::new_for_expr_execution_scope(self.db, file, expr);
         let expr_scope = resolver.scope_for_expr(expr)?;
         let curr_execution_scope = resolver.execution_scope_for_expr(expr)?;
@@ -1105,9 +1703,11 @@ impl TyCtxt<'_> {
                         .iter()
                         .next()?
                     {
-                        ScopeDef::IntrinsicFunction(func) => {
-                            TyKind::IntrinsicFunction(*func, Substitution::new_identity(0)).intern()
-                        }
+                        ScopeDef::IntrinsicFunction(func) => TyKind::IntrinsicFunction(
+                            *func,
+                            Substitution::new_identity(func.num_vars(self.db)),
+                        )
+                        .intern(),
                         ScopeDef::BuiltinFunction(func) => TyKind::BuiltinFunction(*func).intern(),
                         ScopeDef::BuiltinVariable(type_ref) => {
                             resolve_type_ref(self.db, &type_ref).0
@@ -1210,7 +1810,7 @@ impl TyCtxt<'_> {
                         .type_of_expr
                         .get(&FileExprId::new(file, *expr))
                         .cloned()
-                        .unwrap_or_else(|| Ty::never())
+                        .unwrap_or_else(|| self.never_ty())
                 }
                 FlowNode::Branch { antecedents } => {
                     let mut antecedent_tys = Vec::with_capacity(antecedents.len());
@@ -1231,8 +1831,106 @@ impl TyCtxt<'_> {
                     }
                     Ty::union(antecedent_tys.into_iter())
                 }
-                FlowNode::Loop { .. } => Ty::unknown(), // TODO(withered-magic): Correctly handle loops.
-                FlowNode::Unreachable { .. } => Ty::never(),
+                FlowNode::Narrow {
+                    name: node_name,
+                    is_none,
+                    antecedent,
+                } => {
+                    if name != node_name {
+                        curr_node_id = *antecedent;
+                        continue;
+                    }
+
+                    match self.infer_ref_from_flow_node(
+                        cfg,
+                        file,
+                        execution_scope,
+                        name,
+                        start_ty,
+                        *antecedent,
+                    ) {
+                        Some(ty) => ty.narrow_none(*is_none),
+                        None => break 'outer None,
+                    }
+                }
+                FlowNode::Loop { antecedents } => {
+                    if let Some(res) = self.read_cached_ref_type_at_flow_node(
+                        file,
+                        execution_scope,
+                        name,
+                        curr_node_id,
+                    ) {
+                        break 'outer res;
+                    }
+
+                    // The first antecedent is the one entering the loop; the rest are back edges
+                    // from the end of the loop body and from `continue` statements. To keep
+                    // inference from widening without bound, the loop is only walked once: while
+                    // the back edges are inferred, references that reach this node again see
+                    // just the type from before the loop.
+                    let (entry, back_edges) = match antecedents.split_first() {
+                        Some(split) => split,
+                        None => break 'outer None,
+                    };
+                    let entry_ty = match self.infer_ref_from_flow_node(
+                        cfg,
+                        file,
+                        execution_scope,
+                        name,
+                        start_ty,
+                        *entry,
+                    ) {
+                        Some(entry_ty) => entry_ty,
+                        None => break 'outer None,
+                    };
+                    self.cache_ref_type_at_flow_node(
+                        file,
+                        execution_scope,
+                        name,
+                        curr_node_id,
+                        Some(entry_ty.clone()),
+                    );
+
+                    let skipped_assigns = self.skipped_assigns;
+                    let mut antecedent_tys = Some(vec![entry_ty]);
+                    for antecedent in back_edges {
+                        let antecedent_ty = self.infer_ref_from_flow_node(
+                            cfg,
+                            file,
+                            execution_scope,
+                            name,
+                            start_ty,
+                            *antecedent,
+                        );
+                        match (antecedent_tys.as_mut(), antecedent_ty) {
+                            (Some(tys), Some(antecedent_ty)) => tys.push(antecedent_ty),
+                            _ => {
+                                antecedent_tys = None;
+                                break;
+                            }
+                        }
+                    }
+                    let loop_ty = antecedent_tys.map(|tys| Ty::union(tys.into_iter()));
+                    if self.skipped_assigns != skipped_assigns {
+                        self.provisional_loops.push(CodeFlowCacheKey {
+                            file,
+                            execution_scope,
+                            name: name.clone(),
+                            flow_node: curr_node_id,
+                        });
+                    }
+                    match self.cache_ref_type_at_flow_node(
+                        file,
+                        execution_scope,
+                        name,
+                        curr_node_id,
+                        loop_ty,
+                    ) {
+                        Some(loop_ty) => loop_ty,
+                        None => break 'outer None,
+                    }
+                }
+                FlowNode::Unreachable { .. } => self.never_ty(),
             };
 
             break Some(curr_node_ty);
@@ -1287,7 +1985,7 @@ impl TyCtxt<'_> {
         source_ty: Ty,
         expected_ty: Option<Ty>,
     ) {
-        match module(self.db, file).exprs.get(expr).unwrap() {
+        match self.module(file).exprs.get(expr).unwrap() {
             Expr::Name { .. } => {
                 // If we have an expected type from a type comment, use that.
                 // We also emit any error if the source and expected types aren't compatible.
@@ -1324,7 +2022,7 @@ impl TyCtxt<'_> {
         source_ty: Ty,
     ) {
         match source_ty.kind() {
-            TyKind::List(ty) | TyKind::Tuple(Tuple::Variable(ty)) => {
+            TyKind::List(ty) | TyKind::Set(ty) | TyKind::Tuple(Tuple::Variable(ty)) => {
                 for expr in exprs.iter().copied() {
                     self.assign_expr_source_ty(file, root, expr, ty.clone(), None);
                 }
@@ -1371,10 +2069,12 @@ impl TyCtxt<'_> {
     }
 
     fn assign_expr_unknown_rec(&mut self, file: File, expr: ExprId) {
-        self.set_expr_type(file, expr, self.unknown_ty());
-        module(self.db, file)[expr].walk_child_exprs(|expr| {
-            self.assign_expr_unknown_rec(file, expr);
-        })
+        // This uses an explicit stack, since the expression might be too deeply nested to recurse.
+        let mut exprs = vec![expr];
+        while let Some(expr) = exprs.pop() {
+            self.set_expr_type(file, expr, self.unknown_ty());
+            self.module(file)[expr].walk_child_exprs(|expr| exprs.push(expr));
+        }
     }
 
     fn set_expr_type(&mut self, file: File, expr: ExprId, ty: Ty) -> Ty {
@@ -1384,23 +2084,38 @@ impl TyCtxt<'_> {
         ty
     }
 
+    fn has_unpacked_dict_arg(&self, call_expr: InFile<ExprId>) -> bool {
+        match &module(self.db, call_expr.file)[call_expr.value] {
+            Expr::Call { args, .. } => args
+                .iter()
+                .any(|arg| matches!(arg, Argument::UnpackedDict { .. })),
+            _ => false,
+        }
+    }
+
     fn get_common_type(
         &mut self,
         file: File,
         mut exprs: impl Iterator<Item = ExprId>,
         default: Ty,
     ) -> Ty {
-        let first = exprs.next();
-        first
-            .map(|first| self.infer_expr(file, first))
-            .and_then(|first_ty| {
-                let first_ty = first_ty.normalize();
-                exprs
-                    .map(|expr| self.infer_expr(file, expr))
-                    .all(|ty| Ty::eq(&ty.normalize(), &first_ty))
-                    .then_some(first_ty)
-            })
-            .unwrap_or(default)
+        let mut common_ty = match exprs.next() {
+            Some(first) => Some(self.infer_expr(file, first).normalize()),
+            None => return default,
+        };
+
+        // Every expression is still inferred so that its type is recorded, but comparisons stop
+        // once the types are known to differ.
+        for expr in exprs {
+            let ty = self.infer_expr(file, expr);
+            if common_ty
+                .as_ref()
+                .map_or(false, |common_ty| !Ty::eq(&ty.normalize(), common_ty))
+            {
+                common_ty = None;
+            }
+        }
+        common_ty.unwrap_or(default)
     }
 
     fn add_expr_diagnostic_warning<T: Into<String>>(
@@ -1412,10 +2127,47 @@ impl TyCtxt<'_> {
         self.add_expr_diagnostic_with_severity(file, expr, Severity::Warning, message)
     }
 
+    fn add_expr_diagnostic_hint<T: Into<String>>(&mut self, file: File, expr: ExprId, message: T) {
+        self.add_expr_diagnostic_with_severity(file, expr, Severity::Hint, message)
+    }
+
     fn add_expr_diagnostic_error<T: Into<String>>(&mut self, file: File, expr: ExprId, message: T) {
         self.add_expr_diagnostic_with_severity(file, expr, Severity::Error, message)
     }
 
+    /// Reports each required parameter that wasn't supplied by a call, at the call's closing
+    /// parenthesis.
+    fn add_missing_params_diagnostics(
+        &mut self,
+        file: File,
+        expr: ExprId,
+        missing_params: Vec<Name>,
+    ) {
+        if missing_params.is_empty() {
+            return;
+        }
+
+        let db = self.db;
+        let ptr = match self.source_map(file).expr_map_back.get(&expr) {
+            Some(ptr) => ptr.clone(),
+            None => return,
+        };
+        let node = ptr.to_node(&parse(db, file).syntax(db));
+        let range = node
+            .syntax()
+            .last_token()
+            .filter(|token| token.kind() == T![')'])
+            .map_or_else(|| node.syntax().text_range(), |token| token.text_range());
+        for name in missing_params {
+            self.add_diagnostic_for_range(
+                file,
+                Severity::Error,
+                range,
+                format!("Missing required argument \"{}\"", name.as_str()),
+            );
+        }
+    }
+
     fn add_expr_diagnostic_with_severity<T: Into<String>>(
         &mut self,
         file: File,
@@ -1423,7 +2175,7 @@ impl TyCtxt<'_> {
         severity: Severity,
         message: T,
     ) {
-        let range = match source_map(self.db, file).expr_map_back.get(&expr) {
+        let range = match self.source_map(file).expr_map_back.get(&expr) {
             Some(ptr) => ptr.syntax_node_ptr().text_range(),
             None => return,
         };
@@ -1457,14 +2209,18 @@ impl TyCtxt<'_> {
         range: TextRange,
         message: T,
     ) {
-        self.cx.diagnostics.push(Diagnostic {
-            message: message.into(),
-            severity,
-            range: FileRange {
-                file_id: file.id(self.db),
-                range,
-            },
-        });
+        self.cx
+            .diagnostics
+            .entry(file)
+            .or_default()
+            .push(Diagnostic {
+                message: message.into(),
+                severity,
+                range: FileRange {
+                    file_id: file.id(self.db),
+                    range,
+                },
+            });
     }
 
     pub(crate) fn infer_param(&mut self, file: File, param: ParamId) -> Ty {
@@ -1478,11 +2234,23 @@ impl TyCtxt<'_> {
             .infer_ctx_attributes
             .then(|| self.infer_param_from_rule_usage(file, param))
             .and_then(|ty| ty)
-            .unwrap_or_else(|| match &module(self.db, file)[param] {
-                Param::Simple { type_ref, .. } => type_ref
-                    .as_ref()
-                    .map(|type_ref| self.lower_param_type_ref(file, param, &type_ref))
-                    .unwrap_or_else(|| self.unknown_ty()),
+            .unwrap_or_else(|| match &self.module(file)[param] {
+                Param::Simple {
+                    type_ref, default, ..
+                } => match (type_ref, default) {
+                    (Some(type_ref), _) => self.lower_param_type_ref(file, param, &type_ref),
+                    // Without an annotation, the type of the parameter is inferred from its default
+                    // value, if any. A default of `None` is treated as unknown, since it usually
+                    // indicates that the parameter accepts some other type.
+                    (None, Some(default)) => {
+                        let ty = self.infer_expr(file, *default).normalize();
+                        match ty.kind() {
+                            TyKind::None => self.unknown_ty(),
+                            _ => ty,
+                        }
+                    }
+                    (None, None) => self.unknown_ty(),
+                },
                 Param::ArgsList { type_ref, .. } => TyKind::Tuple(Tuple::Variable(
                     type_ref
                         .as_ref()
@@ -1508,7 +2276,7 @@ impl TyCtxt<'_> {
     }
 
     fn infer_param_from_rule_usage(&mut self, file: File, param: ParamId) -> Option<Ty> {
-        let module = module(self.db, file);
+        let module = self.module(file);
         let name = match module[*module.param_to_def_stmt.get(&param)?] {
             Stmt::Def { func, .. } if func.params(self.db).len() == 1 => func.name(self.db),
             _ => return None,
@@ -1542,7 +2310,7 @@ impl TyCtxt<'_> {
         // TODO(withered-magic): This will eventually need to handle diagnostics
         // for other places that type comments can appear.
         for error in errors {
-            if let Some(ptr) = source_map(self.db, file).param_map_back.get(&param) {
+            if let Some(ptr) = self.source_map(file).param_map_back.get(&param) {
                 self.add_diagnostic_for_range(
                     file,
                     Severity::Warning,
@@ -1573,7 +2341,7 @@ impl TyCtxt<'_> {
             ptr.syntax_node_ptr().text_range()
         };
 
-        let ty = match &module(db, file).load_items[load_item] {
+        let ty = match &self.module(file).load_items[load_item] {
             LoadItem::Direct { name, load_stmt }
             | LoadItem::Aliased {
                 name, load_stmt, ..
@@ -1720,7 +2488,7 @@ impl TyCtxt<'_> {
         active_arg: usize,
     ) -> Option<usize> {
         let db = self.db;
-        match &module(db, file)[expr] {
+        match &self.module(file)[expr] {
             Expr::Call { callee, args } => {
                 // Determine args that are in invalid positions.
                 let mut saw_keyword = false;
@@ -1790,6 +2558,10 @@ impl TyCtxt<'_> {
         self.types().none.clone()
     }
 
+    fn never_ty(&self) -> Ty {
+        self.types().never.clone()
+    }
+
     fn bool_ty(&self) -> Ty {
         self.types().bool.clone()
     }
@@ -1809,4 +2581,8 @@ impl TyCtxt<'_> {
     fn bytes_ty(&self) -> Ty {
         self.types().bytes.clone()
     }
+
+    fn range_ty(&self) -> Ty {
+        self.types().range.clone()
+    }
 }
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{iter, sync::Arc};
 
 use rustc_hash::FxHashMap;
 use smallvec::smallvec;
@@ -19,6 +19,8 @@ pub(crate) struct Intrinsics {
     pub(crate) bytes_base_class: IntrinsicClass,
     pub(crate) list_base_class: IntrinsicClass,
     pub(crate) dict_base_class: IntrinsicClass,
+    pub(crate) set_base_class: IntrinsicClass,
+    pub(crate) depset_base_class: IntrinsicClass,
 }
 
 /// This serves to pre-intern common types.
@@ -28,6 +30,7 @@ pub(crate) struct IntrinsicTypes {
     pub(crate) unbound: Ty,
     pub(crate) unknown: Ty,
     pub(crate) none: Ty,
+    pub(crate) never: Ty,
     pub(crate) bool: Ty,
     pub(crate) int: Ty,
     pub(crate) float: Ty,
@@ -45,6 +48,7 @@ impl Default for IntrinsicTypes {
             unbound: TyKind::Unbound.intern(),
             unknown: Ty::unknown(),
             none: TyKind::None.intern(),
+            never: Ty::never(),
             bool: Ty::bool(),
             int: Ty::int(),
             float: TyKind::Float.intern(),
@@ -98,6 +102,36 @@ pub(crate) fn intrinsic_field_types(db: &dyn Db, class: IntrinsicClass) -> Intri
     IntrinsicFieldTypes::new(db, field_tys)
 }
 
+/// An intrinsic class with its type variables substituted, e.g. `list[int]` for `list`.
+#[salsa::interned]
+pub(crate) struct IntrinsicClassInstance {
+    pub(crate) class: IntrinsicClass,
+    #[return_ref]
+    pub(crate) subst: Substitution,
+}
+
+#[salsa::tracked]
+pub(crate) struct SubstitutedFieldTypes {
+    #[return_ref]
+    pub(crate) field_tys: Vec<Ty>,
+}
+
+/// Substitutes the field types of an intrinsic class. The results are cached, since the fields
+/// of the same type are looked up repeatedly, e.g. for completions on a `list[string]`.
+#[salsa::tracked]
+pub(crate) fn substituted_field_types(
+    db: &dyn Db,
+    instance: IntrinsicClassInstance,
+) -> SubstitutedFieldTypes {
+    let subst = instance.subst(db);
+    let field_tys = intrinsic_field_types(db, instance.class(db))
+        .field_tys(db)
+        .iter()
+        .map(|binders| binders.substitute(subst))
+        .collect();
+    SubstitutedFieldTypes::new(db, field_tys)
+}
+
 #[salsa::tracked]
 pub(crate) struct IntrinsicFunctions {
     #[return_ref]
@@ -113,19 +147,103 @@ pub(crate) struct IntrinsicFunction {
     #[return_ref]
     pub params: Vec<IntrinsicFunctionParam>,
     pub ret_ty: Ty,
-    is_dict_constructor: bool,
+    pub kind: IntrinsicFunctionKind,
+}
+
+/// Marks the intrinsic functions whose return types depend on their arguments beyond what
+/// their signatures describe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum IntrinsicFunctionKind {
+    DictConstructor,
+    DictSetdefault,
+    Getattr,
+    MinMax,
+    SetConstructor,
+    Zip,
+    Other,
 }
 
 impl IntrinsicFunction {
-    pub(crate) fn maybe_unique_ret_type<'a, I>(&'a self, db: &'a dyn Db, args: I) -> Option<Ty>
+    pub(crate) fn maybe_unique_ret_type<'a, I>(&'a self, db: &'a dyn Db, mut args: I) -> Option<Ty>
     where
         I: Iterator<Item = (&'a Argument, &'a Ty)>,
     {
-        if !self.is_dict_constructor(db) {
+        // The element type of a set is inferred from the iterable passed to `set()`, if any.
+        if self.kind(db) == IntrinsicFunctionKind::SetConstructor {
+            let elem_ty = args
+                .find_map(|(arg, ty)| match arg {
+                    Argument::Simple { .. } => ty.iterable_element_ty(),
+                    _ => None,
+                })
+                .unwrap_or_else(|| Ty::unknown());
+            return Some(Ty::set(elem_ty));
+        }
+
+        // `zip` returns tuples of the element types of its arguments. Calls with many arguments
+        // fall back to the declared return type.
+        if self.kind(db) == IntrinsicFunctionKind::Zip {
+            let mut elem_tys = smallvec![];
+            for (arg, ty) in args {
+                match arg {
+                    Argument::Simple { .. } => {
+                        elem_tys.push(ty.iterable_element_ty()?.normalize());
+                    }
+                    _ => return None,
+                }
+            }
+            if elem_tys.is_empty() || elem_tys.len() > 8 {
+                return None;
+            }
+            return Some(Ty::list(
+                TyKind::Tuple(TupleVariants::Simple(elem_tys)).intern(),
+            ));
+        }
+
+        // `min` and `max` return an element of their only argument, or else one of their
+        // arguments.
+        if self.kind(db) == IntrinsicFunctionKind::MinMax {
+            let mut positional_tys = Vec::new();
+            for (arg, ty) in args {
+                match arg {
+                    Argument::Simple { .. } => positional_tys.push(ty.clone()),
+                    Argument::Keyword { .. } => {}
+                    _ => return None,
+                }
+            }
+            return match positional_tys.as_slice() {
+                [] => None,
+                [ty] => ty.iterable_element_ty().map(|ty| ty.normalize()),
+                tys => Some(Ty::union(tys.iter().map(|ty| ty.clone().normalize()))),
+            };
+        }
+
+        if self.kind(db) != IntrinsicFunctionKind::DictConstructor {
             return None;
         }
 
+        // A positional argument is either a dict to copy, or an iterable of key/value pairs.
+        let args = args.collect::<Vec<_>>();
+        let entry_tys = args.iter().find_map(|(arg, ty)| match arg {
+            Argument::Simple { .. } => Some(match ty.kind() {
+                TyKind::Dict(key_ty, value_ty, _) => (key_ty.clone(), value_ty.clone()),
+                _ => {
+                    let pair_ty = ty.iterable_element_ty().unwrap_or_else(Ty::unknown);
+                    match pair_ty.kind() {
+                        TyKind::Tuple(TupleVariants::Simple(tys)) if tys.len() == 2 => {
+                            (tys[0].clone().normalize(), tys[1].clone().normalize())
+                        }
+                        TyKind::List(ty) | TyKind::Tuple(TupleVariants::Variable(ty)) => {
+                            (ty.clone().normalize(), ty.clone().normalize())
+                        }
+                        _ => (Ty::unknown(), Ty::unknown()),
+                    }
+                }
+            }),
+            _ => None,
+        });
+
         let known_keys = args
+            .into_iter()
             .filter_map(|(arg, ty)| match arg {
                 Argument::Keyword { name, .. } => Some((
                     LiteralString::new(db, name.as_str().to_string().into_boxed_str()),
@@ -135,25 +253,31 @@ impl IntrinsicFunction {
             })
             .collect::<Vec<_>>();
 
+        if let Some((key_ty, value_ty)) = entry_tys {
+            // Keyword arguments add string keys to the entries from the positional argument.
+            return Some(if known_keys.is_empty() {
+                Ty::dict(key_ty, value_ty, None)
+            } else {
+                Ty::dict(
+                    Ty::union([key_ty, Ty::string()].into_iter()),
+                    Ty::union(iter::once(value_ty).chain(known_keys.into_iter().map(|(_, ty)| ty))),
+                    None,
+                )
+            });
+        }
+
         let key_ty = if known_keys.is_empty() {
             Ty::unknown()
         } else {
             Ty::string()
         };
 
-        // Determine the common type of the dict values.
-        let mut iter = known_keys.iter().map(|(_, ty)| ty);
-        let value_ty = iter
-            .next()
-            .and_then(|first_ty| {
-                iter.all(|ty| match (ty.kind(), first_ty.kind()) {
-                    (TyKind::Attribute(_), TyKind::Attribute(_)) => true,
-                    _ => ty == first_ty,
-                })
-                .then_some(first_ty)
-            })
-            .cloned()
-            .unwrap_or_else(|| Ty::unknown());
+        // As with dict literals, the value type is the union of the keyword argument types.
+        let value_ty = if known_keys.is_empty() {
+            Ty::unknown()
+        } else {
+            Ty::union(known_keys.iter().map(|(_, ty)| ty.clone()))
+        };
 
         Some(
             TyKind::Dict(
@@ -306,23 +430,6 @@ x.f = y
         vec![positional(Any)],
         List(Ty::string()),
     );
-    add_function(
-        "enumerate",
-        r#"`enumerate(x)` returns a list of (index, value) pairs, each containing
-successive values of the iterable sequence xand the index of the value
-within the sequence.
-        
-The optional second parameter, `start`, specifies an integer value to
-add to each index.
-        
-```python
-enumerate(["zero", "one", "two"])               # [(0, "zero"), (1, "one"), (2, "two")]
-enumerate(["one", "two"], 1)                    # [(1, "one"), (2, "two")]
-```
-"#,
-        vec![positional(Any)],
-        List(Tuple(TupleVariants::Simple(smallvec![Ty::int(), Any.intern()])).intern()),
-    );
     add_function(
         "float",
         r#"`float(x)` interprets its argument as a floating-point number.
@@ -358,8 +465,23 @@ fail("oops")			# "fail: oops"
 fail("oops", 1, False)		# "fail: oops 1 False"
 ```
 "#,
-        vec![ArgsList { ty: Any.intern() }],
-        None,
+        // `msg` and `attr` are deprecated in Bazel, but are still accepted.
+        vec![
+            ArgsList { ty: Any.intern() },
+            Keyword {
+                name: Name::new_inline("msg"),
+                ty: Any.intern(),
+            },
+            Keyword {
+                name: Name::new_inline("attr"),
+                ty: Union(smallvec![Ty::string(), Ty::none()]).intern(),
+            },
+            Keyword {
+                name: Name::new_inline("sep"),
+                ty: Ty::string(),
+            },
+        ],
+        Never,
     );
     add_function(
         "getattr",
@@ -448,7 +570,15 @@ int("0b111", 0)    # 7
 int("0x1234")      # error (invalid base 10 number)
 ```
 "#,
-        vec![positional(Any), positional_opt(non_literal_int())],
+        vec![
+            positional(Union(smallvec![
+                Ty::string(),
+                Ty::bool(),
+                Ty::int(),
+                Float.intern(),
+            ])),
+            positional_opt(non_literal_int()),
+        ],
         non_literal_int(),
     );
     add_function(
@@ -459,16 +589,6 @@ It is a dynamic error if its argument is not a sequence."#,
         vec![positional(Any)],
         non_literal_int(),
     );
-    add_function(
-        "list",
-        r#"`list` constructs a list.
-
-`list(x)` returns a new list containing the elements of the iterable sequence x.
-    
-With no argument, `list()` returns a new empty list."#,
-        vec![positional(Any)],
-        List(Any.intern()),
-    );
     add_function(
         "max",
         r#"`max(x)` returns the greatest element in the iterable sequence x.
@@ -540,7 +660,7 @@ determined by the host application.
         vec![
             ArgsList { ty: Any.intern() },
             Keyword {
-                name: Name::new_inline("str"),
+                name: Name::new_inline("sep"),
                 ty: Ty::string(),
             },
         ],
@@ -625,54 +745,22 @@ repr("🙂"[:1])		# "\xf0" (UTF-8) or "\ud83d" (UTF-16)
         vec![positional(Any)],
         non_literal_string(),
     );
-    // TODO(withered-magic): Iterable[T] -> List[T]
-    add_function(
-        "reversed",
-        r#"`reversed(x)` returns a new list containing the elements of the iterable sequence x in reverse order.
-
-```python
-reversed(range(5))                              # [4, 3, 2, 1, 0]
-reversed({"one": 1, "two": 2}.keys())           # ["two", "one"]
-```
-"#,
-        vec![positional(Any)],
-        List(Any.intern()),
-    );
-    // TODO(withered-magic): Iterable[T] -> List[T]
     add_function(
-        "sorted",
-        r#"`sorted(x)` returns a new list containing the elements of the iterable sequence x,
-in sorted order.  The sort algorithm is stable.
+        "set",
+        r#"`set(x)` returns a new set containing the unique elements of the iterable sequence x.
 
-The optional named boolean parameter `reverse`, if true, causes `sorted` to
-return results in reverse sorted order.
-
-The optional named parameter `key` specifies a function of one
-argument to apply to obtain the value's sort key.
-The default behavior is the identity function.
-The `key` function is called exactly once per element of the sequence, in order,
-even for a single-element list.
+With no argument, `set()` returns a new empty set.
 
 ```python
-sorted([3, 1, 4, 1, 5, 9])                                 # [1, 1, 3, 4, 5, 9]
-sorted([3, 1, 4, 1, 5, 9], reverse=True)                   # [9, 5, 4, 3, 1, 1]
-
-sorted(["two", "three", "four"], key=len)                  # ["two", "four", "three"], shortest to longest
-sorted(["two", "three", "four"], key=len, reverse=True)    # ["three", "four", "two"], longest to shortest
+set()                           # set([])
+set([3, 1, 1, 2])               # set([3, 1, 2])
+set("hello".elems())            # set(["h", "e", "l", "o"])
 ```
 "#,
-        vec![
-            positional(Any),
-            Keyword {
-                name: Name::new_inline("reverse"),
-                ty: non_literal_bool().intern(),
-            },
-            Keyword {
-                name: Name::new_inline("key"),
-                ty: Any.intern(),
-            },
-        ],
-        List(Any.intern()),
+        vec![positional_opt(Protocol(typeck::Protocol::Iterable(
+            Any.intern(),
+        )))],
+        Set(Unknown.intern()),
     );
     add_function(
         "str",
@@ -696,16 +784,6 @@ UTF-K encoding of the replacement character, U+FFFD.
         vec![positional(Any)],
         non_literal_string(),
     );
-    // TODO(withered-magic): The tuple returned here can be of any size,
-    // might have to introduce a separate type.
-    add_function(
-        "tuple",
-        r#"`tuple(x)` returns a tuple containing the elements of the iterable x.
-
-With no arguments, `tuple()` returns the empty tuple."#,
-        vec![positional(Any)],
-        Any,
-    );
     add_function(
         "type",
         r#"`type(x)` returns a string describing the type of its operand.
@@ -733,10 +811,132 @@ zip(range(5))                           # [(0,), (1,), (2,), (3,), (4,)]
 zip(range(10), ["a", "b", "c"])         # [(0, "a"), (1, "b"), (2, "c")]
 ```
 "#,
-        vec![ArgsList { ty: Any.intern() }],
+        vec![ArgsList {
+            ty: Protocol(typeck::Protocol::Iterable(Any.intern())).intern(),
+        }],
         List(Any.intern()),
     );
 
+    // Generic functions, whose return types are inferred from the types of their arguments.
+    let iterable_of_bound_var = || Protocol(typeck::Protocol::Iterable(BoundVar(0).intern()));
+    functions.insert(
+        Name::new_inline("list"),
+        function(
+            db,
+            "list",
+            r#"`list` constructs a list.
+
+`list(x)` returns a new list containing the elements of the iterable sequence x.
+    
+With no argument, `list()` returns a new empty list."#,
+            vec![positional_opt(iterable_of_bound_var())],
+            1,
+            List(BoundVar(0).intern()),
+        ),
+    );
+    functions.insert(
+        Name::new_inline("enumerate"),
+        function(
+            db,
+            "enumerate",
+            r#"`enumerate(x)` returns a list of (index, value) pairs, each containing
+successive values of the iterable sequence xand the index of the value
+within the sequence.
+        
+The optional second parameter, `start`, specifies an integer value to
+add to each index.
+        
+```python
+enumerate(["zero", "one", "two"])               # [(0, "zero"), (1, "one"), (2, "two")]
+enumerate(["one", "two"], 1)                    # [(1, "one"), (2, "two")]
+```
+"#,
+            vec![
+                positional(iterable_of_bound_var()),
+                positional_opt(non_literal_int()),
+            ],
+            1,
+            List(
+                Tuple(TupleVariants::Simple(smallvec![
+                    Ty::int(),
+                    BoundVar(0).intern()
+                ]))
+                .intern(),
+            ),
+        ),
+    );
+    functions.insert(
+        Name::new_inline("reversed"),
+        function(
+            db,
+            "reversed",
+            r#"`reversed(x)` returns a new list containing the elements of the iterable sequence x in reverse order.
+
+```python
+reversed(range(5))                              # [4, 3, 2, 1, 0]
+reversed({"one": 1, "two": 2}.keys())           # ["two", "one"]
+```
+"#,
+            vec![positional(iterable_of_bound_var())],
+            1,
+            List(BoundVar(0).intern()),
+        ),
+    );
+    functions.insert(
+        Name::new_inline("sorted"),
+        function(
+            db,
+            "sorted",
+            r#"`sorted(x)` returns a new list containing the elements of the iterable sequence x,
+in sorted order.  The sort algorithm is stable.
+
+The optional named boolean parameter `reverse`, if true, causes `sorted` to
+return results in reverse sorted order.
+
+The optional named parameter `key` specifies a function of one
+argument to apply to obtain the value's sort key.
+The default behavior is the identity function.
+The `key` function is called exactly once per element of the sequence, in order,
+even for a single-element list.
+
+```python
+sorted([3, 1, 4, 1, 5, 9])                                 # [1, 1, 3, 4, 5, 9]
+sorted([3, 1, 4, 1, 5, 9], reverse=True)                   # [9, 5, 4, 3, 1, 1]
+
+sorted(["two", "three", "four"], key=len)                  # ["two", "four", "three"], shortest to longest
+sorted(["two", "three", "four"], key=len, reverse=True)    # ["three", "four", "two"], longest to shortest
+```
+"#,
+            vec![
+                positional(iterable_of_bound_var()),
+                Keyword {
+                    name: Name::new_inline("reverse"),
+                    ty: non_literal_bool().intern(),
+                },
+                Keyword {
+                    name: Name::new_inline("key"),
+                    ty: Any.intern(),
+                },
+            ],
+            1,
+            List(BoundVar(0).intern()),
+        ),
+    );
+
+    functions.insert(
+        Name::new_inline("tuple"),
+        function(
+            db,
+            "tuple",
+            r#"`tuple(x)` returns a tuple containing the elements of the iterable x.
+
+With no arguments, `tuple()` returns the empty tuple."#,
+            vec![positional_opt(iterable_of_bound_var())],
+            1,
+            Tuple(TupleVariants::Variable(BoundVar(0).intern())),
+        ),
+    );
+
     IntrinsicFunctions::new(db, functions)
 }
 
@@ -749,6 +949,8 @@ pub(crate) fn intrinsic_types(db: &dyn Db) -> Intrinsics {
         make_bytes_base_class(db),
         make_list_base_class(db),
         make_dict_base_class(db),
+        make_set_base_class(db),
+        make_depset_base_class(db),
     )
 }
 
@@ -832,7 +1034,10 @@ function reports whether any one of them is a suffix.
 ```
 "#,
                 vec![
-                    positional(non_literal_string()),
+                    positional(TyKind::Union(smallvec![
+                        Ty::string(),
+                        TyKind::Tuple(typeck::Tuple::Variable(Ty::string())).intern()
+                    ])),
                     positional_opt(non_literal_int()),
                     positional_opt(non_literal_int()),
                 ],
@@ -865,7 +1070,6 @@ If no occurrence is found, `found` returns -1.
                 non_literal_int(),
                 0,
             ),
-            // TODO(withered-magic): Handle *args and **kwargs for format().
             function_field(
                 db,
                 "format",
@@ -1044,7 +1248,9 @@ are strings.
 "a".join("ctmrn".elems())               # "catamaran"
 ```
 "#,
-                vec![positional(Any)],
+                vec![positional(Protocol(typeck::Protocol::Iterable(
+                    Ty::string(),
+                )))],
                 non_literal_string(),
                 0,
             ),
@@ -1232,7 +1438,7 @@ rightmost splits.
 ```
 "#,
                 vec![
-                    positional(non_literal_string()),
+                    positional_opt(string_or_none()),
                     positional_opt(non_literal_int()),
                 ],
                 List(Ty::string()),
@@ -1285,7 +1491,7 @@ If `maxsplit` is given and non-negative, it specifies a maximum number of splits
 ```
 "#,
                 vec![
-                    positional_opt(non_literal_string()),
+                    positional_opt(string_or_none()),
                     positional_opt(non_literal_int()),
                 ],
                 List(Ty::string()),
@@ -1635,9 +1841,9 @@ x.get("three")                          # None
 x.get("three", 0)                       # 0
 ```
 "#,
-                vec![positional(BoundVar(0)), positional_opt(BoundVar(1))],
-                BoundVar(1),
-                2,
+                vec![positional(BoundVar(0)), positional_opt(BoundVar(2))],
+                Union(smallvec![BoundVar(1).intern(), BoundVar(2).intern()]),
+                3,
             ),
             function_field(
                 db,
@@ -1718,7 +1924,7 @@ x.popitem()                             # error: empty dict
                 ])),
                 2,
             ),
-            function_field(
+            function_field_with_kind(
                 db,
                 "setdefault",
                 r#"`D.setdefault(key[, default])` returns the dictionary value corresponding to the given key.
@@ -1739,9 +1945,12 @@ x.setdefault("four")                    # None
 x                                       # {"one": 1, "two": 2, "three": 3, "four": None}
 ```
 "#,
+                // The type of the dict isn't widened by the inserted entry, so a default that
+                // doesn't match the value type is reported as an error instead.
                 vec![positional(BoundVar(0)), positional_opt(BoundVar(1))],
                 BoundVar(1),
                 2,
+                IntrinsicFunctionKind::DictSetdefault,
             ),
             function_field(
                 db,
@@ -1775,7 +1984,11 @@ x                                       # {"a": 1, "b": "2", "c": 3, "d": 4, "e"
 "#,
                 vec![
                     positional_opt(TyKind::Union(smallvec![
-                        Ty::dict(Ty::unknown(), Ty::unknown(), std::option::Option::None,),
+                        Ty::dict(
+                            BoundVar(0).intern(),
+                            BoundVar(1).intern(),
+                            std::option::Option::None,
+                        ),
                         TyKind::Protocol(typeck::Protocol::Iterable(
                             TyKind::Protocol(typeck::Protocol::Iterable(Ty::any())).intern()
                         ))
@@ -1807,6 +2020,174 @@ x.values()                              # [1, 2]
     )
 }
 
+fn make_set_base_class(db: &dyn Db) -> IntrinsicClass {
+    use TyKind::*;
+    let set_of_bound_var = || Set(BoundVar(0).intern());
+    IntrinsicClass::new(
+        db,
+        crate::Name::new_inline("set"),
+        1,
+        vec![
+            function_field(
+                db,
+                "add",
+                r#"`S.add(x)` adds the value `x` to the set S and returns `None`.
+
+`add` fails if the set is frozen or has active iterators.
+
+```python
+x = set([1, 2])
+x.add(3)                                # None
+x                                       # set([1, 2, 3])
+```
+"#,
+                vec![positional(BoundVar(0))],
+                None,
+                1,
+            ),
+            function_field(
+                db,
+                "clear",
+                r#"`S.clear()` removes all the elements of the set S and returns `None`.
+It fails if the set is frozen or if there are active iterators.
+"#,
+                vec![],
+                None,
+                1,
+            ),
+            function_field(
+                db,
+                "difference",
+                r#"`S.difference(x)` returns a new set containing the elements of S that are not in the iterable `x`.
+
+```python
+set([1, 2, 3]).difference([2])          # set([1, 3])
+```
+"#,
+                vec![positional(Any)],
+                set_of_bound_var(),
+                1,
+            ),
+            function_field(
+                db,
+                "discard",
+                r#"`S.discard(x)` removes the value `x` from the set S if it is present, and returns `None`.
+"#,
+                vec![positional(BoundVar(0))],
+                None,
+                1,
+            ),
+            function_field(
+                db,
+                "intersection",
+                r#"`S.intersection(x)` returns a new set containing the elements of S that are also in the iterable `x`.
+
+```python
+set([1, 2, 3]).intersection([2, 4])     # set([2])
+```
+"#,
+                vec![positional(Any)],
+                set_of_bound_var(),
+                1,
+            ),
+            function_field(
+                db,
+                "issubset",
+                r#"`S.issubset(x)` returns `True` if every element of S is in the iterable `x`.
+"#,
+                vec![positional(Any)],
+                non_literal_bool(),
+                1,
+            ),
+            function_field(
+                db,
+                "issuperset",
+                r#"`S.issuperset(x)` returns `True` if every element of the iterable `x` is in S.
+"#,
+                vec![positional(Any)],
+                non_literal_bool(),
+                1,
+            ),
+            function_field(
+                db,
+                "pop",
+                r#"`S.pop()` removes and returns the first element of the set S.
+
+`pop` fails if the set is empty, frozen, or has active iterators.
+"#,
+                vec![],
+                BoundVar(0),
+                1,
+            ),
+            function_field(
+                db,
+                "remove",
+                r#"`S.remove(x)` removes the value `x` from the set S, and returns `None`.
+
+`remove` fails if the set does not contain `x`, is frozen, or has active iterators.
+"#,
+                vec![positional(BoundVar(0))],
+                None,
+                1,
+            ),
+            function_field(
+                db,
+                "symmetric_difference",
+                r#"`S.symmetric_difference(x)` returns a new set containing the elements that are in either S or the iterable `x`, but not both.
+"#,
+                vec![positional(Any)],
+                set_of_bound_var(),
+                1,
+            ),
+            function_field(
+                db,
+                "union",
+                r#"`S.union(x)` returns a new set containing the elements of S and the elements of the iterable `x`.
+
+```python
+set([1, 2]).union([2, 3])               # set([1, 2, 3])
+```
+"#,
+                vec![positional(Any)],
+                set_of_bound_var(),
+                1,
+            ),
+            function_field(
+                db,
+                "update",
+                r#"`S.update(x)` adds the elements of the iterable `x` to the set S, and returns `None`.
+
+`update` fails if the set is frozen or has active iterators.
+"#,
+                vec![positional(Any)],
+                None,
+                1,
+            ),
+        ],
+    )
+}
+
+fn make_depset_base_class(db: &dyn Db) -> IntrinsicClass {
+    use TyKind::*;
+    IntrinsicClass::new(
+        db,
+        crate::Name::new_inline("depset"),
+        1,
+        vec![function_field(
+            db,
+            "to_list",
+            r#"Returns a list of the elements, without duplicates, in the depset's traversal order.
+Note that order is unspecified (but deterministic) for elements that were added more than once
+to the depset. Order is also unspecified for `"default"`-ordered depsets, and for elements of
+child depsets whose order differs from that of the parent depset. The list is a copy; modifying
+it has no effect on the depset and vice versa."#,
+            vec![],
+            List(BoundVar(0).intern()),
+            1,
+        )],
+    )
+}
+
 fn function(
     db: &dyn Db,
     name: &'static str,
@@ -1814,6 +2195,26 @@ fn function(
     params: Vec<IntrinsicFunctionParam>,
     num_vars: usize,
     ret_ty: TyKind,
+) -> IntrinsicFunction {
+    let kind = match name {
+        "dict" => IntrinsicFunctionKind::DictConstructor,
+        "getattr" => IntrinsicFunctionKind::Getattr,
+        "min" | "max" => IntrinsicFunctionKind::MinMax,
+        "set" => IntrinsicFunctionKind::SetConstructor,
+        "zip" => IntrinsicFunctionKind::Zip,
+        _ => IntrinsicFunctionKind::Other,
+    };
+    function_with_kind(db, name, doc, params, num_vars, ret_ty, kind)
+}
+
+fn function_with_kind(
+    db: &dyn Db,
+    name: &'static str,
+    doc: &'static str,
+    params: Vec<IntrinsicFunctionParam>,
+    num_vars: usize,
+    ret_ty: TyKind,
+    kind: IntrinsicFunctionKind,
 ) -> IntrinsicFunction {
     IntrinsicFunction::new(
         db,
@@ -1822,7 +2223,7 @@ fn function(
         num_vars,
         params,
         ret_ty.intern(),
-        name == "dict",
+        kind,
     )
 }
 
@@ -1833,12 +2234,32 @@ fn function_field(
     params: Vec<IntrinsicFunctionParam>,
     ret_ty: TyKind,
     num_vars: usize,
+) -> IntrinsicField {
+    function_field_with_kind(
+        db,
+        name,
+        doc,
+        params,
+        ret_ty,
+        num_vars,
+        IntrinsicFunctionKind::Other,
+    )
+}
+
+fn function_field_with_kind(
+    db: &dyn Db,
+    name: &'static str,
+    doc: &'static str,
+    params: Vec<IntrinsicFunctionParam>,
+    ret_ty: TyKind,
+    num_vars: usize,
+    kind: IntrinsicFunctionKind,
 ) -> IntrinsicField {
     IntrinsicField::new_inline(
         name,
         doc,
         TyKind::IntrinsicFunction(
-            function(db, name, doc, params, num_vars, ret_ty),
+            function_with_kind(db, name, doc, params, num_vars, ret_ty, kind),
             Substitution::new_identity(num_vars),
         )
         .intern(),
@@ -1863,6 +2284,10 @@ fn non_literal_string() -> TyKind {
     TyKind::String(None)
 }
 
+fn string_or_none() -> TyKind {
+    TyKind::Union(smallvec![Ty::string(), Ty::none()])
+}
+
 fn non_literal_bool() -> TyKind {
     TyKind::Bool(None)
 }
@@ -1,14 +1,17 @@
-use std::{cmp::Ordering, fmt::Write};
+use std::{cmp::Ordering, fmt::Write, panic::AssertUnwindSafe};
 
 use expect_test::{expect, Expect};
 use itertools::Itertools;
 use starpls_bazel::APIContext;
-use starpls_common::{parse, Db as _, Dialect, FileId, FileInfo};
+use starpls_common::{parse, Db as _, Dialect, File, FileId, FileInfo};
 use starpls_syntax::ast::AstNode;
 use starpls_test_util::FixtureType;
 
 use crate::{
-    source_map, test_database::TestDatabaseBuilder, Db as _, DisplayWithDb, InferenceOptions,
+    def::Expr,
+    module, source_map,
+    test_database::{TestDatabase, TestDatabaseBuilder},
+    Cancelled, Db as _, DisplayWithDb, InferenceOptions, Ty, TyCtxt,
 };
 
 fn check_infer(input: &str, expect: Expect) {
@@ -31,6 +34,7 @@ fn check_infer_with_options(input: &str, expect: Expect, options: InferenceOptio
     builder.add_function("provider");
     builder.add_function("rule");
     builder.add_function("struct");
+    builder.add_function("depset");
     builder.add_type(FixtureType::new("File", vec![], vec![]));
     builder.add_type(FixtureType::new(
         "ctx",
@@ -239,6 +243,35 @@ h, i = [4, 5, 6]
     );
 }
 
+#[test]
+fn test_infer_assign_stmt_size_mismatch() {
+    check_infer(
+        r#"
+c, d = 1, 2, 3
+e, f, g = 1, 2
+"#,
+        expect![[r#"
+            1..2 "c": Literal[1]
+            4..5 "d": Literal[2]
+            1..5 "c, d": tuple[Literal[1], Literal[2]]
+            8..9 "1": Literal[1]
+            11..12 "2": Literal[2]
+            14..15 "3": Literal[3]
+            8..15 "1, 2, 3": tuple[Literal[1], Literal[2], Literal[3]]
+            16..17 "e": Literal[1]
+            19..20 "f": Literal[2]
+            22..23 "g": Unknown
+            16..23 "e, f, g": tuple[Literal[1], Literal[2], Unknown]
+            26..27 "1": Literal[1]
+            29..30 "2": Literal[2]
+            26..30 "1, 2": tuple[Literal[1], Literal[2]]
+
+            8..15 Tuple size mismatch, 2 on left-hand side and 3 on right-hand side
+            26..30 Tuple size mismatch, 3 on left-hand side and 2 on right-hand side
+        "#]],
+    );
+}
+
 #[test]
 fn test_common_type() {
     check_infer(
@@ -267,12 +300,205 @@ fn test_common_type() {
             38..39 "1": Literal[1]
             41..44 "\"b\"": Literal["b"]
             46..49 "\"c\"": Literal["c"]
-            32..50 "{\"a\": 1, \"b\": \"c\"}": dict[string, Unknown]
+            32..50 "{\"a\": 1, \"b\": \"c\"}": dict[string, int | string]
             52..55 "\"a\"": Literal["a"]
             57..58 "1": Literal[1]
             60..61 "1": Literal[1]
             63..66 "\"a\"": Literal["a"]
-            51..67 "{\"a\": 1, 1: \"a\"}": dict[string | int, Unknown]
+            51..67 "{\"a\": 1, 1: \"a\"}": dict[string | int, int | string]
+        "#]],
+    );
+}
+
+#[test]
+fn test_dict_union_types() {
+    check_infer(
+        r#"
+d = {"a": 1, 2: "b"}
+x = d["a"]
+y = d[2]
+"#,
+        expect![[r#"
+            1..2 "d": dict[string | int, int | string]
+            6..9 "\"a\"": Literal["a"]
+            11..12 "1": Literal[1]
+            14..15 "2": Literal[2]
+            17..20 "\"b\"": Literal["b"]
+            5..21 "{\"a\": 1, 2: \"b\"}": dict[string | int, int | string]
+            22..23 "x": int | string
+            26..27 "d": dict[string | int, int | string]
+            28..31 "\"a\"": Literal["a"]
+            26..32 "d[\"a\"]": int | string
+            33..34 "y": int | string
+            37..38 "d": dict[string | int, int | string]
+            39..40 "2": Literal[2]
+            37..41 "d[2]": int | string
+        "#]],
+    );
+}
+
+#[test]
+fn test_dict_index_key_types() {
+    check_infer(
+        r#"
+d = {1: "a", "b": 2}
+d[1]
+d["b"]
+d[1.5]
+f = {1.5: "a"}
+f[1]
+"#,
+        expect![[r#"
+            1..2 "d": dict[int | string, string | int]
+            6..7 "1": Literal[1]
+            9..12 "\"a\"": Literal["a"]
+            14..17 "\"b\"": Literal["b"]
+            19..20 "2": Literal[2]
+            5..21 "{1: \"a\", \"b\": 2}": dict[int | string, string | int]
+            22..23 "d": dict[int | string, string | int]
+            24..25 "1": Literal[1]
+            22..26 "d[1]": string | int
+            27..28 "d": dict[int | string, string | int]
+            29..32 "\"b\"": Literal["b"]
+            27..33 "d[\"b\"]": string | int
+            34..35 "d": dict[int | string, string | int]
+            36..39 "1.5": float
+            34..40 "d[1.5]": Unknown
+            41..42 "f": dict[float, string]
+            46..49 "1.5": float
+            51..54 "\"a\"": Literal["a"]
+            45..55 "{1.5: \"a\"}": dict[float, string]
+            56..57 "f": dict[float, string]
+            58..59 "1": Literal[1]
+            56..60 "f[1]": string
+
+            34..35 Cannot index dict with type "float"
+        "#]],
+    );
+}
+
+#[test]
+fn test_string_bytes_indexing() {
+    check_infer(
+        r#"
+"abc"[0]
+"abc"[-1]
+"abc"[3]
+"abc"["a"]
+b"ab"[0]
+"abc"[-4]
+"#,
+        expect![[r#"
+            1..6 "\"abc\"": Literal["abc"]
+            7..8 "0": Literal[0]
+            1..9 "\"abc\"[0]": string
+            10..15 "\"abc\"": Literal["abc"]
+            17..18 "1": Literal[1]
+            16..18 "-1": Literal[-1]
+            10..19 "\"abc\"[-1]": string
+            20..25 "\"abc\"": Literal["abc"]
+            26..27 "3": Literal[3]
+            20..28 "\"abc\"[3]": Unknown
+            29..34 "\"abc\"": Literal["abc"]
+            35..38 "\"a\"": Literal["a"]
+            29..39 "\"abc\"[\"a\"]": Unknown
+            40..45 "b\"ab\"": bytes
+            46..47 "0": Literal[0]
+            40..48 "b\"ab\"[0]": int
+            49..54 "\"abc\"": Literal["abc"]
+            56..57 "4": Literal[4]
+            55..57 "-4": Literal[-4]
+            49..58 "\"abc\"[-4]": Unknown
+
+            20..28 Index 3 is out of range for type "Literal["abc"]"
+            29..34 Cannot index string with type "Literal["a"]"
+            49..58 Index -4 is out of range for type "Literal["abc"]"
+        "#]],
+    );
+}
+
+#[test]
+fn test_tuple_negative_index() {
+    check_infer(
+        r#"
+t = (1, "a")
+t[-1]
+t[-3]
+"#,
+        expect![[r#"
+            1..2 "t": tuple[Literal[1], Literal["a"]]
+            6..7 "1": Literal[1]
+            9..12 "\"a\"": Literal["a"]
+            5..13 "(1, \"a\")": tuple[Literal[1], Literal["a"]]
+            14..15 "t": tuple[Literal[1], Literal["a"]]
+            17..18 "1": Literal[1]
+            16..18 "-1": Literal[-1]
+            14..19 "t[-1]": Literal["a"]
+            20..21 "t": tuple[Literal[1], Literal["a"]]
+            23..24 "3": Literal[3]
+            22..24 "-3": Literal[-3]
+            20..25 "t[-3]": Unknown
+
+            20..25 Index -3 is out of range for type tuple[Literal[1], Literal["a"]]
+        "#]],
+    );
+}
+
+#[test]
+fn test_fail_never_returns() {
+    check_infer(
+        r#"
+x = [1] or fail("bad")
+y = fail("oops", 1, False)
+z = 1 and "a"
+"#,
+        expect![[r#"
+            1..2 "x": list[int]
+            6..7 "1": Literal[1]
+            5..8 "[1]": list[int]
+            12..16 "fail": def fail(*args: Any, msg: Any = None, attr: string | None = None, sep: string = None) -> Never
+            17..22 "\"bad\"": Literal["bad"]
+            12..23 "fail(\"bad\")": Never
+            5..23 "[1] or fail(\"bad\")": list[int]
+            24..25 "y": Never
+            28..32 "fail": def fail(*args: Any, msg: Any = None, attr: string | None = None, sep: string = None) -> Never
+            33..39 "\"oops\"": Literal["oops"]
+            41..42 "1": Literal[1]
+            44..49 "False": Literal[False]
+            28..50 "fail(\"oops\", 1, False)": Never
+            51..52 "z": int | string
+            55..56 "1": Literal[1]
+            61..64 "\"a\"": Literal["a"]
+            55..64 "1 and \"a\"": int | string
+        "#]],
+    );
+}
+
+#[test]
+fn test_print_fail_keywords() {
+    check_infer(
+        r#"
+print("a", "b", sep = ", ")
+print("a", sep = 1)
+fail("a", attr = 2)
+"#,
+        expect![[r#"
+            1..6 "print": def print(*args: Any, sep: string = None) -> None
+            7..10 "\"a\"": Literal["a"]
+            12..15 "\"b\"": Literal["b"]
+            23..27 "\", \"": Literal[", "]
+            1..28 "print(\"a\", \"b\", sep = \", \")": None
+            29..34 "print": def print(*args: Any, sep: string = None) -> None
+            35..38 "\"a\"": Literal["a"]
+            46..47 "1": Literal[1]
+            29..48 "print(\"a\", sep = 1)": None
+            49..53 "fail": def fail(*args: Any, msg: Any = None, attr: string | None = None, sep: string = None) -> Never
+            54..57 "\"a\"": Literal["a"]
+            66..67 "2": Literal[2]
+            49..68 "fail(\"a\", attr = 2)": Never
+
+            46..47 Argument of type "Literal[1]" cannot be assigned to parameter of type "string"
+            66..67 Argument of type "Literal[2]" cannot be assigned to parameter of type "string | None"
         "#]],
     );
 }
@@ -335,6 +561,125 @@ def frobnicate(
     )
 }
 
+#[test]
+fn test_never() {
+    check_infer(
+        r#"
+def stop():
+    # type: () -> Never
+    pass
+
+x = stop()
+y = 1 # type: Never
+z = stop() # type: int
+"#,
+        expect![[r#"
+            47..48 "x": Never
+            51..55 "stop": def stop() -> Never
+            51..57 "stop()": Never
+            58..59 "y": Never
+            62..63 "1": Literal[1]
+            78..79 "z": int
+            82..86 "stop": def stop() -> Never
+            82..88 "stop()": Never
+
+            62..63 Expression of type "Literal[1]" cannot be assigned to variable of type "Never"
+        "#]],
+    )
+}
+
+#[test]
+fn test_param_type_comments() {
+    check_infer(
+        r#"
+def f(x, y, *args, **kwargs):
+    # type: (int, string, *bool, **float) -> None
+    x
+    y
+    args
+    kwargs
+
+def g(x):
+    x
+"#,
+        expect![[r#"
+            85..86 "x": int
+            91..92 "y": string
+            97..101 "args": tuple[bool, ...]
+            106..112 "kwargs": dict[string, float]
+            128..129 "x": Unknown
+        "#]],
+    )
+}
+
+#[test]
+fn test_variadic_params_in_body() {
+    check_infer(
+        r#"
+def f(*args, **kwargs):
+    # type: (*int, **string) -> None
+    for arg in args:
+        arg
+    kwargs["a"]
+"#,
+        expect![[r#"
+            70..73 "arg": int
+            77..81 "args": tuple[int, ...]
+            91..94 "arg": int
+            99..105 "kwargs": dict[string, string]
+            106..109 "\"a\"": Literal["a"]
+            99..110 "kwargs[\"a\"]": string
+        "#]],
+    )
+}
+
+#[test]
+fn test_param_default_values() {
+    check_infer(
+        r#"
+def f(a = 0, b = "x", c = [1], d = None, *args, **kwargs):
+    a
+    b
+    c
+    d
+    args
+    kwargs
+"#,
+        expect![[r#"
+            11..12 "0": Literal[0]
+            18..21 "\"x\"": Literal["x"]
+            28..29 "1": Literal[1]
+            27..30 "[1]": list[int]
+            36..40 "None": None
+            64..65 "a": int
+            70..71 "b": string
+            76..77 "c": list[int]
+            82..83 "d": Unknown
+            88..92 "args": tuple[Unknown, ...]
+            97..103 "kwargs": dict[string, Unknown]
+        "#]],
+    )
+}
+
+#[test]
+fn test_param_default_earlier_param() {
+    check_infer(
+        r#"
+def f(a = 0, b = a, c = [b]):
+    b
+    c
+"#,
+        expect![[r#"
+            11..12 "0": Literal[0]
+            18..19 "a": int
+            26..27 "b": int
+            25..28 "[b]": list[int]
+            35..36 "b": int
+            41..42 "c": list[int]
+        "#]],
+    )
+}
+
 #[test]
 fn test_union() {
     check_infer(
@@ -446,8 +791,8 @@ foo(baz=1)
             40..41 "1": Literal[1]
             32..42 "foo(baz=1)": Unknown
 
-            32..42 Argument missing for parameter(s) "bar"
             40..41 Unexpected keyword argument "baz"
+            41..42 Missing required argument "bar"
         "#]],
     );
 }
@@ -475,13 +820,49 @@ foo(bar=4)
             57..58 "4": Literal[4]
             49..59 "foo(bar=4)": Unknown
 
-            28..34 Argument missing for parameter(s) "bar"
             32..33 Unexpected positional argument
+            33..34 Missing required argument "bar"
             39..40 Unexpected positional argument
         "#]],
     );
 }
 
+#[test]
+fn test_call_missing_required_arguments() {
+    check_infer(
+        r#"
+def foo(a, b):
+    pass
+
+foo()
+foo(b=1)
+foo(*[1, 2])
+foo(**{"a": 1})
+"#,
+        expect![[r#"
+            26..29 "foo": def foo(a, b) -> Unknown
+            26..31 "foo()": Unknown
+            32..35 "foo": def foo(a, b) -> Unknown
+            38..39 "1": Literal[1]
+            32..40 "foo(b=1)": Unknown
+            41..44 "foo": def foo(a, b) -> Unknown
+            47..48 "1": Literal[1]
+            50..51 "2": Literal[2]
+            46..52 "[1, 2]": list[int]
+            41..53 "foo(*[1, 2])": Unknown
+            54..57 "foo": def foo(a, b) -> Unknown
+            61..64 "\"a\"": Literal["a"]
+            66..67 "1": Literal[1]
+            60..68 "{\"a\": 1}": dict[string, int]
+            54..69 "foo(**{\"a\": 1})": Unknown
+
+            30..31 Missing required argument "a"
+            30..31 Missing required argument "b"
+            39..40 Missing required argument "a"
+        "#]],
+    );
+}
+
 #[test]
 fn test_call_redundant_kwargs() {
     check_infer(
@@ -554,23 +935,40 @@ fn test_dict_constructor() {
 foo = dict(a = 1, b = 2, c = 3)
 bar = dict(d = 4, e = "five", f = 6.)
 baz = dict()
+qux = dict([("g", 7)])
+quux = dict([("h", 8)], i = "nine")
 "#,
         expect![[r#"
-            1..4 "foo": dict[string, Unknown]
+            1..4 "foo": dict[string, int]
             7..11 "dict": def dict(x0: dict[Unknown, Unknown] | Iterable[Iterable[Any]] = None, **kwargs) -> dict[Unknown, Unknown]
             16..17 "1": Literal[1]
             23..24 "2": Literal[2]
             30..31 "3": Literal[3]
-            7..32 "dict(a = 1, b = 2, c = 3)": dict[string, Unknown]
-            33..36 "bar": dict[string, Unknown]
+            7..32 "dict(a = 1, b = 2, c = 3)": dict[string, int]
+            33..36 "bar": dict[string, int | string | float]
             39..43 "dict": def dict(x0: dict[Unknown, Unknown] | Iterable[Iterable[Any]] = None, **kwargs) -> dict[Unknown, Unknown]
             48..49 "4": Literal[4]
             55..61 "\"five\"": Literal["five"]
             67..69 "6.": float
-            39..70 "dict(d = 4, e = \"five\", f = 6.)": dict[string, Unknown]
+            39..70 "dict(d = 4, e = \"five\", f = 6.)": dict[string, int | string | float]
             71..74 "baz": dict[Unknown, Unknown]
             77..81 "dict": def dict(x0: dict[Unknown, Unknown] | Iterable[Iterable[Any]] = None, **kwargs) -> dict[Unknown, Unknown]
             77..83 "dict()": dict[Unknown, Unknown]
+            84..87 "qux": dict[string, int]
+            90..94 "dict": def dict(x0: dict[Unknown, Unknown] | Iterable[Iterable[Any]] = None, **kwargs) -> dict[Unknown, Unknown]
+            97..100 "\"g\"": Literal["g"]
+            102..103 "7": Literal[7]
+            96..104 "(\"g\", 7)": tuple[Literal["g"], Literal[7]]
+            95..105 "[(\"g\", 7)]": list[tuple[Literal["g"], Literal[7]]]
+            90..106 "dict([(\"g\", 7)])": dict[string, int]
+            107..111 "quux": dict[string, int | string]
+            114..118 "dict": def dict(x0: dict[Unknown, Unknown] | Iterable[Iterable[Any]] = None, **kwargs) -> dict[Unknown, Unknown]
+            121..124 "\"h\"": Literal["h"]
+            126..127 "8": Literal[8]
+            120..128 "(\"h\", 8)": tuple[Literal["h"], Literal[8]]
+            119..129 "[(\"h\", 8)]": list[tuple[Literal["h"], Literal[8]]]
+            135..141 "\"nine\"": Literal["nine"]
+            114..142 "dict([(\"h\", 8)], i = \"nine\")": dict[string, int | string]
         "#]],
     )
 }
@@ -674,17 +1072,117 @@ foo.b
 foo.c
 "#,
         expect![[r#"
-            1..4 "foo": struct
+            1..4 "foo": struct(a = int, b = string)
             7..13 "struct": def struct(*args, **kwargs) -> Unknown
             18..19 "1": Literal[1]
             25..30 "\"bar\"": Literal["bar"]
-            7..31 "struct(a = 1, b = \"bar\")": struct
-            32..35 "foo": struct
+            7..31 "struct(a = 1, b = \"bar\")": struct(a = int, b = string)
+            32..35 "foo": struct(a = int, b = string)
             32..37 "foo.a": Literal[1]
-            38..41 "foo": struct
+            38..41 "foo": struct(a = int, b = string)
             38..43 "foo.b": Literal["bar"]
-            44..47 "foo": struct
+            44..47 "foo": struct(a = int, b = string)
             44..49 "foo.c": Unknown
+
+            44..49 Cannot access field "c" for type "struct(a = int, b = string)"
+        "#]],
+    )
+}
+
+#[test]
+fn test_getattr() {
+    check_infer(
+        r#"
+foo = struct(a = 1)
+getattr(foo, "a")
+getattr(foo, "b", "c")
+getattr(foo, "b")
+hasattr(foo, "a")
+"#,
+        expect![[r#"
+            1..4 "foo": struct(a = int)
+            7..13 "struct": def struct(*args, **kwargs) -> Unknown
+            18..19 "1": Literal[1]
+            7..20 "struct(a = 1)": struct(a = int)
+            21..28 "getattr": def getattr(x0: Any, x1: string, x2: Any = None) -> Any
+            29..32 "foo": struct(a = int)
+            34..37 "\"a\"": Literal["a"]
+            21..38 "getattr(foo, \"a\")": Literal[1]
+            39..46 "getattr": def getattr(x0: Any, x1: string, x2: Any = None) -> Any
+            47..50 "foo": struct(a = int)
+            52..55 "\"b\"": Literal["b"]
+            57..60 "\"c\"": Literal["c"]
+            39..61 "getattr(foo, \"b\", \"c\")": Literal["c"]
+            62..69 "getattr": def getattr(x0: Any, x1: string, x2: Any = None) -> Any
+            70..73 "foo": struct(a = int)
+            75..78 "\"b\"": Literal["b"]
+            62..79 "getattr(foo, \"b\")": Unknown
+            80..87 "hasattr": def hasattr(x0: Any, x1: string) -> bool
+            88..91 "foo": struct(a = int)
+            93..96 "\"a\"": Literal["a"]
+            80..97 "hasattr(foo, \"a\")": bool
+
+            75..78 Cannot access field "b" for type "struct(a = int)"
+        "#]],
+    )
+}
+
+#[test]
+fn test_introspection_builtins() {
+    check_infer(
+        r#"
+s = struct(a = 1)
+dir(s)
+getattr(s, "a")
+l = [1]
+dir(l)
+getattr(l, "append")
+hasattr(l, "b")
+"#,
+        expect![[r#"
+            1..2 "s": struct(a = int)
+            5..11 "struct": def struct(*args, **kwargs) -> Unknown
+            16..17 "1": Literal[1]
+            5..18 "struct(a = 1)": struct(a = int)
+            19..22 "dir": def dir(x0: Any) -> list[string]
+            23..24 "s": struct(a = int)
+            19..25 "dir(s)": list[string]
+            26..33 "getattr": def getattr(x0: Any, x1: string, x2: Any = None) -> Any
+            34..35 "s": struct(a = int)
+            37..40 "\"a\"": Literal["a"]
+            26..41 "getattr(s, \"a\")": Literal[1]
+            42..43 "l": list[int]
+            47..48 "1": Literal[1]
+            46..49 "[1]": list[int]
+            50..53 "dir": def dir(x0: Any) -> list[string]
+            54..55 "l": list[int]
+            50..56 "dir(l)": list[string]
+            57..64 "getattr": def getattr(x0: Any, x1: string, x2: Any = None) -> Any
+            65..66 "l": list[int]
+            68..76 "\"append\"": Literal["append"]
+            57..77 "getattr(l, \"append\")": def append(x0: int) -> None
+            78..85 "hasattr": def hasattr(x0: Any, x1: string) -> bool
+            86..87 "l": list[int]
+            89..92 "\"b\"": Literal["b"]
+            78..93 "hasattr(l, \"b\")": bool
+        "#]],
+    )
+}
+
+#[test]
+fn test_struct_duplicate_fields() {
+    check_infer(
+        r#"
+s = struct(a = 1, a = "b")
+"#,
+        expect![[r#"
+            1..2 "s": struct(a = int, a = string)
+            5..11 "struct": def struct(*args, **kwargs) -> Unknown
+            16..17 "1": Literal[1]
+            23..26 "\"b\"": Literal["b"]
+            5..27 "struct(a = 1, a = \"b\")": struct(a = int, a = string)
+
+            23..26 Field "a" is assigned more than once
         "#]],
     )
 }
@@ -720,6 +1218,37 @@ info = DataInfo(foo = "foo", bar = "bar")
     )
 }
 
+#[test]
+fn test_provider_list_fields() {
+    check_infer(
+        r#"
+DataInfo = provider(fields = ["foo", "bar"])
+info = DataInfo(foo = 1, bar = 2)
+info.foo
+info.baz
+"#,
+        expect![[r#"
+            1..9 "DataInfo": Provider[DataInfo]
+            12..20 "provider": def provider(*args, **kwargs) -> Unknown
+            31..36 "\"foo\"": Literal["foo"]
+            38..43 "\"bar\"": Literal["bar"]
+            30..44 "[\"foo\", \"bar\"]": list[string]
+            12..45 "provider(fields = [\"foo\", \"bar\"])": Provider[DataInfo]
+            46..50 "info": DataInfo
+            53..61 "DataInfo": Provider[DataInfo]
+            68..69 "1": Literal[1]
+            77..78 "2": Literal[2]
+            53..79 "DataInfo(foo = 1, bar = 2)": DataInfo
+            80..84 "info": DataInfo
+            80..88 "info.foo": Unknown
+            89..93 "info": DataInfo
+            89..97 "info.baz": Unknown
+
+            89..97 Cannot access field "baz" for type "DataInfo"
+        "#]],
+    )
+}
+
 #[test]
 fn test_provider_constructor() {
     check_infer(
@@ -760,29 +1289,29 @@ info1 = providers.result[0]()
 info2 = providers.result[1]()
 "#,
         expect![[r#"
-            1..10 "providers": struct
+            1..10 "providers": struct(DefaultInfo = Provider[_])
             13..19 "struct": def struct(*args, **kwargs) -> Unknown
             34..42 "provider": def provider(*args, **kwargs) -> Unknown
             34..44 "provider()": Provider[_]
-            13..45 "struct(DefaultInfo = provider())": struct
+            13..45 "struct(DefaultInfo = provider())": struct(DefaultInfo = Provider[_])
             46..50 "info": _
-            53..62 "providers": struct
+            53..62 "providers": struct(DefaultInfo = Provider[_])
             53..74 "providers.DefaultInfo": Provider[_]
             53..76 "providers.DefaultInfo()": _
-            78..87 "providers": struct
+            78..87 "providers": struct(result = tuple[Provider[_], ProviderRawConstructor])
             90..96 "struct": def struct(*args, **kwargs) -> Unknown
             106..114 "provider": def provider(*args, **kwargs) -> Unknown
             122..126 "None": None
             106..127 "provider(init = None)": tuple[Provider[_], ProviderRawConstructor]
-            90..128 "struct(result = provider(init = None))": struct
+            90..128 "struct(result = provider(init = None))": struct(result = tuple[Provider[_], ProviderRawConstructor])
             129..134 "info1": _
-            137..146 "providers": struct
+            137..146 "providers": struct(result = tuple[Provider[_], ProviderRawConstructor])
             137..153 "providers.result": tuple[Provider[_], ProviderRawConstructor]
             154..155 "0": Literal[0]
             137..156 "providers.result[0]": Provider[_]
             137..158 "providers.result[0]()": _
             159..164 "info2": _
-            167..176 "providers": struct
+            167..176 "providers": struct(result = tuple[Provider[_], ProviderRawConstructor])
             167..183 "providers.result": tuple[Provider[_], ProviderRawConstructor]
             184..185 "1": Literal[1]
             167..186 "providers.result[1]": ProviderRawConstructor
@@ -849,12 +1378,12 @@ n = +m
 o = ~m
 "#,
         expect![[r#"
-            1..2 "a": int
+            1..2 "a": Literal[1]
             6..7 "1": Literal[1]
-            5..7 "+1": int
-            8..9 "b": int
+            5..7 "+1": Literal[1]
+            8..9 "b": Literal[-1]
             13..14 "1": Literal[1]
-            12..14 "-1": int
+            12..14 "-1": Literal[-1]
             15..16 "c": int
             20..21 "1": Literal[1]
             19..21 "~1": int
@@ -979,6 +1508,219 @@ fn test_tuple_assignments() {
     )
 }
 
+#[test]
+fn test_string_methods() {
+    check_infer(
+        r#"
+"a b".split(None)
+", ".join(["a"])
+", ".join([1])
+"a".endswith(("a", "b"))
+"#,
+        expect![[r#"
+            1..6 "\"a b\"": Literal["a b"]
+            1..12 "\"a b\".split": def split(x0: string | None = None, x1: int = None) -> list[string]
+            13..17 "None": None
+            1..18 "\"a b\".split(None)": list[string]
+            19..23 "\", \"": Literal[", "]
+            19..28 "\", \".join": def join(x0: Iterable[string]) -> string
+            30..33 "\"a\"": Literal["a"]
+            29..34 "[\"a\"]": list[string]
+            19..35 "\", \".join([\"a\"])": string
+            36..40 "\", \"": Literal[", "]
+            36..45 "\", \".join": def join(x0: Iterable[string]) -> string
+            47..48 "1": Literal[1]
+            46..49 "[1]": list[int]
+            36..50 "\", \".join([1])": string
+            51..54 "\"a\"": Literal["a"]
+            51..63 "\"a\".endswith": def endswith(x0: string | tuple[string, ...], x1: int = None, x2: int = None) -> bool
+            65..68 "\"a\"": Literal["a"]
+            70..73 "\"b\"": Literal["b"]
+            64..74 "(\"a\", \"b\")": tuple[Literal["a"], Literal["b"]]
+            51..75 "\"a\".endswith((\"a\", \"b\"))": bool
+
+            46..49 Argument of type "list[int]" cannot be assigned to parameter of type "Iterable[string]"
+        "#]],
+    )
+}
+
+#[test]
+fn test_method_chain() {
+    check_infer(
+        r#"
+"a,b".split(",").pop().upper()
+"#,
+        expect![[r#"
+            1..6 "\"a,b\"": Literal["a,b"]
+            1..12 "\"a,b\".split": def split(x0: string | None = None, x1: int = None) -> list[string]
+            13..16 "\",\"": Literal[","]
+            1..17 "\"a,b\".split(\",\")": list[string]
+            1..21 "\"a,b\".split(\",\").pop": def pop(x0: int = None) -> string
+            1..23 "\"a,b\".split(\",\").pop()": string
+            1..29 "\"a,b\".split(\",\").pop().upper": def upper() -> string
+            1..31 "\"a,b\".split(\",\").pop().upper()": string
+        "#]],
+    )
+}
+
+#[test]
+fn test_bitwise_ops() {
+    check_infer(
+        r#"
+1 << 2
+"a" >> 1
+1 & "a"
+{} | {}
+"#,
+        expect![[r#"
+            1..2 "1": Literal[1]
+            6..7 "2": Literal[2]
+            1..7 "1 << 2": int
+            8..11 "\"a\"": Literal["a"]
+            15..16 "1": Literal[1]
+            8..16 "\"a\" >> 1": Unknown
+            17..18 "1": Literal[1]
+            21..24 "\"a\"": Literal["a"]
+            17..24 "1 & \"a\"": Unknown
+            25..27 "{}": dict[Unknown, Unknown]
+            30..32 "{}": dict[Unknown, Unknown]
+            25..32 "{} | {}": Unknown
+
+            8..16 Operator ">>" requires "int" operands, got "Literal["a"]" and "Literal[1]"
+            17..24 Operator "&" requires "int" operands, got "Literal[1]" and "Literal["a"]"
+            25..32 Merging dicts with "|" is not supported in this dialect
+        "#]],
+    )
+}
+
+#[test]
+fn test_mod_ops() {
+    check_infer(
+        r#"
+"%d" % 5
+b"%d" % 5
+7 % 2
+"#,
+        expect![[r#"
+            1..5 "\"%d\"": Literal["%d"]
+            8..9 "5": Literal[5]
+            1..9 "\"%d\" % 5": string
+            10..15 "b\"%d\"": bytes
+            18..19 "5": Literal[5]
+            10..19 "b\"%d\" % 5": bytes
+            20..21 "7": Literal[7]
+            24..25 "2": Literal[2]
+            20..25 "7 % 2": int
+        "#]],
+    )
+}
+
+#[test]
+fn test_dict_union_disallowed() {
+    check_infer_with_options(
+        r#"
+{"a": 1} | {"b": ""}
+"#,
+        expect![[r#"
+            2..5 "\"a\"": Literal["a"]
+            7..8 "1": Literal[1]
+            1..9 "{\"a\": 1}": dict[string, int]
+            13..16 "\"b\"": Literal["b"]
+            18..20 "\"\"": Literal[""]
+            12..21 "{\"b\": \"\"}": dict[string, string]
+            1..21 "{\"a\": 1} | {\"b\": \"\"}": Unknown
+
+            1..21 Merging dicts with "|" is not supported in this dialect
+        "#]],
+        InferenceOptions {
+            disallow_dict_union: true,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn test_dict_get() {
+    check_infer(
+        r#"
+d = {"a": 1}
+d.get("a")
+d.get("a", "b")
+d.get(1)
+"#,
+        expect![[r#"
+            1..2 "d": dict[string, int]
+            6..9 "\"a\"": Literal["a"]
+            11..12 "1": Literal[1]
+            5..13 "{\"a\": 1}": dict[string, int]
+            14..15 "d": dict[string, int]
+            14..19 "d.get": def get(x0: string, x1: '2 = None) -> int | '2
+            20..23 "\"a\"": Literal["a"]
+            14..24 "d.get(\"a\")": int | None
+            25..26 "d": dict[string, int]
+            25..30 "d.get": def get(x0: string, x1: '2 = None) -> int | '2
+            31..34 "\"a\"": Literal["a"]
+            36..39 "\"b\"": Literal["b"]
+            25..40 "d.get(\"a\", \"b\")": int | string
+            41..42 "d": dict[string, int]
+            41..46 "d.get": def get(x0: string, x1: '2 = None) -> int | '2
+            47..48 "1": Literal[1]
+            41..49 "d.get(1)": int | None
+
+            47..48 Argument of type "Literal[1]" cannot be assigned to parameter of type "string"
+        "#]],
+    )
+}
+
+#[test]
+fn test_dict_setdefault_update() {
+    check_infer(
+        r#"
+d = {"a": 1}
+d.setdefault("b", 2)
+d.setdefault("c", "d")
+d.update({"e": 3})
+d.update({1: 2})
+d.setdefault("f")
+"#,
+        expect![[r#"
+            1..2 "d": dict[string, int]
+            6..9 "\"a\"": Literal["a"]
+            11..12 "1": Literal[1]
+            5..13 "{\"a\": 1}": dict[string, int]
+            14..15 "d": dict[string, int]
+            14..26 "d.setdefault": def setdefault(x0: string, x1: int = None) -> int
+            27..30 "\"b\"": Literal["b"]
+            32..33 "2": Literal[2]
+            14..34 "d.setdefault(\"b\", 2)": int
+            35..36 "d": dict[string, int]
+            35..47 "d.setdefault": def setdefault(x0: string, x1: int = None) -> int
+            48..51 "\"c\"": Literal["c"]
+            53..56 "\"d\"": Literal["d"]
+            35..57 "d.setdefault(\"c\", \"d\")": int
+            58..59 "d": dict[string, int]
+            58..66 "d.update": def update(x0: dict[string, int] | Iterable[Iterable[Any]] | None = None, **kwargs) -> None
+            68..71 "\"e\"": Literal["e"]
+            73..74 "3": Literal[3]
+            67..75 "{\"e\": 3}": dict[string, int]
+            58..76 "d.update({\"e\": 3})": None
+            77..78 "d": dict[string, int]
+            77..85 "d.update": def update(x0: dict[string, int] | Iterable[Iterable[Any]] | None = None, **kwargs) -> None
+            87..88 "1": Literal[1]
+            90..91 "2": Literal[2]
+            86..92 "{1: 2}": dict[int, int]
+            77..93 "d.update({1: 2})": None
+            94..95 "d": dict[string, int]
+            94..106 "d.setdefault": def setdefault(x0: string, x1: int = None) -> int
+            107..110 "\"f\"": Literal["f"]
+            94..111 "d.setdefault(\"f\")": int | None
+
+            53..56 Argument of type "Literal["d"]" cannot be assigned to parameter of type "int"
+            86..92 Argument of type "dict[int, int]" cannot be assigned to parameter of type "dict[string, int] | Iterable[Iterable[Any]] | None"
+        "#]],
+    )
+}
+
 #[test]
 fn test_slice_expr() {
     check_infer(
@@ -1042,11 +1784,11 @@ def foo(*nums):
             103..105 "[]": list[Unknown]
             94..106 "(\"a\", 1, [])": tuple[Literal["a"], Literal[1], list[Unknown]]
             94..109 "(\"a\", 1, [])[:]": string | int | list[Unknown]
-            110..111 "d": list[int]
+            110..111 "d": range
             114..119 "range": def range(x0: int, x1: int = None, x2: int = None) -> range
             120..122 "10": Literal[10]
             114..123 "range(10)": range
-            114..126 "range(10)[:]": list[int]
+            114..126 "range(10)[:]": range
             127..128 "e": Sequence[int]
             132..133 "1": Literal[1]
             135..136 "2": Literal[2]
@@ -1144,6 +1886,211 @@ def f():
     );
 }
 
+#[test]
+fn test_narrow_none_checks() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(cond):
+    x = 1
+    if cond:
+        x = None
+    if x != None:
+        x
+    else:
+        x
+    if not x:
+        x
+    else:
+        x
+"#,
+        expect![[r#"
+            18..19 "x": Literal[1]
+            22..23 "1": Literal[1]
+            31..35 "cond": Unknown
+            45..46 "x": None
+            49..53 "None": None
+            61..62 "x": None | int
+            66..70 "None": None
+            61..70 "x != None": bool
+            80..81 "x": int
+            100..101 "x": None
+            113..114 "x": int | None
+            109..114 "not x": bool
+            124..125 "x": int | None
+            144..145 "x": int
+        "#]],
+    );
+}
+
+#[test]
+fn test_narrow_none_equality() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(cond):
+    x = 1
+    if cond:
+        x = None
+    if x == None:
+        x
+    else:
+        x
+    if None != x:
+        x
+"#,
+        expect![[r#"
+            18..19 "x": Literal[1]
+            22..23 "1": Literal[1]
+            31..35 "cond": Unknown
+            45..46 "x": None
+            49..53 "None": None
+            61..62 "x": None | int
+            66..70 "None": None
+            61..70 "x == None": bool
+            80..81 "x": None
+            100..101 "x": int
+            109..113 "None": None
+            117..118 "x": None | int
+            109..118 "None != x": bool
+            128..129 "x": int
+        "#]],
+    );
+}
+
+#[test]
+fn test_identity_comparison() {
+    check_infer_with_options(
+        r#"
+def f(cond):
+    x = 1
+    if cond:
+        x = None
+    if x is None:
+        x
+    else:
+        x
+    y = [1]
+    y is [1]
+
+def g(z):
+    z is not None
+"#,
+        expect![[r#"
+            18..19 "x": Literal[1]
+            22..23 "1": Literal[1]
+            31..35 "cond": Unknown
+            45..46 "x": None
+            49..53 "None": None
+            61..62 "x": None | int
+            66..70 "None": None
+            61..70 "x is None": bool
+            80..81 "x": None
+            100..101 "x": int
+            106..107 "y": list[int]
+            111..112 "1": Literal[1]
+            110..113 "[1]": list[int]
+            118..119 "y": list[int]
+            124..125 "1": Literal[1]
+            123..126 "[1]": list[int]
+            118..126 "y is [1]": bool
+            142..143 "z": Unknown
+            151..155 "None": None
+            142..155 "z is not None": bool
+
+            118..126 Operator "is" requires a "None" or "bool" operand, got "list[int]" and "list[int]"
+        "#]],
+        InferenceOptions {
+            allow_identity_operators: true,
+            use_code_flow_analysis: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn test_identity_comparison_unsupported() {
+    check_infer(
+        r#"
+def f(x):
+    x is None
+"#,
+        expect![[r#"
+            15..16 "x": Unknown
+            20..24 "None": None
+            15..24 "x is None": bool
+
+            15..24 Operator "is" is not supported in this dialect
+        "#]],
+    );
+}
+
+#[test]
+fn test_optional_field_access() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(cond):
+    x = None
+    if cond:
+        x = struct(a = 1)
+    x.a
+    if x != None:
+        x.a
+"#,
+        expect![[r#"
+            18..19 "x": None
+            22..26 "None": None
+            34..38 "cond": Unknown
+            48..49 "x": struct(a = int)
+            52..58 "struct": def struct(*args, **kwargs) -> Unknown
+            63..64 "1": Literal[1]
+            52..65 "struct(a = 1)": struct(a = int)
+            70..71 "x": struct(a = int) | None
+            70..73 "x.a": int
+            81..82 "x": struct(a = int) | None
+            86..90 "None": None
+            81..90 "x != None": bool
+            100..101 "x": struct(a = int)
+            100..103 "x.a": int
+
+            70..71 "x" may be None here
+        "#]],
+    );
+}
+
+#[test]
+fn test_narrow_if_expr() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f(cond):
+    x = None
+    if cond:
+        x = 1
+    y = x if x != None else 0
+    z = 1 if f else 2
+"#,
+        expect![[r#"
+            18..19 "x": None
+            22..26 "None": None
+            34..38 "cond": Unknown
+            48..49 "x": Literal[1]
+            52..53 "1": Literal[1]
+            58..59 "y": int
+            62..63 "x": int
+            67..68 "x": int | None
+            72..76 "None": None
+            67..76 "x != None": bool
+            82..83 "0": Literal[0]
+            62..83 "x if x != None else 0": int
+            88..89 "z": int
+            92..93 "1": Literal[1]
+            97..98 "f": def f(cond) -> Unknown
+            104..105 "2": Literal[2]
+            92..105 "1 if f else 2": int
+
+            97..98 Condition is a function and is always true; did you mean to call it?
+        "#]],
+    );
+}
+
 #[test]
 fn test_infer_ctx_attrs() {
     check_infer_with_options(
@@ -1204,6 +2151,7 @@ my_rule = repository_rule(
         InferenceOptions {
             infer_ctx_attributes: true,
             use_code_flow_analysis: true,
+            ..Default::default()
         },
     );
 }
@@ -1453,6 +2401,247 @@ def f():
     )
 }
 
+#[test]
+fn test_generic_intrinsic_functions() {
+    check_infer(
+        r#"
+a = sorted([3, 1, 2])
+b = reversed({"a": 1})
+c = [1]
+c.append("d")
+"#,
+        expect![[r#"
+            1..2 "a": list[int]
+            5..11 "sorted": def sorted(x0: Iterable['0], reverse: bool = None, key: Any = None) -> list['0]
+            13..14 "3": Literal[3]
+            16..17 "1": Literal[1]
+            19..20 "2": Literal[2]
+            12..21 "[3, 1, 2]": list[int]
+            5..22 "sorted([3, 1, 2])": list[int]
+            23..24 "b": list[string]
+            27..35 "reversed": def reversed(x0: Iterable['0]) -> list['0]
+            37..40 "\"a\"": Literal["a"]
+            42..43 "1": Literal[1]
+            36..44 "{\"a\": 1}": dict[string, int]
+            27..45 "reversed({\"a\": 1})": list[string]
+            46..47 "c": list[int]
+            51..52 "1": Literal[1]
+            50..53 "[1]": list[int]
+            54..55 "c": list[int]
+            54..62 "c.append": def append(x0: int) -> None
+            63..66 "\"d\"": Literal["d"]
+            54..67 "c.append(\"d\")": None
+
+            63..66 Argument of type "Literal["d"]" cannot be assigned to parameter of type "int"
+        "#]],
+    );
+}
+
+#[test]
+fn test_min_max() {
+    check_infer(
+        r#"
+a = max([3, 1, 2])
+b = min(1, "a", key = len)
+c = max(("a", "b"))
+"#,
+        expect![[r#"
+            1..2 "a": int
+            5..8 "max": def max(*args: Any, key: Any = None) -> Any
+            10..11 "3": Literal[3]
+            13..14 "1": Literal[1]
+            16..17 "2": Literal[2]
+            9..18 "[3, 1, 2]": list[int]
+            5..19 "max([3, 1, 2])": int
+            20..21 "b": int | string
+            24..27 "min": def min(*args: Any, key: Any = None) -> Any
+            28..29 "1": Literal[1]
+            31..34 "\"a\"": Literal["a"]
+            42..45 "len": def len(x0: Any) -> int
+            24..46 "min(1, \"a\", key = len)": int | string
+            47..48 "c": string
+            51..54 "max": def max(*args: Any, key: Any = None) -> Any
+            56..59 "\"a\"": Literal["a"]
+            61..64 "\"b\"": Literal["b"]
+            55..65 "(\"a\", \"b\")": tuple[Literal["a"], Literal["b"]]
+            51..66 "max((\"a\", \"b\"))": string
+        "#]],
+    );
+}
+
+#[test]
+fn test_enumerate_zip() {
+    check_infer(
+        r#"
+for i, s in enumerate(["a"]):
+    pass
+for n, b in zip([1], [True]):
+    pass
+zip(1)
+"#,
+        expect![[r#"
+            5..6 "i": int
+            8..9 "s": string
+            13..22 "enumerate": def enumerate(x0: Iterable['0], x1: int = None) -> list[tuple[int, '0]]
+            24..27 "\"a\"": Literal["a"]
+            23..28 "[\"a\"]": list[string]
+            13..29 "enumerate([\"a\"])": list[tuple[int, string]]
+            44..45 "n": int
+            47..48 "b": bool
+            52..55 "zip": def zip(*args: Iterable[Any]) -> list[Any]
+            57..58 "1": Literal[1]
+            56..59 "[1]": list[int]
+            62..66 "True": Literal[True]
+            61..67 "[True]": list[bool]
+            52..68 "zip([1], [True])": list[tuple[int, bool]]
+            79..82 "zip": def zip(*args: Iterable[Any]) -> list[Any]
+            83..84 "1": Literal[1]
+            79..85 "zip(1)": list[Any]
+
+            83..84 Argument of type "Literal[1]" cannot be assigned to parameter of type "Iterable[Any]"
+        "#]],
+    );
+}
+
+#[test]
+fn test_constant_comparisons() {
+    check_infer(
+        r#"
+x = 1
+x == x
+x < x
+len(x) < 0
+len(x) >= 0
+"#,
+        expect![[r#"
+            1..2 "x": Literal[1]
+            5..6 "1": Literal[1]
+            7..8 "x": Literal[1]
+            12..13 "x": Literal[1]
+            7..13 "x == x": bool
+            14..15 "x": Literal[1]
+            18..19 "x": Literal[1]
+            14..19 "x < x": bool
+            20..23 "len": def len(x0: Any) -> int
+            24..25 "x": Literal[1]
+            20..26 "len(x)": int
+            29..30 "0": Literal[0]
+            20..30 "len(x) < 0": bool
+            31..34 "len": def len(x0: Any) -> int
+            35..36 "x": Literal[1]
+            31..37 "len(x)": int
+            41..42 "0": Literal[0]
+            31..42 "len(x) >= 0": bool
+
+            7..13 Expression always evaluates to True
+            14..19 Expression always evaluates to False
+            20..30 Expression always evaluates to False
+            31..42 Expression always evaluates to True
+        "#]],
+    )
+}
+
+#[test]
+fn test_constant_comparisons_unknown_and_float() {
+    check_infer(
+        r#"
+def f(a, b = 1.0, c = [1]):
+    a == a
+    b == b
+    c == c
+"#,
+        expect![[r#"
+            14..17 "1.0": float
+            24..25 "1": Literal[1]
+            23..26 "[1]": list[int]
+            33..34 "a": Unknown
+            38..39 "a": Unknown
+            33..39 "a == a": Unknown
+            44..45 "b": float
+            49..50 "b": float
+            44..50 "b == b": bool
+            55..56 "c": list[int]
+            60..61 "c": list[int]
+            55..61 "c == c": bool
+
+            55..61 Expression always evaluates to True
+        "#]],
+    )
+}
+
+#[test]
+fn test_not_callable() {
+    check_infer(
+        r#"
+[1]()
+{"a": 1}()
+(1, 2)()
+"a"()
+True()
+None()
+1()
+"#,
+        expect![[r#"
+            2..3 "1": Literal[1]
+            1..4 "[1]": list[int]
+            1..6 "[1]()": Unknown
+            8..11 "\"a\"": Literal["a"]
+            13..14 "1": Literal[1]
+            7..15 "{\"a\": 1}": dict[string, int]
+            7..17 "{\"a\": 1}()": Unknown
+            19..20 "1": Literal[1]
+            22..23 "2": Literal[2]
+            18..24 "(1, 2)": tuple[Literal[1], Literal[2]]
+            18..26 "(1, 2)()": Unknown
+            27..30 "\"a\"": Literal["a"]
+            27..32 "\"a\"()": Unknown
+            33..37 "True": Literal[True]
+            33..39 "True()": Unknown
+            40..44 "None": None
+            40..46 "None()": Unknown
+            47..48 "1": Literal[1]
+            47..50 "1()": Unknown
+
+            1..4 Type "list[int]" is not callable
+            7..15 Type "dict[string, int]" is not callable
+            18..24 Type "tuple[Literal[1], Literal[2]]" is not callable
+            27..30 Type "Literal["a"]" is not callable
+            33..37 Type "Literal[True]" is not callable
+            40..44 Type "None" is not callable
+            47..48 Type "Literal[1]" is not callable
+        "#]],
+    )
+}
+
+#[test]
+fn test_undefined_names() {
+    check_infer(
+        r#"
+x = y
+y = 1
+z
+
+def f():
+    w
+
+w = 2
+"#,
+        expect![[r#"
+            1..2 "x": Unbound
+            5..6 "y": Unbound
+            7..8 "y": Literal[1]
+            11..12 "1": Literal[1]
+            13..14 "z": Unbound
+            29..30 "w": Literal[2]
+            32..33 "w": Literal[2]
+            36..37 "2": Literal[2]
+
+            5..6 "y" is not defined
+            13..14 "z" is not defined
+        "#]],
+    )
+}
+
 #[test]
 fn test_unreachable() {
     check_infer_with_code_flow_analysis(
@@ -1501,9 +2690,9 @@ def h():
             169..170 "2": Literal[2]
             172..173 "3": Literal[3]
             166..173 "1, 2, 3": tuple[Literal[1], Literal[2], Literal[3]]
-            186..187 "x": Unknown
+            186..187 "x": int
             190..191 "1": Literal[1]
-            186..191 "x < 1": Unknown
+            186..191 "x < 1": bool
             205..206 "y": Literal[1]
             209..210 "1": Literal[1]
             255..256 "y": Literal["one"]
@@ -1533,7 +2722,653 @@ def f():
             33..40 "1, 2, 3": tuple[Literal[1], Literal[2], Literal[3]]
             50..51 "x": Literal["one"]
             54..59 "\"one\"": Literal["one"]
-            64..65 "x": Unknown
+            64..65 "x": int | string
         "#]],
     );
 }
+
+#[test]
+fn test_for_accumulator() {
+    check_infer_with_code_flow_analysis(
+        r#"
+def f():
+    acc = [0]
+    for x in ["a", "b"]:
+        acc = acc + [x]
+    acc
+"#,
+        expect![[r#"
+            14..17 "acc": list[int]
+            21..22 "0": Literal[0]
+            20..23 "[0]": list[int]
+            32..33 "x": string
+            38..41 "\"a\"": Literal["a"]
+            43..46 "\"b\"": Literal["b"]
+            37..47 "[\"a\", \"b\"]": list[string]
+            57..60 "acc": list[int | string]
+            63..66 "acc": list[int]
+            70..71 "x": string
+            69..72 "[x]": list[string]
+            63..72 "acc + [x]": list[int | string]
+            77..80 "acc": list[int] | list[int | string]
+        "#]],
+    );
+}
+
+#[test]
+fn test_set() {
+    check_infer(
+        r#"
+s = set([1, 2])
+t = set()
+for x in s:
+    pass
+b = 1 in s
+u = s.union([3])
+"#,
+        expect![[r#"
+            1..2 "s": set[int]
+            5..8 "set": def set(x0: Iterable[Any] = None) -> set[Unknown]
+            10..11 "1": Literal[1]
+            13..14 "2": Literal[2]
+            9..15 "[1, 2]": list[int]
+            5..16 "set([1, 2])": set[int]
+            17..18 "t": set[Unknown]
+            21..24 "set": def set(x0: Iterable[Any] = None) -> set[Unknown]
+            21..26 "set()": set[Unknown]
+            31..32 "x": int
+            36..37 "s": set[int]
+            48..49 "b": bool
+            52..53 "1": Literal[1]
+            57..58 "s": set[int]
+            52..58 "1 in s": bool
+            59..60 "u": set[int]
+            63..64 "s": set[int]
+            63..70 "s.union": def union(x0: Any) -> set[int]
+            72..73 "3": Literal[3]
+            71..74 "[3]": list[int]
+            63..75 "s.union([3])": set[int]
+        "#]],
+    );
+}
+
+#[test]
+fn test_conversion_intrinsics() {
+    check_infer(
+        r#"
+a = list([1, 2])
+b = list((1, "a"))
+c = list()
+d = tuple([True])
+e = dict([("a", 1)])
+f = dict({"a": 1}, b = "x")
+g = int("5")
+h = int("ff", 16)
+i = int([])
+"#,
+        expect![[r#"
+            1..2 "a": list[int]
+            5..9 "list": def list(x0: Iterable['0] = None) -> list['0]
+            11..12 "1": Literal[1]
+            14..15 "2": Literal[2]
+            10..16 "[1, 2]": list[int]
+            5..17 "list([1, 2])": list[int]
+            18..19 "b": list[int | string]
+            22..26 "list": def list(x0: Iterable['0] = None) -> list['0]
+            28..29 "1": Literal[1]
+            31..34 "\"a\"": Literal["a"]
+            27..35 "(1, \"a\")": tuple[Literal[1], Literal["a"]]
+            22..36 "list((1, \"a\"))": list[int | string]
+            37..38 "c": list[Unknown]
+            41..45 "list": def list(x0: Iterable['0] = None) -> list['0]
+            41..47 "list()": list[Unknown]
+            48..49 "d": tuple[bool, ...]
+            52..57 "tuple": def tuple(x0: Iterable['0] = None) -> tuple['0, ...]
+            59..63 "True": Literal[True]
+            58..64 "[True]": list[bool]
+            52..65 "tuple([True])": tuple[bool, ...]
+            66..67 "e": dict[string, int]
+            70..74 "dict": def dict(x0: dict[Unknown, Unknown] | Iterable[Iterable[Any]] = None, **kwargs) -> dict[Unknown, Unknown]
+            77..80 "\"a\"": Literal["a"]
+            82..83 "1": Literal[1]
+            76..84 "(\"a\", 1)": tuple[Literal["a"], Literal[1]]
+            75..85 "[(\"a\", 1)]": list[tuple[Literal["a"], Literal[1]]]
+            70..86 "dict([(\"a\", 1)])": dict[string, int]
+            87..88 "f": dict[string, int | string]
+            91..95 "dict": def dict(x0: dict[Unknown, Unknown] | Iterable[Iterable[Any]] = None, **kwargs) -> dict[Unknown, Unknown]
+            97..100 "\"a\"": Literal["a"]
+            102..103 "1": Literal[1]
+            96..104 "{\"a\": 1}": dict[string, int]
+            110..113 "\"x\"": Literal["x"]
+            91..114 "dict({\"a\": 1}, b = \"x\")": dict[string, int | string]
+            115..116 "g": int
+            119..122 "int": def int(x0: string | bool | int | float, x1: int = None) -> int
+            123..126 "\"5\"": Literal["5"]
+            119..127 "int(\"5\")": int
+            128..129 "h": int
+            132..135 "int": def int(x0: string | bool | int | float, x1: int = None) -> int
+            136..140 "\"ff\"": Literal["ff"]
+            142..144 "16": Literal[16]
+            132..145 "int(\"ff\", 16)": int
+            146..147 "i": int
+            150..153 "int": def int(x0: string | bool | int | float, x1: int = None) -> int
+            154..156 "[]": list[Unknown]
+            150..157 "int([])": int
+
+            154..156 Argument of type "list[Unknown]" cannot be assigned to parameter of type "string | bool | int | float"
+        "#]],
+    );
+}
+
+#[test]
+fn test_depset() {
+    check_infer(
+        r#"
+d = depset([1, 2])
+e = depset(transitive = [d])
+l = e.to_list()
+"#,
+        expect![[r#"
+            1..2 "d": depset[int]
+            5..11 "depset": def depset(*args, **kwargs) -> Unknown
+            13..14 "1": Literal[1]
+            16..17 "2": Literal[2]
+            12..18 "[1, 2]": list[int]
+            5..19 "depset([1, 2])": depset[int]
+            20..21 "e": depset[int]
+            24..30 "depset": def depset(*args, **kwargs) -> Unknown
+            45..46 "d": depset[int]
+            44..47 "[d]": list[depset[int]]
+            24..48 "depset(transitive = [d])": depset[int]
+            49..50 "l": list[int]
+            53..54 "e": depset[int]
+            53..62 "e.to_list": def to_list() -> list[int]
+            53..64 "e.to_list()": list[int]
+        "#]],
+    );
+}
+
+#[test]
+fn test_string_bytes_dict_iteration() {
+    check_infer(
+        r#"
+for c in "ab":
+    c
+for b in b"ab":
+    b
+d = {"a": 1}
+for k in d:
+    k
+"#,
+        expect![[r#"
+            5..6 "c": string
+            10..14 "\"ab\"": Literal["ab"]
+            20..21 "c": string
+            26..27 "b": int
+            31..36 "b\"ab\"": bytes
+            42..43 "b": int
+            44..45 "d": dict[string, int]
+            49..52 "\"a\"": Literal["a"]
+            54..55 "1": Literal[1]
+            48..56 "{\"a\": 1}": dict[string, int]
+            61..62 "k": string
+            66..67 "d": dict[string, int]
+            73..74 "k": string
+        "#]],
+    );
+}
+
+#[test]
+fn test_for_nested_targets() {
+    check_infer(
+        r#"
+pairs = [((1, "a"), True)]
+for (a, b), c in pairs:
+    a
+    c
+for (x, y) in [1]:
+    x
+"#,
+        expect![[r#"
+            1..6 "pairs": list[tuple[tuple[Literal[1], Literal["a"]], Literal[True]]]
+            12..13 "1": Literal[1]
+            15..18 "\"a\"": Literal["a"]
+            11..19 "(1, \"a\")": tuple[Literal[1], Literal["a"]]
+            21..25 "True": Literal[True]
+            10..26 "((1, \"a\"), True)": tuple[tuple[Literal[1], Literal["a"]], Literal[True]]
+            9..27 "[((1, \"a\"), True)]": list[tuple[tuple[Literal[1], Literal["a"]], Literal[True]]]
+            33..34 "a": Literal[1]
+            36..37 "b": Literal["a"]
+            32..38 "(a, b)": tuple[Literal[1], Literal["a"]]
+            40..41 "c": Literal[True]
+            45..50 "pairs": list[tuple[tuple[Literal[1], Literal["a"]], Literal[True]]]
+            56..57 "a": Literal[1]
+            62..63 "c": Literal[True]
+            69..70 "x": Unknown
+            72..73 "y": Unknown
+            68..74 "(x, y)": tuple[Unknown, Unknown]
+            79..80 "1": Literal[1]
+            78..81 "[1]": list[int]
+            87..88 "x": Unknown
+
+            78..81 Type "int" is not iterable
+        "#]],
+    );
+}
+
+#[test]
+fn test_for_shadowing_outer_binding() {
+    check_infer(
+        r#"
+_x = 0
+x = 1
+
+def f(y):
+    for x in [1]:
+        pass
+    for y in [2]:
+        pass
+    for _x, z in [(1, 2)]:
+        def g():
+            for z in [3]:
+                pass
+"#,
+        expect![[r#"
+            1..3 "_x": Literal[0]
+            6..7 "0": Literal[0]
+            8..9 "x": Literal[1]
+            12..13 "1": Literal[1]
+            33..34 "x": int
+            39..40 "1": Literal[1]
+            38..41 "[1]": list[int]
+            64..65 "y": int
+            70..71 "2": Literal[2]
+            69..72 "[2]": list[int]
+            95..97 "_x": Literal[1]
+            99..100 "z": Literal[2]
+            106..107 "1": Literal[1]
+            109..110 "2": Literal[2]
+            105..111 "(1, 2)": tuple[Literal[1], Literal[2]]
+            104..112 "[(1, 2)]": list[tuple[Literal[1], Literal[2]]]
+            147..148 "z": int
+            153..154 "3": Literal[3]
+            152..155 "[3]": list[int]
+
+            33..34 Loop variable "x" shadows an outer binding
+            147..148 Loop variable "z" shadows an outer binding
+        "#]],
+    );
+}
+
+#[test]
+fn test_membership() {
+    check_infer(
+        r#"
+1 in {"a": 2}
+"a" in {"a": 2}
+"a" in [1]
+"b" in ("a", "c")
+1 in "abc"
+"#,
+        expect![[r#"
+            1..2 "1": Literal[1]
+            7..10 "\"a\"": Literal["a"]
+            12..13 "2": Literal[2]
+            6..14 "{\"a\": 2}": dict[string, int]
+            1..14 "1 in {\"a\": 2}": bool
+            15..18 "\"a\"": Literal["a"]
+            23..26 "\"a\"": Literal["a"]
+            28..29 "2": Literal[2]
+            22..30 "{\"a\": 2}": dict[string, int]
+            15..30 "\"a\" in {\"a\": 2}": bool
+            31..34 "\"a\"": Literal["a"]
+            39..40 "1": Literal[1]
+            38..41 "[1]": list[int]
+            31..41 "\"a\" in [1]": bool
+            42..45 "\"b\"": Literal["b"]
+            50..53 "\"a\"": Literal["a"]
+            55..58 "\"c\"": Literal["c"]
+            49..59 "(\"a\", \"c\")": tuple[Literal["a"], Literal["c"]]
+            42..59 "\"b\" in (\"a\", \"c\")": bool
+            60..61 "1": Literal[1]
+            65..70 "\"abc\"": Literal["abc"]
+            60..70 "1 in \"abc\"": bool
+
+            1..14 Type "Literal[1]" can never be a key of type "dict[string, int]"
+            31..41 Type "Literal["a"]" can never be an element of type "list[int]"
+            60..70 Type "Literal[1]" can never be a substring of type "Literal["abc"]"
+        "#]],
+    );
+}
+
+#[test]
+fn test_range_iteration() {
+    check_infer(
+        r#"
+for i in range(3):
+    i
+[j for j in range(2)]
+"#,
+        expect![[r#"
+            5..6 "i": int
+            10..15 "range": def range(x0: int, x1: int = None, x2: int = None) -> range
+            16..17 "3": Literal[3]
+            10..18 "range(3)": range
+            24..25 "i": int
+            27..28 "j": int
+            33..34 "j": int
+            38..43 "range": def range(x0: int, x1: int = None, x2: int = None) -> range
+            44..45 "2": Literal[2]
+            38..46 "range(2)": range
+            26..47 "[j for j in range(2)]": list[int]
+        "#]],
+    );
+}
+
+#[test]
+fn test_list_comprehension() {
+    check_infer(
+        r#"
+[x * 2 for x in [1, 2, 3]]
+[a + b for a in [1] for b in [2] if a]
+"#,
+        expect![[r#"
+            2..3 "x": int
+            6..7 "2": Literal[2]
+            2..7 "x * 2": int
+            12..13 "x": int
+            18..19 "1": Literal[1]
+            21..22 "2": Literal[2]
+            24..25 "3": Literal[3]
+            17..26 "[1, 2, 3]": list[int]
+            1..27 "[x * 2 for x in [1, 2, 3]]": list[int]
+            29..30 "a": int
+            33..34 "b": int
+            29..34 "a + b": int
+            39..40 "a": int
+            45..46 "1": Literal[1]
+            44..47 "[1]": list[int]
+            52..53 "b": int
+            58..59 "2": Literal[2]
+            57..60 "[2]": list[int]
+            64..65 "a": int
+            28..66 "[a + b for a in [1] for b in [2] if a]": list[int]
+        "#]],
+    );
+}
+
+#[test]
+fn test_dict_comprehension() {
+    check_infer(
+        r#"
+{k: len(k) for k in ["a"]}
+{k: v for k, v in [("a", 1)]}
+"#,
+        expect![[r#"
+            2..3 "k": string
+            5..8 "len": def len(x0: Any) -> int
+            9..10 "k": string
+            5..11 "len(k)": int
+            16..17 "k": string
+            22..25 "\"a\"": Literal["a"]
+            21..26 "[\"a\"]": list[string]
+            1..27 "{k: len(k) for k in [\"a\"]}": dict[string, int]
+            29..30 "k": Literal["a"]
+            32..33 "v": Literal[1]
+            38..39 "k": Literal["a"]
+            41..42 "v": Literal[1]
+            48..51 "\"a\"": Literal["a"]
+            53..54 "1": Literal[1]
+            47..55 "(\"a\", 1)": tuple[Literal["a"], Literal[1]]
+            46..56 "[(\"a\", 1)]": list[tuple[Literal["a"], Literal[1]]]
+            28..57 "{k: v for k, v in [(\"a\", 1)]}": dict[string, int]
+        "#]],
+    );
+}
+
+#[test]
+fn test_comprehension_dict_items_unpacking() {
+    check_infer(
+        r#"
+d = {"a": 1}
+[k for k, v in d.items()]
+{v: k for k, v in d.items()}
+"#,
+        expect![[r#"
+            1..2 "d": dict[string, int]
+            6..9 "\"a\"": Literal["a"]
+            11..12 "1": Literal[1]
+            5..13 "{\"a\": 1}": dict[string, int]
+            15..16 "k": string
+            21..22 "k": string
+            24..25 "v": int
+            29..30 "d": dict[string, int]
+            29..36 "d.items": def items() -> list[tuple[string, int]]
+            29..38 "d.items()": list[tuple[string, int]]
+            14..39 "[k for k, v in d.items()]": list[string]
+            41..42 "v": int
+            44..45 "k": string
+            50..51 "k": string
+            53..54 "v": int
+            58..59 "d": dict[string, int]
+            58..65 "d.items": def items() -> list[tuple[string, int]]
+            58..67 "d.items()": list[tuple[string, int]]
+            40..68 "{v: k for k, v in d.items()}": dict[int, string]
+        "#]],
+    );
+}
+
+fn infer_all_expr_types(tcx: &mut TyCtxt, file: File) -> Vec<Ty> {
+    module(tcx.db, file)
+        .exprs
+        .iter()
+        .map(|(expr, _)| tcx.infer_expr(file, expr))
+        .collect()
+}
+
+#[test]
+fn test_cancel_during_inference() {
+    let mut db = TestDatabaseBuilder::default().build();
+    let file_id = FileId(0);
+    let file = db.create_file(file_id, Dialect::Standard, None, "x = 1\n".to_string());
+    let gcx = db.gcx.clone();
+    let display_all = |db: &TestDatabase, tys: Vec<Ty>| {
+        tys.iter()
+            .map(|ty| ty.display(db).to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // Inference must bail out instead of running to completion while a change is pending.
+    let guard = gcx.cancel();
+    let res = Cancelled::catch(AssertUnwindSafe(|| {
+        gcx.with_tcx(&db, |tcx| infer_all_expr_types(tcx, file))
+    }));
+    assert!(matches!(res, Err(Cancelled::Typecheck(_))));
+    drop(guard);
+
+    let tys = gcx.with_tcx(&db, |tcx| infer_all_expr_types(tcx, file));
+    assert!(display_all(&db, tys).contains(&"Literal[1]".to_string()));
+
+    // Simulate an edit. Once the guard is released, the stale results are gone and
+    // inference picks up the new contents.
+    let guard = gcx.cancel();
+    db.update_file(file_id, "x = \"one\"\n".to_string());
+    drop(guard);
+
+    let tys = display_all(
+        &db,
+        gcx.with_tcx(&db, |tcx| infer_all_expr_types(tcx, file)),
+    );
+    assert!(tys.contains(&"Literal[\"one\"]".to_string()));
+    assert!(!tys.contains(&"Literal[1]".to_string()));
+}
+
+#[test]
+fn test_common_type_infers_every_element() {
+    let mut db = TestDatabaseBuilder::default().build();
+    let file = db.create_file(
+        FileId(0),
+        Dialect::Standard,
+        None,
+        "[1, \"a\", 2, 3]\n".to_string(),
+    );
+    let (list_expr, elements) = module(&db, file)
+        .exprs
+        .iter()
+        .find_map(|(expr, data)| match data {
+            Expr::List { exprs } => Some((expr, exprs.clone())),
+            _ => None,
+        })
+        .unwrap();
+
+    // The elements after the mismatch must have their types recorded as well.
+    db.gcx.with_tcx(&db, |tcx| {
+        tcx.infer_expr(file, list_expr);
+        for expr in elements.iter() {
+            assert!(tcx
+                .cx
+                .type_of_expr
+                .keys()
+                .any(|key| key.file == file && key.expr == *expr));
+        }
+    });
+}
+
+#[test]
+fn test_max_inference_depth() {
+    let mut builder = TestDatabaseBuilder::default();
+    builder.set_inference_options(InferenceOptions {
+        max_inference_depth: Some(16),
+        ..Default::default()
+    });
+    let mut db = builder.build();
+    let depth = 64;
+    let file = db.create_file(
+        FileId(0),
+        Dialect::Standard,
+        None,
+        format!("x = {}1{}\n", "[".repeat(depth), "]".repeat(depth)),
+    );
+    let source_map = source_map(&db, file);
+    let outermost_expr = source_map
+        .expr_map
+        .iter()
+        .max_by_key(|(ptr, _)| ptr.syntax_node_ptr().text_range().len())
+        .map(|(_, expr)| *expr)
+        .unwrap();
+
+    // Inference stops at the limit instead of recursing through every nested list.
+    db.gcx.with_tcx(&db, |tcx| {
+        tcx.infer_expr(file, outermost_expr);
+        let diagnostics = tcx.diagnostics_for_file(file);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Expression is too deeply nested to infer its type"
+        );
+    });
+}
+
+#[test]
+fn test_infer_records_sub_expr_types() {
+    let mut db = TestDatabaseBuilder::default().build();
+    let file = db.create_file(
+        FileId(0),
+        Dialect::Standard,
+        None,
+        "[{\"a\": \"b\"}][0].get(\"a\").upper()[1:]\n".to_string(),
+    );
+    let source_map = source_map(&db, file);
+    let outermost_expr = source_map
+        .expr_map
+        .iter()
+        .max_by_key(|(ptr, _)| ptr.syntax_node_ptr().text_range().len())
+        .map(|(_, expr)| *expr)
+        .unwrap();
+
+    // Inferring the whole chain records the types of its sub-expressions, so that they're
+    // available to hovers and completions without being inferred again.
+    db.gcx.with_tcx(&db, |tcx| {
+        tcx.infer_expr(file, outermost_expr);
+        for (expr, _) in module(&db, file).exprs.iter() {
+            assert!(tcx
+                .cx
+                .type_of_expr
+                .keys()
+                .any(|key| key.file == file && key.expr == expr));
+        }
+    });
+}
+
+#[test]
+fn test_try_infer_all_exprs() {
+    let mut db = TestDatabaseBuilder::default().build();
+    let file = db.create_file(
+        FileId(0),
+        Dialect::Standard,
+        None,
+        "x = 1 + \"\"\ny = 2 + \"\"\n".to_string(),
+    );
+    let gcx = db.gcx.clone();
+    let root = parse(&db, file).syntax(&db);
+    let first_expr = source_map(&db, file)
+        .expr_map
+        .iter()
+        .find(|(ptr, _)| ptr.to_node(&root).syntax().text().to_string() == "1 + \"\"")
+        .map(|(_, expr)| *expr)
+        .unwrap();
+
+    // Inferring only part of the file collects one of its two diagnostics.
+    gcx.with_tcx(&db, |tcx| {
+        tcx.infer_expr(file, first_expr);
+        assert_eq!(tcx.diagnostics_for_file(file).len(), 1);
+    });
+
+    // Cancellation is reported as an error and the partial diagnostics are discarded. The
+    // context can still be used afterwards.
+    let guard = gcx.cancel();
+    gcx.with_tcx(&db, |tcx| {
+        assert!(matches!(
+            tcx.try_infer_all_exprs(file),
+            Err(Cancelled::Typecheck(_))
+        ));
+        assert!(tcx.diagnostics_for_file(file).is_empty());
+        assert_eq!(tcx.inference_depth, 0);
+    });
+    drop(guard);
+
+    gcx.with_tcx(&db, |tcx| {
+        assert!(tcx.try_infer_all_exprs(file).is_ok());
+        assert_eq!(tcx.diagnostics_for_file(file).len(), 2);
+    });
+}
+
+#[test]
+fn test_cancel_for_files_keeps_unaffected_results() {
+    let mut db = TestDatabaseBuilder::default().build();
+    let file_a = db.create_file(FileId(0), Dialect::Standard, None, "x = 1\n".to_string());
+    let file_b = db.create_file(FileId(1), Dialect::Standard, None, "y = 2\n".to_string());
+    let gcx = db.gcx.clone();
+    let has_results_for = |db: &TestDatabase, file: File| {
+        gcx.with_tcx(db, |tcx| {
+            tcx.cx.type_of_expr.keys().any(|expr| expr.file == file)
+        })
+    };
+
+    gcx.with_tcx(&db, |tcx| {
+        infer_all_expr_types(tcx, file_a);
+        infer_all_expr_types(tcx, file_b);
+    });
+
+    // Only the results for the edited file are discarded.
+    let guard = gcx.cancel_for_files(vec![file_a]);
+    db.update_file(FileId(0), "x = \"one\"\n".to_string());
+    drop(guard);
+    assert!(!has_results_for(&db, file_a));
+    assert!(has_results_for(&db, file_b));
+
+    let tys = gcx.with_tcx(&db, |tcx| infer_all_expr_types(tcx, file_a));
+    assert!(tys
+        .iter()
+        .any(|ty| ty.display(&db).to_string() == "Literal[\"one\"]"));
+}
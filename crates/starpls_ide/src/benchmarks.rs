@@ -0,0 +1,168 @@
+//! Benchmarks for analyzing large files. These are ignored by default, and can be run with
+//! `cargo test --release -p starpls_ide benchmark -- --ignored --nocapture`.
+
+use std::{fmt::Write, sync::Arc, time::Instant};
+
+use rustc_hash::FxHashMap;
+use starpls_bazel::{APIContext, Builtins};
+use starpls_common::{Dialect, FileId, FileInfo};
+use starpls_syntax::TextSize;
+use starpls_test_util::make_test_builtins;
+
+use crate::{Analysis, Change, FilePosition, SimpleFileLoader};
+
+/// Creates an analysis for the given `.bzl` files. The files are assigned IDs in order.
+fn analysis_with_files(files: &[(&str, &str)]) -> Analysis {
+    let mut file_set = FxHashMap::default();
+    let mut change = Change::default();
+    for (index, (path, contents)) in files.iter().enumerate() {
+        let file_id = FileId(index as u32);
+        file_set.insert(path.to_string(), (file_id, contents.to_string()));
+        change.create_file(
+            file_id,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+            contents.to_string(),
+        );
+    }
+    let mut analysis = Analysis::new(
+        Arc::new(SimpleFileLoader::from_file_set(file_set)),
+        Default::default(),
+    );
+    analysis.set_builtin_defs(
+        make_test_builtins(vec![], vec![], vec![]),
+        Builtins::default(),
+    );
+    analysis.apply_change(change);
+    analysis
+}
+
+/// Runs `f` and prints how long it took.
+fn measure<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let res = f();
+    println!("{}: {:?}", label, start.elapsed());
+    res
+}
+
+/// Computes the diagnostics for the given file, which infers the types of all of its
+/// expressions.
+fn diagnostics(analysis: &Analysis, file_id: FileId) -> usize {
+    analysis.snapshot().diagnostics(file_id).unwrap().len()
+}
+
+/// Generates a module with the given number of functions, named `f0`, `f1`, and so on.
+fn functions(count: usize) -> String {
+    let mut contents = String::new();
+    for i in 0..count {
+        write!(
+            contents,
+            "def f{}(x):\n    y = [x, {}]\n    z = {{\"a\": y}}\n    return z[\"a\"][0]\n\n",
+            i, i
+        )
+        .unwrap();
+    }
+    contents
+}
+
+#[test]
+#[ignore]
+fn benchmark_one_character_edit() {
+    let contents = functions(500);
+    let mut analysis = analysis_with_files(&[("edited.bzl", &contents), ("other.bzl", &contents)]);
+    let edited_file = FileId(0);
+    let other_file = FileId(1);
+    measure("initial inference", || {
+        diagnostics(&analysis, edited_file) + diagnostics(&analysis, other_file)
+    });
+
+    let mut change = Change::default();
+    change.update_file(edited_file, contents.replacen("[x, 0]", "[x, 1]", 1));
+    analysis.apply_change(change);
+    measure("edited file after a one-character edit", || {
+        diagnostics(&analysis, edited_file)
+    });
+    measure("other file after a one-character edit", || {
+        diagnostics(&analysis, other_file)
+    });
+}
+
+#[test]
+#[ignore]
+fn benchmark_large_list_literal() {
+    // The first element doesn't match the others, so a common element type can't be found.
+    let mut contents = String::from("x = [\"a\"");
+    for i in 1..10_000 {
+        write!(contents, ", {}", i).unwrap();
+    }
+    contents.push_str("]\n");
+    let analysis = analysis_with_files(&[("list.bzl", &contents)]);
+    measure("inference of a list literal with 10000 elements", || {
+        diagnostics(&analysis, FileId(0))
+    });
+}
+
+#[test]
+#[ignore]
+fn benchmark_large_file() {
+    let mut contents = String::new();
+    for i in 0..3000 {
+        write!(
+            contents,
+            "a{} = {{\"k\": [{}, \"v\"]}}\nb{} = a{}[\"k\"]\nc{}, d{} = b{}[0], b{}[1]\n",
+            i, i, i, i, i, i, i, i
+        )
+        .unwrap();
+    }
+    contents.push_str(&functions(1000));
+    let analysis = analysis_with_files(&[("large.bzl", &contents)]);
+    measure(
+        "inference of a file with 9000 assignments and 1000 functions",
+        || diagnostics(&analysis, FileId(0)),
+    );
+}
+
+#[test]
+#[ignore]
+fn benchmark_list_completion() {
+    let contents = "x = [1, 2]\nx.";
+    let analysis = analysis_with_files(&[("completion.bzl", contents)]);
+    let snap = analysis.snapshot();
+    let pos = FilePosition {
+        file_id: FileId(0),
+        pos: TextSize::of(contents),
+    };
+    let complete = || {
+        snap.completion(pos.clone(), Some(".".to_string()))
+            .unwrap()
+            .map_or(0, |items| items.len())
+    };
+    measure("first completion on a list", complete);
+    measure("1000 repeated completions on a list", || {
+        for _ in 0..1000 {
+            complete();
+        }
+    });
+}
+
+#[test]
+#[ignore]
+fn benchmark_edit_one_function() {
+    let contents = functions(50);
+    let mut analysis = analysis_with_files(&[("functions.bzl", &contents)]);
+    let file_id = FileId(0);
+    measure("initial inference of 50 functions", || {
+        diagnostics(&analysis, file_id)
+    });
+
+    // Only the body of `f25` changes.
+    let mut change = Change::default();
+    change.update_file(file_id, contents.replacen("[x, 25]", "[x, \"25\"]", 1));
+    analysis.apply_change(change);
+    measure("inference after editing one of 50 functions", || {
+        diagnostics(&analysis, file_id)
+    });
+}
@@ -0,0 +1,310 @@
+use starpls_common::{parse, Db as _, File, FileId};
+use starpls_hir::{Callable, Name, ScopeDef, Semantics};
+use starpls_syntax::{
+    ast::{self, AstNode, SyntaxNodePtr},
+    SyntaxNode, TextRange, TextSize, T,
+};
+
+use crate::{util::pick_best_token, Database, FilePosition, SymbolKind};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub file_id: FileId,
+    pub range: TextRange,
+    pub selection_range: TextRange,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IncomingCall {
+    pub from: CallHierarchyItem,
+    pub from_ranges: Vec<TextRange>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutgoingCall {
+    pub to: CallHierarchyItem,
+    pub from_ranges: Vec<TextRange>,
+}
+
+pub(crate) fn prepare_call_hierarchy(
+    db: &Database,
+    FilePosition { file_id, pos }: FilePosition,
+) -> Option<Vec<CallHierarchyItem>> {
+    let file = db.get_file(file_id)?;
+    let def_stmt = def_stmt_at(db, file, pos)?;
+    Some(vec![item_for_def_stmt(file_id, &def_stmt)?])
+}
+
+/// Finds the calls to the function at the given position. Only calls from the same file are
+/// considered.
+pub(crate) fn incoming_calls(
+    db: &Database,
+    FilePosition { file_id, pos }: FilePosition,
+) -> Option<Vec<IncomingCall>> {
+    let sema = Semantics::new(db);
+    let file = db.get_file(file_id)?;
+    let def_stmt = def_stmt_at(db, file, pos)?;
+    let def_ptr = SyntaxNodePtr::new(def_stmt.syntax());
+    let root = parse(db, file).syntax(db);
+    let mut calls: Vec<IncomingCall> = Vec::new();
+
+    for call in root.descendants().filter_map(ast::CallExpr::cast) {
+        let callee = match call.callee() {
+            Some(callee) => callee,
+            None => continue,
+        };
+        let is_target = sema
+            .resolve_call_expr(file, &call)
+            .and_then(|callable| def_ptr_for_callable(db, file, callable))
+            .map_or(false, |ptr| ptr == def_ptr);
+        if !is_target {
+            continue;
+        }
+
+        // Calls at the top level of the module are attributed to the module itself.
+        let from = match enclosing_def_stmt(call.syntax()) {
+            Some(caller) => match item_for_def_stmt(file_id, &caller) {
+                Some(item) => item,
+                None => continue,
+            },
+            None => CallHierarchyItem {
+                name: "<module>".to_string(),
+                kind: SymbolKind::File,
+                file_id,
+                range: root.text_range(),
+                selection_range: TextRange::empty(root.text_range().start()),
+            },
+        };
+        let range = callee.syntax().text_range();
+        match calls.iter_mut().find(|call| call.from == from) {
+            Some(call) => call.from_ranges.push(range),
+            None => calls.push(IncomingCall {
+                from,
+                from_ranges: vec![range],
+            }),
+        }
+    }
+
+    Some(calls)
+}
+
+/// Finds the calls made directly from the body of the function at the given position, excluding
+/// calls made from nested functions. Only calls to functions defined in the current file are
+/// included, since builtins don't have a location to point to.
+pub(crate) fn outgoing_calls(
+    db: &Database,
+    FilePosition { file_id, pos }: FilePosition,
+) -> Option<Vec<OutgoingCall>> {
+    let sema = Semantics::new(db);
+    let file = db.get_file(file_id)?;
+    let def_stmt = def_stmt_at(db, file, pos)?;
+    let root = parse(db, file).syntax(db);
+    let mut calls: Vec<OutgoingCall> = Vec::new();
+
+    for call in def_stmt
+        .syntax()
+        .descendants()
+        .filter_map(ast::CallExpr::cast)
+        .filter(|call| enclosing_def_stmt(call.syntax()).as_ref() == Some(&def_stmt))
+    {
+        let (callee, callable) = match (call.callee(), sema.resolve_call_expr(file, &call)) {
+            (Some(callee), Some(callable)) => (callee, callable),
+            _ => continue,
+        };
+        if !callable.is_user_defined() {
+            continue;
+        }
+
+        // Functions defined in other files aren't supported yet.
+        let to = match def_ptr_for_callable(db, file, callable)
+            .and_then(|ptr| ptr.try_to_node(&root))
+            .and_then(ast::DefStmt::cast)
+            .and_then(|def_stmt| item_for_def_stmt(file_id, &def_stmt))
+        {
+            Some(item) => item,
+            None => continue,
+        };
+        let range = callee.syntax().text_range();
+        match calls.iter_mut().find(|call| call.to == to) {
+            Some(call) => call.from_ranges.push(range),
+            None => calls.push(OutgoingCall {
+                to,
+                from_ranges: vec![range],
+            }),
+        }
+    }
+
+    Some(calls)
+}
+
+/// Finds the function declared or referenced at the given position. Only functions defined in
+/// the current file are supported.
+fn def_stmt_at(db: &Database, file: File, pos: TextSize) -> Option<ast::DefStmt> {
+    let root = parse(db, file).syntax(db);
+    let token = pick_best_token(root.token_at_offset(pos), |kind| match kind {
+        T![ident] => 2,
+        kind if kind.is_trivia_token() => 0,
+        _ => 1,
+    })?;
+    let parent = token.parent()?;
+    if let Some(name) = ast::Name::cast(parent.clone()) {
+        return name.syntax().parent().and_then(ast::DefStmt::cast);
+    }
+
+    let name_ref = ast::NameRef::cast(parent)?;
+    let scope = Semantics::new(db)
+        .scope_for_expr(file, &ast::Expression::cast(name_ref.syntax().clone())?)?;
+    match scope
+        .resolve_name(&Name::from_ast_node(name_ref))
+        .into_iter()
+        .next()?
+    {
+        ScopeDef::Callable(callable) => def_ptr_for_callable(db, file, callable)?
+            .try_to_node(&root)
+            .and_then(ast::DefStmt::cast),
+        _ => None,
+    }
+}
+
+fn def_ptr_for_callable(db: &Database, file: File, callable: Callable) -> Option<SyntaxNodePtr> {
+    let def = ScopeDef::Callable(callable);
+    if def.file(db) != Some(file) {
+        return None;
+    }
+    def.syntax_node_ptr(db, file)
+}
+
+fn enclosing_def_stmt(node: &SyntaxNode) -> Option<ast::DefStmt> {
+    node.ancestors().skip(1).find_map(ast::DefStmt::cast)
+}
+
+fn item_for_def_stmt(file_id: FileId, def_stmt: &ast::DefStmt) -> Option<CallHierarchyItem> {
+    let name = def_stmt.name()?;
+    Some(CallHierarchyItem {
+        name: name.name()?.text().to_string(),
+        kind: SymbolKind::Function,
+        file_id,
+        range: def_stmt.syntax().text_range(),
+        selection_range: name.syntax().text_range(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+    use starpls_test_util::parse_fixture;
+
+    use crate::{AnalysisSnapshot, FilePosition};
+
+    fn snapshot(fixture: &str) -> (String, AnalysisSnapshot, FilePosition) {
+        let (contents, pos, _) = parse_fixture(fixture);
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            &contents,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        (contents, snap, FilePosition { file_id, pos })
+    }
+
+    fn check_incoming(fixture: &str, expect: Expect) {
+        let (contents, snap, pos) = snapshot(fixture);
+        let items = snap.prepare_call_hierarchy(pos).unwrap().unwrap();
+        let pos = FilePosition {
+            file_id: pos.file_id,
+            pos: items[0].selection_range.start(),
+        };
+        let mut actual = String::new();
+        for call in snap.incoming_calls(pos).unwrap().unwrap() {
+            let ranges = call
+                .from_ranges
+                .iter()
+                .map(|range| format!("{:?}", range))
+                .collect::<Vec<_>>();
+            actual.push_str(&format!(
+                "{} {:?}: {}\n",
+                call.from.name,
+                &contents[call.from.selection_range],
+                ranges.join(", ")
+            ));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    fn check_outgoing(fixture: &str, expect: Expect) {
+        let (contents, snap, pos) = snapshot(fixture);
+        let mut actual = String::new();
+        for call in snap.outgoing_calls(pos).unwrap().unwrap() {
+            let ranges = call
+                .from_ranges
+                .iter()
+                .map(|range| format!("{:?}", range))
+                .collect::<Vec<_>>();
+            actual.push_str(&format!(
+                "{} {:?}: {}\n",
+                call.to.name,
+                &contents[call.to.selection_range],
+                ranges.join(", ")
+            ));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_incoming_calls() {
+        check_incoming(
+            r#"
+def f$0oo():
+    pass
+
+def bar():
+    foo()
+    foo()
+
+foo()
+"#,
+            expect![[r#"
+                bar "bar": 37..40, 47..50
+                <module> "": 54..57
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_outgoing_calls() {
+        check_outgoing(
+            r#"
+def foo():
+    pass
+
+def b$0ar():
+    foo()
+    print(1)
+    print(2)
+
+    def nested():
+        len([])
+"#,
+            expect![[r#"
+                foo "foo": 37..40
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_outgoing_calls_skips_builtins() {
+        check_outgoing(
+            r#"
+def f$0oo():
+    print(len([]))
+"#,
+            expect![""],
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use rustc_hash::FxHashSet;
+use starpls_common::{parse, Db as _, File, FileId};
+use starpls_hir::{Name, Semantics};
+use starpls_syntax::{
+    ast::{self, AstNode},
+    SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+
+use crate::{
+    formatting::load_item_key,
+    util::{is_valid_identifier, pick_best_token},
+    Database, TextEdit,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeAction {
+    pub title: String,
+    pub kind: CodeActionKind,
+    /// The edits to apply to the file that the code action was requested for.
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeActionKind {
+    QuickFix,
+    RefactorRewrite,
+}
+
+pub(crate) fn code_actions(
+    db: &Database,
+    file_id: FileId,
+    range: TextRange,
+) -> Option<Vec<CodeAction>> {
+    let file = db.get_file(file_id)?;
+    let root = parse(db, file).syntax(db);
+    let token = pick_best_token(root.token_at_offset(range.start()), |kind| {
+        if kind.is_trivia_token() {
+            0
+        } else {
+            1
+        }
+    })?;
+
+    let mut actions = Vec::new();
+    actions.extend(add_missing_load(db, file, &root, &token));
+    actions.extend(dict_to_struct(&token));
+    actions.extend(positional_to_keyword_args(db, file, &token));
+    Some(actions)
+}
+
+/// Loads an undefined name from a module that exports it. Modules loaded by the current file are
+/// tried first, followed by modules loaded by other files. Only absolute labels are taken from
+/// other files, since relative labels depend on the package of the file that loads them.
+fn add_missing_load(
+    db: &Database,
+    file: File,
+    root: &SyntaxNode,
+    token: &SyntaxToken,
+) -> Vec<CodeAction> {
+    let sema = Semantics::new(db);
+    let name_ref = match token.parent().and_then(ast::NameRef::cast) {
+        Some(name_ref) => name_ref,
+        None => return Vec::new(),
+    };
+    let is_undefined = ast::Expression::cast(name_ref.syntax().clone())
+        .and_then(|expr| sema.scope_for_expr(file, &expr))
+        .map_or(false, |scope| {
+            scope
+                .resolve_name(&Name::from_ast_node(name_ref.clone()))
+                .is_empty()
+        });
+    if !is_undefined {
+        return Vec::new();
+    }
+
+    let name = token.text();
+    let mut files = db
+        .files
+        .iter()
+        .map(|entry| *entry.value())
+        .filter(|other| *other != file)
+        .collect::<Vec<_>>();
+    files.sort_by_key(|file| file.id(db));
+
+    let mut modules = Vec::new();
+    for (loading_file, load_stmt) in load_stmts(root)
+        .into_iter()
+        .map(|load_stmt| (file, load_stmt))
+        .chain(files.into_iter().flat_map(|other| {
+            load_stmts(&parse(db, other).syntax(db))
+                .into_iter()
+                .map(move |load_stmt| (other, load_stmt))
+        }))
+    {
+        let module = match load_module(&load_stmt) {
+            Some(module) => module,
+            None => continue,
+        };
+        if modules.contains(&module)
+            || (loading_file != file && !(module.starts_with("//") || module.starts_with('@')))
+        {
+            continue;
+        }
+        let exports_name =
+            sema.resolve_load_stmt(loading_file, &load_stmt)
+                .map_or(false, |loaded_file| {
+                    sema.scope_for_module(loaded_file)
+                        .exports()
+                        .any(|(export, _)| export.as_str() == name)
+                });
+        if exports_name {
+            modules.push(module);
+        }
+    }
+
+    let load_stmts = load_stmts(root);
+    modules
+        .into_iter()
+        .map(|module| {
+            let edit = match load_stmts
+                .iter()
+                .find(|load_stmt| load_module(load_stmt).as_ref() == Some(&module))
+            {
+                Some(load_stmt) => extend_load_stmt(load_stmt, name),
+                None => insert_load_stmt(root, &load_stmts, &module, name),
+            };
+            CodeAction {
+                title: format!("Load `{}` from `{}`", name, module),
+                kind: CodeActionKind::QuickFix,
+                edits: vec![edit],
+            }
+        })
+        .collect()
+}
+
+fn load_stmts(root: &SyntaxNode) -> Vec<ast::LoadStmt> {
+    root.children().filter_map(ast::LoadStmt::cast).collect()
+}
+
+fn load_module(load_stmt: &ast::LoadStmt) -> Option<String> {
+    load_stmt
+        .module()?
+        .name()
+        .and_then(ast::String::cast)?
+        .value()
+        .map(|value| value.to_string())
+}
+
+/// Adds the name to an existing `load` statement, keeping its items in the order used by the
+/// formatter.
+fn extend_load_stmt(load_stmt: &ast::LoadStmt, name: &str) -> TextEdit {
+    let items = load_stmt.items().collect::<Vec<_>>();
+    match items
+        .iter()
+        .find(|item| load_item_key(item.syntax()).as_str() > name)
+    {
+        Some(item) => TextEdit {
+            range: TextRange::empty(item.syntax().text_range().start()),
+            new_text: format!("\"{}\", ", name),
+        },
+        None => {
+            let end = items
+                .last()
+                .map(|item| item.syntax().text_range().end())
+                .or_else(|| {
+                    load_stmt
+                        .module()
+                        .map(|module| module.syntax().text_range().end())
+                })
+                .unwrap_or_else(|| load_stmt.syntax().text_range().end());
+            TextEdit {
+                range: TextRange::empty(end),
+                new_text: format!(", \"{}\"", name),
+            }
+        }
+    }
+}
+
+/// Adds a new `load` statement after the existing ones, or at the top of the file after its
+/// docstring if there are none.
+fn insert_load_stmt(
+    root: &SyntaxNode,
+    load_stmts: &[ast::LoadStmt],
+    module: &str,
+    name: &str,
+) -> TextEdit {
+    let load_stmt = format!("load(\"{}\", \"{}\")", module, name);
+    if let Some(last) = load_stmts.last() {
+        return TextEdit {
+            range: TextRange::empty(last.syntax().text_range().end()),
+            new_text: format!("\n{}", load_stmt),
+        };
+    }
+
+    match ast::Module::cast(root.clone()).filter(|module| module.doc().is_some()) {
+        Some(module) => TextEdit {
+            range: TextRange::empty(
+                module
+                    .syntax()
+                    .first_child()
+                    .map_or(TextSize::new(0), |doc| doc.text_range().end()),
+            ),
+            new_text: format!("\n\n{}", load_stmt),
+        },
+        None => TextEdit {
+            range: TextRange::empty(TextSize::new(0)),
+            new_text: format!("{}\n\n", load_stmt),
+        },
+    }
+}
+
+/// Rewrites the innermost dict literal around the cursor as a `struct()` call, e.g.
+/// `{"a": 1, "b": 2}` as `struct(a = 1, b = 2)`. Every key must be a string literal that is a
+/// valid identifier and that isn't repeated.
+fn dict_to_struct(token: &SyntaxToken) -> Option<CodeAction> {
+    let dict_expr = token.parent_ancestors().find_map(ast::DictExpr::cast)?;
+    let mut keys = FxHashSet::default();
+    let mut args = Vec::new();
+    for entry in dict_expr.entries() {
+        let key = match entry.key()? {
+            ast::Expression::Literal(lit) => match lit.kind() {
+                ast::LiteralKind::String(s) => s.value()?,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        if !is_valid_identifier(&key) || !keys.insert(key.clone()) {
+            return None;
+        }
+        args.push(format!("{} = {}", key, entry.value()?.syntax().text()));
+    }
+
+    Some(CodeAction {
+        title: "Convert to `struct()`".to_string(),
+        kind: CodeActionKind::RefactorRewrite,
+        edits: vec![TextEdit {
+            range: dict_expr.syntax().text_range(),
+            new_text: format!("struct({})", args.join(", ")),
+        }],
+    })
+}
+
+/// Rewrites the positional arguments of the innermost call around the cursor as keyword
+/// arguments, e.g. `foo(1, 2)` as `foo(a = 1, b = 2)`. Only calls to user-defined functions are
+/// supported, and every positional argument must be bound to a named parameter rather than being
+/// absorbed by `*args`.
+fn positional_to_keyword_args(
+    db: &Database,
+    file: File,
+    token: &SyntaxToken,
+) -> Option<CodeAction> {
+    let sema = Semantics::new(db);
+    let call_expr = token.parent_ancestors().find_map(ast::CallExpr::cast)?;
+    let callable = sema.resolve_call_expr(file, &call_expr)?;
+    if !callable.is_user_defined() {
+        return None;
+    }
+
+    let params = callable.params(db);
+    let mut edits = Vec::new();
+    for (index, arg) in call_expr.arguments()?.arguments().enumerate() {
+        let arg = match arg {
+            ast::Argument::Simple(arg) => arg,
+            ast::Argument::Keyword(_) => continue,
+            _ => return None,
+        };
+        let (param, _) = params.get(index)?;
+        if param.is_args_list(db) || param.is_kwargs_dict(db) {
+            return None;
+        }
+        edits.push(TextEdit {
+            range: arg.syntax().text_range(),
+            new_text: format!("{} = {}", param.name(db)?.as_str(), arg.syntax().text()),
+        });
+    }
+
+    (!edits.is_empty()).then(|| CodeAction {
+        title: "Convert positional arguments to keyword arguments".to_string(),
+        kind: CodeActionKind::RefactorRewrite,
+        edits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use expect_test::{expect, Expect};
+    use rustc_hash::FxHashMap;
+    use starpls_bazel::{APIContext, Builtins};
+    use starpls_common::{Dialect, FileId, FileInfo};
+    use starpls_syntax::{TextRange, TextSize};
+    use starpls_test_util::{make_test_builtins, parse_fixture};
+
+    use crate::{Analysis, AnalysisSnapshot, Change, SimpleFileLoader};
+
+    fn check(fixture: &str, expect: Expect) {
+        let (contents, pos, _) = parse_fixture(fixture);
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            &contents,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        check_snapshot(snap, file_id, pos, expect);
+    }
+
+    /// Like `check`, but with `load`able files, and with other files that are already open.
+    fn check_with_files(
+        fixture: &str,
+        loadable_files: &[(&str, &str)],
+        open_files: &[&str],
+        expect: Expect,
+    ) {
+        let (contents, pos, _) = parse_fixture(fixture);
+        let file_id = FileId(0);
+        let mut file_set = FxHashMap::default();
+        let mut next_file_id = 1;
+        for (path, contents) in loadable_files {
+            file_set.insert(
+                path.to_string(),
+                (FileId(next_file_id), contents.to_string()),
+            );
+            next_file_id += 1;
+        }
+
+        let info = Some(FileInfo::Bazel {
+            api_context: APIContext::Bzl,
+            is_external: false,
+        });
+        let mut change = Change::default();
+        change.create_file(file_id, Dialect::Bazel, info.clone(), contents);
+        for contents in open_files {
+            change.create_file(
+                FileId(next_file_id),
+                Dialect::Bazel,
+                info.clone(),
+                contents.to_string(),
+            );
+            next_file_id += 1;
+        }
+
+        let mut analysis = Analysis::new(
+            Arc::new(SimpleFileLoader::from_file_set(file_set)),
+            Default::default(),
+        );
+        analysis.db.set_builtin_defs(
+            Dialect::Bazel,
+            make_test_builtins(vec![], vec![], vec![]),
+            Builtins::default(),
+        );
+        analysis.apply_change(change);
+        check_snapshot(analysis.snapshot(), file_id, pos, expect);
+    }
+
+    fn check_snapshot(snap: AnalysisSnapshot, file_id: FileId, pos: TextSize, expect: Expect) {
+        let mut actual = String::new();
+        for action in snap
+            .code_actions(file_id, TextRange::empty(pos))
+            .unwrap()
+            .unwrap()
+        {
+            actual.push_str(&format!("{} ({:?})\n", action.title, action.kind));
+            for edit in action.edits {
+                actual.push_str(&format!("{:?}: {:?}\n", edit.range, edit.new_text));
+            }
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_dict_to_struct() {
+        check(
+            r#"x = {"a": 1, "b$0": [2, 3]}"#,
+            expect![[r#"
+                Convert to `struct()` (RefactorRewrite)
+                4..25: "struct(a = 1, b = [2, 3])"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_dict_to_struct_invalid_key() {
+        check(r#"x = {"a": 1, "b-c$0": 2}"#, expect![""]);
+        check(r#"x = {"a": 1, "a$0": 2}"#, expect![""]);
+    }
+
+    #[test]
+    fn test_add_missing_load_to_existing_load() {
+        check_with_files(
+            r#"
+load("//:defs.bzl", "bar")
+
+f$0oo()
+"#,
+            &[(
+                "//:defs.bzl",
+                "def foo():\n    pass\n\ndef bar():\n    pass\n",
+            )],
+            &[],
+            expect![[r#"
+                Load `foo` from `//:defs.bzl` (QuickFix)
+                27..27: ", \"foo\""
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_add_missing_load_from_other_file() {
+        check_with_files(
+            r#"f$0oo()"#,
+            &[("//:defs.bzl", "def foo():\n    pass\n")],
+            &[
+                r#"load("//:defs.bzl", "foo")"#,
+                r#"load(":local.bzl", "foo")"#,
+            ],
+            expect![[r#"
+                Load `foo` from `//:defs.bzl` (QuickFix)
+                0..0: "load(\"//:defs.bzl\", \"foo\")\n\n"
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_positional_to_keyword_args() {
+        check(
+            r#"
+def foo(a, b, c = 1):
+    pass
+
+foo(1, "tw$0o", c = 3)
+"#,
+            expect![[r#"
+                Convert positional arguments to keyword arguments (RefactorRewrite)
+                37..38: "a = 1"
+                40..45: "b = \"two\""
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_positional_to_keyword_args_args_list() {
+        check(
+            r#"
+def foo(a, *args):
+    pass
+
+foo(1, 2$0)
+"#,
+            expect![""],
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use starpls_bazel::APIContext;
+use starpls_common::{parse, Db as _, FileId};
+use starpls_hir::Semantics;
+use starpls_syntax::{
+    ast::{self, AstNode},
+    TextRange,
+};
+
+use crate::Database;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeLens {
+    pub range: TextRange,
+    pub title: String,
+}
+
+/// Computes a lens for each function defined in a `.bzl` file, showing its signature above the
+/// definition. Functions without a return type annotation still get a lens ending in
+/// `-> Unknown`, so that lenses don't appear and disappear while the function is being edited.
+pub(crate) fn code_lenses(db: &Database, file_id: FileId) -> Option<Vec<CodeLens>> {
+    let sema = Semantics::new(db);
+    let file = db.get_file(file_id)?;
+    if file.api_context(db) != Some(APIContext::Bzl) {
+        return None;
+    }
+
+    let root = parse(db, file).syntax(db);
+    Some(
+        root.descendants()
+            .filter_map(ast::DefStmt::cast)
+            .filter_map(|def_stmt| {
+                let range = def_stmt.name()?.syntax().text_range();
+                let title = sema
+                    .callable_for_def(file, def_stmt)?
+                    .ty(db)
+                    .signature_string(db)?;
+                Some(CodeLens { range, title })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+
+    use crate::AnalysisSnapshot;
+
+    fn check(input: &str, expect: Expect) {
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            input,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let mut actual = String::new();
+        for lens in snap.code_lenses(file_id).unwrap().unwrap() {
+            actual.push_str(&format!(
+                "{:?} {:?}: {}\n",
+                lens.range, &input[lens.range], lens.title
+            ));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_code_lenses() {
+        check(
+            r#"def foo(x, y = "a"):
+    # type: (int, string) -> string
+    def bar():
+        pass
+    return y
+
+def baz(*args, **kwargs):
+    pass
+"#,
+            expect![[r#"
+                4..7 "foo": (x: int, y: string) -> string
+                65..68 "bar": () -> Unknown
+                103..106 "baz": (*args, **kwargs) -> Unknown
+            "#]],
+        );
+    }
+}
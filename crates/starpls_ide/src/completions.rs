@@ -2,7 +2,7 @@
 
 use rustc_hash::FxHashMap;
 use starpls_common::{parse, FileId, LoadItemCandidateKind};
-use starpls_hir::{Db, Name, Param, ScopeDef, Semantics, Type};
+use starpls_hir::{Db, DisplayWithDb, Name, Param, ScopeDef, Semantics, Type};
 use starpls_syntax::{
     ast::{self, AstNode, AstToken},
     parse_module,
@@ -24,6 +24,7 @@ pub struct CompletionItem {
     pub kind: CompletionItemKind,
     pub mode: Option<CompletionMode>,
     pub filter_text: Option<String>,
+    pub detail: Option<String>,
     relevance: CompletionRelevance,
 }
 
@@ -55,6 +56,8 @@ pub struct InsertReplaceEdit {
 #[derive(Debug)]
 pub enum CompletionMode {
     InsertText(String),
+    /// Like `InsertText`, but the text is a snippet that may contain tabstops like `$0`.
+    InsertSnippet(String),
     TextEdit(Edit),
 }
 
@@ -93,7 +96,7 @@ enum NameContext {
 
 struct NameRefContext {
     names: FxHashMap<Name, ScopeDef>,
-    params: Vec<Param>,
+    params: Vec<(Param, Type)>,
     is_in_def: bool,
     is_in_for: bool,
     is_lone_expr: bool,
@@ -137,15 +140,15 @@ pub(crate) fn completions(
             is_loop_variable,
         }) => {
             // Add completions for parameter names (excluding arg list and kwarg dict parameters).
-            for name in params
+            for (name, ty) in params
                 .iter()
-                .filter(|param| {
+                .filter(|(param, _)| {
                     !param.is_args_list(db)
                         && !param.is_kwargs_dict(db)
                         && !param.is_positional_only(db)
                 })
-                .filter_map(|param| match param.name(db) {
-                    Some(name) if !name.is_missing() => Some(name),
+                .filter_map(|(param, ty)| match param.name(db) {
+                    Some(name) if !name.is_missing() => Some((name, ty)),
                     _ => None,
                 })
             {
@@ -155,6 +158,7 @@ pub(crate) fn completions(
                     mode: Some(CompletionMode::InsertText(format!("{} = ", name.as_str()))),
                     relevance: CompletionRelevance::Parameter,
                     filter_text: None,
+                    detail: (!ty.is_unknown()).then(|| ty.display(db).to_string()),
                 });
             }
 
@@ -179,6 +183,7 @@ pub(crate) fn completions(
                             CompletionRelevance::Builtin
                         },
                         filter_text: None,
+                        detail: None,
                     });
                 }
 
@@ -188,17 +193,25 @@ pub(crate) fn completions(
             }
         }
         CompletionAnalysis::Name(NameContext::Dot { receiver_ty }) => {
-            for (name, ty) in receiver_ty.fields(db) {
+            for (field, ty) in receiver_ty.fields(db) {
+                let label = field.name(db).to_string();
+                let (kind, mode) = if ty.is_callable() {
+                    // Place the cursor between the parentheses of the method call.
+                    let snippet = format!("{}($0)", label);
+                    (
+                        CompletionItemKind::Function,
+                        Some(CompletionMode::InsertSnippet(snippet)),
+                    )
+                } else {
+                    (CompletionItemKind::Field, None)
+                };
                 items.push(CompletionItem {
-                    label: name.name(db).to_string(),
-                    kind: if ty.is_callable() {
-                        CompletionItemKind::Function
-                    } else {
-                        CompletionItemKind::Field
-                    },
-                    mode: None,
+                    label,
+                    kind,
+                    mode,
                     relevance: CompletionRelevance::VariableOrKeyword,
                     filter_text: None,
+                    detail: Some(ty.display(db).to_string()),
                 })
             }
         }
@@ -210,6 +223,7 @@ pub(crate) fn completions(
                     mode: None,
                     relevance: CompletionRelevance::VariableOrKeyword,
                     filter_text: None,
+                    detail: None,
                 })
             }
         }
@@ -258,6 +272,7 @@ pub(crate) fn completions(
                     mode: Some(CompletionMode::TextEdit(edit)),
                     relevance: CompletionRelevance::VariableOrKeyword,
                     filter_text,
+                    detail: None,
                 });
             }
         }
@@ -285,6 +300,7 @@ pub(crate) fn completions(
                     mode: None,
                     relevance: CompletionRelevance::VariableOrKeyword,
                     filter_text: None,
+                    detail: None,
                 });
             }
         }
@@ -300,6 +316,7 @@ pub(crate) fn completions(
                     mode: None,
                     relevance: CompletionRelevance::VariableOrKeyword,
                     filter_text: None,
+                    detail: None,
                 });
             }
         }
@@ -317,6 +334,7 @@ pub(crate) fn add_globals(items: &mut Vec<CompletionItem>) {
             mode: None,
             relevance: CompletionRelevance::VariableOrKeyword,
             filter_text: None,
+            detail: None,
         })
     };
     add_global("True");
@@ -332,6 +350,7 @@ fn add_keywords(items: &mut Vec<CompletionItem>, is_in_def: bool, is_in_for: boo
             mode: None,
             relevance: CompletionRelevance::VariableOrKeyword,
             filter_text: None,
+            detail: None,
         })
     };
     add_keyword("def");
@@ -415,10 +434,12 @@ impl CompletionContext {
 
         let analysis = if let Some(name_ref) = ast::NameRef::cast(parent.clone()) {
             // TODO(withered-magic): There's probably a better way to traverse up the tree.
-            let args = name_ref
+            let current_arg = name_ref
                 .syntax()
                 .parent()
-                .and_then(|parent| ast::SimpleArgument::cast(parent))
+                .and_then(|parent| ast::SimpleArgument::cast(parent));
+            let args = current_arg
+                .as_ref()
                 .and_then(|arg| arg.syntax().parent())
                 .and_then(|parent| ast::Arguments::cast(parent));
 
@@ -437,21 +458,37 @@ impl CompletionContext {
                 })
                 .unwrap_or_else(|| Vec::new());
 
+            // Parameters before `*args` are filled by the other positional arguments first.
+            let num_positional_args = args.as_ref().map_or(0, |args| {
+                args.arguments()
+                    .filter(|arg| match arg {
+                        ast::Argument::Simple(arg) => Some(arg) != current_arg.as_ref(),
+                        _ => false,
+                    })
+                    .count()
+            });
+
             let params = args
                 .and_then(|arg| arg.syntax().parent())
                 .and_then(|parent| ast::CallExpr::cast(parent))
                 .and_then(|expr| expr.callee())
                 .and_then(|expr| sema.type_of_expr(file, &expr))
                 .map(|ty| {
+                    let mut remaining_positional_args = num_positional_args;
                     ty.params(db)
                         .into_iter()
-                        .filter_map(|(param, _)| match param.name(db) {
-                            Some(name)
-                                if keyword_args.iter().all(|kwarg| kwarg != name.as_str()) =>
-                            {
-                                Some(param)
+                        .filter(|(param, _)| {
+                            if param.is_args_list(db) {
+                                remaining_positional_args = 0;
+                            } else if remaining_positional_args > 0 && !param.is_kwargs_dict(db) {
+                                remaining_positional_args -= 1;
+                                return false;
                             }
-                            _ => None,
+                            true
+                        })
+                        .filter(|(param, _)| match param.name(db) {
+                            Some(name) => keyword_args.iter().all(|kwarg| kwarg != name.as_str()),
+                            None => false,
                         })
                         .collect()
                 })
@@ -1,25 +1,57 @@
 use starpls_common::{Db, Diagnostic, FileId};
-use starpls_hir::diagnostics_for_file;
+use starpls_hir::{diagnostics_for_file, Cancelled};
 
 use crate::Database;
 
 pub(crate) fn diagnostics(db: &Database, file_id: FileId) -> Vec<Diagnostic> {
-    let file = match db.get_file(file_id) {
-        Some(file) => file,
-        None => return Vec::new(),
-    };
+    diagnostics_for_files(db, &[file_id])
+        .pop()
+        .map(|(_, diagnostics)| diagnostics)
+        .unwrap_or_default()
+}
 
-    let diagnostics = db.gcx.with_tcx(db, |tcx| {
-        tcx.infer_all_params(file);
-        tcx.infer_all_exprs(file);
-        tcx.infer_all_load_items(file);
-        tcx.diagnostics_for_file(file)
+/// Computes the diagnostics for each of the given files. All files are inferred with the same
+/// type context, so the types of modules that are loaded by several of the files are only
+/// inferred once. Files that don't exist are skipped.
+///
+/// If inference is cancelled, the cancellation is resumed once the type context is released, so
+/// that the query fails as a whole and callers don't publish partial diagnostics.
+pub(crate) fn diagnostics_for_files(
+    db: &Database,
+    file_ids: &[FileId],
+) -> Vec<(FileId, Vec<Diagnostic>)> {
+    let files = file_ids
+        .iter()
+        .filter_map(|file_id| Some((*file_id, db.get_file(*file_id)?)))
+        .collect::<Vec<_>>();
+
+    let type_diagnostics = db.gcx.with_tcx(db, |tcx| {
+        files
+            .iter()
+            .map(|(_, file)| {
+                tcx.infer_all_params(*file);
+                tcx.try_infer_all_exprs(*file)?;
+                tcx.infer_all_load_items(*file);
+                Ok(tcx.diagnostics_for_file(*file))
+            })
+            .collect::<Result<Vec<_>, Cancelled>>()
     });
+    let type_diagnostics = match type_diagnostics {
+        Ok(type_diagnostics) => type_diagnostics,
+        Err(cancelled) => cancelled.throw(),
+    };
 
-    // Limit the amount of syntax errors we send, as this many syntax errors probably means something
-    // is really wrong with the file being analyzed.
-    diagnostics_for_file(db, file)
-        .take(128)
-        .chain(diagnostics.into_iter())
+    files
+        .into_iter()
+        .zip(type_diagnostics)
+        .map(|((file_id, file), diagnostics)| {
+            // Limit the amount of syntax errors we send, as this many syntax errors probably means
+            // something is really wrong with the file being analyzed.
+            let diagnostics = diagnostics_for_file(db, file)
+                .take(128)
+                .chain(diagnostics.into_iter())
+                .collect();
+            (file_id, diagnostics)
+        })
         .collect()
 }
@@ -6,7 +6,7 @@ use starpls_syntax::{
     TextRange,
 };
 
-use crate::Database;
+use crate::{util::is_constant_name, Database};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SymbolKind {
@@ -57,24 +57,39 @@ pub struct DocumentSymbol {
 pub(crate) fn document_symbols(db: &Database, file_id: FileId) -> Option<Vec<DocumentSymbol>> {
     let sema = Semantics::new(db);
     let file = db.get_file(file_id)?;
+    let root = parse(db, file).syntax(db);
     let scope = sema.scope_for_module(file);
     let mut symbols = scope
         .names()
         .filter_map(|(name, def)| {
-            let range = def.syntax_node_ptr(db, file)?.text_range();
-            Some(DocumentSymbol {
-                name: name.as_str().to_string(),
-                detail: None,
-                kind: match def {
-                    ScopeDef::Callable(_) => SymbolKind::Function,
-                    ScopeDef::Variable(_) => SymbolKind::Variable,
-                    _ => return None,
-                },
-                tags: None,
-                range: range.clone(),
-                selection_range: range,
-                children: None,
-            })
+            let node = def.syntax_node_ptr(db, file)?.try_to_node(&root)?;
+            match def {
+                ScopeDef::Callable(_) => def_stmt_symbol(&ast::DefStmt::cast(node)?),
+                ScopeDef::Variable(_) => {
+                    let name = name.as_str().to_string();
+                    let selection_range = node.text_range();
+
+                    // Use the range of the full assignment statement if there is one.
+                    let range = node
+                        .ancestors()
+                        .find(|node| ast::AssignStmt::can_cast(node.kind()))
+                        .map_or(selection_range, |stmt| stmt.text_range());
+                    Some(DocumentSymbol {
+                        kind: if is_constant_name(&name) {
+                            SymbolKind::Constant
+                        } else {
+                            SymbolKind::Variable
+                        },
+                        name,
+                        detail: None,
+                        tags: None,
+                        range,
+                        selection_range,
+                        children: None,
+                    })
+                }
+                _ => None,
+            }
         })
         .collect();
     if file.api_context(db) == Some(APIContext::Build) {
@@ -85,6 +100,65 @@ pub(crate) fn document_symbols(db: &Database, file_id: FileId) -> Option<Vec<Doc
     Some(symbols)
 }
 
+fn def_stmt_symbol(def_stmt: &ast::DefStmt) -> Option<DocumentSymbol> {
+    let name = def_stmt.name()?;
+    let mut children = def_stmt
+        .parameters()
+        .into_iter()
+        .flat_map(|params| params.parameters())
+        .filter_map(|param| {
+            let name = match &param {
+                ast::Parameter::Simple(param) => param.name(),
+                ast::Parameter::ArgsList(param) => param.name(),
+                ast::Parameter::KwargsDict(param) => param.name(),
+            }?;
+            Some(DocumentSymbol {
+                name: name.name()?.text().to_string(),
+                detail: None,
+                kind: SymbolKind::Variable,
+                tags: None,
+                range: param.syntax().text_range(),
+                selection_range: name.syntax().text_range(),
+                children: None,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Add functions nested directly within this function, e.g. not within another nested function.
+    if let Some(suite) = def_stmt.suite() {
+        children.extend(
+            suite
+                .syntax()
+                .descendants()
+                .filter_map(ast::DefStmt::cast)
+                .filter(|nested| {
+                    nested
+                        .syntax()
+                        .ancestors()
+                        .skip(1)
+                        .find_map(ast::DefStmt::cast)
+                        .as_ref()
+                        == Some(def_stmt)
+                })
+                .filter_map(|nested| def_stmt_symbol(&nested)),
+        );
+    }
+
+    Some(DocumentSymbol {
+        name: name.name()?.text().to_string(),
+        detail: None,
+        kind: SymbolKind::Function,
+        tags: None,
+        range: def_stmt.syntax().text_range(),
+        selection_range: name.syntax().text_range(),
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    })
+}
+
 fn add_target_symbols(db: &Database, file: File, acc: &mut Vec<DocumentSymbol>) {
     let root = parse(db, file).syntax(db);
     let targets = root.children().filter_map(|child| {
@@ -163,8 +237,8 @@ def foo():
     pass
 "#,
             expect![[r#"
-                DocumentSymbol { name: "s", detail: None, kind: Variable, tags: None, range: 0..1, selection_range: 0..1, children: None }
-                DocumentSymbol { name: "foo", detail: None, kind: Function, tags: None, range: 11..31, selection_range: 11..31, children: None }
+                DocumentSymbol { name: "s", detail: None, kind: Variable, tags: None, range: 0..9, selection_range: 0..1, children: None }
+                DocumentSymbol { name: "foo", detail: None, kind: Function, tags: None, range: 11..31, selection_range: 15..18, children: None }
             "#]],
         );
     }
@@ -178,8 +252,8 @@ x = 123
 x = "123"
 "#,
             expect![[r#"
-                DocumentSymbol { name: "y", detail: None, kind: Variable, tags: None, range: 1..2, selection_range: 1..2, children: None }
-                DocumentSymbol { name: "x", detail: None, kind: Variable, tags: None, range: 19..20, selection_range: 19..20, children: None }
+                DocumentSymbol { name: "y", detail: None, kind: Variable, tags: None, range: 1..10, selection_range: 1..2, children: None }
+                DocumentSymbol { name: "x", detail: None, kind: Variable, tags: None, range: 19..28, selection_range: 19..20, children: None }
             "#]],
         );
     }
@@ -193,7 +267,7 @@ load("foo.star", "foo")
 bar = 1
 "#,
             expect![[r#"
-                DocumentSymbol { name: "bar", detail: None, kind: Variable, tags: None, range: 26..29, selection_range: 26..29, children: None }
+                DocumentSymbol { name: "bar", detail: None, kind: Variable, tags: None, range: 26..33, selection_range: 26..29, children: None }
             "#]],
         )
     }
@@ -215,10 +289,28 @@ rust_library_test(
 )
 "#,
             expect![[r#"
-                DocumentSymbol { name: "NUMS", detail: None, kind: Variable, tags: None, range: 1..5, selection_range: 1..5, children: None }
+                DocumentSymbol { name: "NUMS", detail: None, kind: Constant, tags: None, range: 1..17, selection_range: 1..5, children: None }
                 DocumentSymbol { name: ":starpls_ide", detail: None, kind: Variable, tags: None, range: 19..94, selection_range: 19..94, children: None }
                 DocumentSymbol { name: ":starpls_ide_test", detail: None, kind: Variable, tags: None, range: 96..176, selection_range: 96..176, children: None }
             "#]],
         )
     }
+
+    #[test]
+    fn test_nested_functions_and_constants() {
+        check(
+            r#"
+MAX_SIZE = 10
+
+def outer(a, *args, **kwargs):
+    def inner(b):
+        pass
+    return inner
+"#,
+            expect![[r#"
+                DocumentSymbol { name: "MAX_SIZE", detail: None, kind: Constant, tags: None, range: 1..14, selection_range: 1..9, children: None }
+                DocumentSymbol { name: "outer", detail: None, kind: Function, tags: None, range: 16..95, selection_range: 20..25, children: Some([DocumentSymbol { name: "a", detail: None, kind: Variable, tags: None, range: 26..27, selection_range: 26..27, children: None }, DocumentSymbol { name: "args", detail: None, kind: Variable, tags: None, range: 29..34, selection_range: 30..34, children: None }, DocumentSymbol { name: "kwargs", detail: None, kind: Variable, tags: None, range: 36..44, selection_range: 38..44, children: None }, DocumentSymbol { name: "inner", detail: None, kind: Function, tags: None, range: 51..78, selection_range: 55..60, children: Some([DocumentSymbol { name: "b", detail: None, kind: Variable, tags: None, range: 61..62, selection_range: 61..62, children: None }]) }]) }
+            "#]],
+        )
+    }
 }
@@ -0,0 +1,221 @@
+use starpls_common::{parse, Db as _, File};
+use starpls_hir::{Name, Semantics};
+use starpls_syntax::{
+    ast::{self, AstNode, SyntaxNodePtr},
+    TextRange, T,
+};
+
+use crate::{util::pick_best_token, Database, FilePosition, LocationLink};
+
+pub(crate) fn find_references(
+    db: &Database,
+    FilePosition { file_id, pos }: FilePosition,
+    include_declaration: bool,
+) -> Option<Vec<LocationLink>> {
+    let sema = Semantics::new(db);
+    let file = db.get_file(file_id)?;
+    let root = parse(db, file).syntax(db);
+    let token = pick_best_token(root.token_at_offset(pos), |kind| match kind {
+        T![ident] => 2,
+        kind if kind.is_trivia_token() => 0,
+        _ => 1,
+    })?;
+    let parent = token.parent()?;
+
+    // Determine the declarations that the symbol under the cursor refers to. The cursor can
+    // either be on a name reference, or on the name of a function or parameter declaration.
+    let decls = if let Some(name_ref) = ast::NameRef::cast(parent.clone()) {
+        resolve_name_ref(db, &sema, file, &name_ref)?
+    } else if let Some(name) = ast::Name::cast(parent) {
+        let owner = name.syntax().parent()?;
+        if !ast::DefStmt::can_cast(owner.kind()) && !ast::Parameter::can_cast(owner.kind()) {
+            return None;
+        }
+        vec![SyntaxNodePtr::new(&owner)]
+    } else {
+        return None;
+    };
+
+    if decls.is_empty() {
+        return None;
+    }
+
+    // For now, references are only searched for in the current file.
+    let mut ranges = Vec::new();
+    references_in_file(
+        db,
+        &sema,
+        file,
+        token.text(),
+        &decls,
+        include_declaration,
+        &mut ranges,
+    );
+
+    ranges.sort_by_key(|range| range.start());
+    ranges.dedup();
+    Some(
+        ranges
+            .into_iter()
+            .map(|range| LocationLink::Local {
+                origin_selection_range: None,
+                target_range: range,
+                target_selection_range: range,
+                target_file_id: file_id,
+            })
+            .collect(),
+    )
+}
+
+/// Collects the ranges of all names in the given file that resolve to any of the given
+/// declarations.
+fn references_in_file(
+    db: &Database,
+    sema: &Semantics,
+    file: File,
+    name: &str,
+    decls: &[SyntaxNodePtr],
+    include_declaration: bool,
+    acc: &mut Vec<TextRange>,
+) {
+    let root = parse(db, file).syntax(db);
+    for name_ref in root.descendants().filter_map(ast::NameRef::cast) {
+        if name_ref.name().map_or(true, |token| token.text() != name) {
+            continue;
+        }
+
+        let resolved = match resolve_name_ref(db, sema, file, &name_ref) {
+            Some(resolved) => resolved,
+            None => continue,
+        };
+        if !resolved.iter().any(|ptr| decls.contains(ptr)) {
+            continue;
+        }
+
+        // Variable declarations are themselves name references, e.g. the `x` in `x = 1`.
+        let is_declaration = decls.contains(&SyntaxNodePtr::new(name_ref.syntax()));
+        if include_declaration || !is_declaration {
+            acc.push(name_ref.syntax().text_range());
+        }
+    }
+
+    // Function and parameter declarations aren't name references, so they are added separately.
+    if include_declaration {
+        acc.extend(decls.iter().filter_map(|ptr| {
+            let node = ptr.try_to_node(&root)?;
+            if !ast::DefStmt::can_cast(node.kind()) && !ast::Parameter::can_cast(node.kind()) {
+                return None;
+            }
+            node.children()
+                .find_map(ast::Name::cast)
+                .map(|name| name.syntax().text_range())
+        }));
+    }
+}
+
+fn resolve_name_ref(
+    db: &Database,
+    sema: &Semantics,
+    file: File,
+    name_ref: &ast::NameRef,
+) -> Option<Vec<SyntaxNodePtr>> {
+    let scope = sema.scope_for_expr(file, &ast::Expression::cast(name_ref.syntax().clone())?)?;
+    Some(
+        scope
+            .resolve_name(&Name::from_ast_node(name_ref.clone()))
+            .into_iter()
+            .filter_map(|def| def.syntax_node_ptr(db, file))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+    use starpls_test_util::parse_fixture;
+
+    use crate::{AnalysisSnapshot, FilePosition, LocationLink};
+
+    fn check_find_references(fixture: &str, include_declaration: bool) {
+        let (contents, pos, expected) = parse_fixture(fixture);
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            &contents,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let actual = snap
+            .find_references(FilePosition { file_id, pos }, include_declaration)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|loc| match loc {
+                LocationLink::Local { target_range, .. } => target_range,
+                _ => panic!("expected local location"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_variable() {
+        check_find_references(
+            r#"
+foo = 1
+#^^
+def f():
+    return foo
+           #^^
+f$0oo + 1
+#^^
+"#,
+            true,
+        )
+    }
+
+    #[test]
+    fn test_exclude_declaration() {
+        check_find_references(
+            r#"
+foo = 1
+def f():
+    return foo
+           #^^
+f$0oo + 1
+#^^
+"#,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_function() {
+        check_find_references(
+            r#"
+def foo():
+    #^^
+    pass
+
+fo$0o()
+#^^
+"#,
+            true,
+        )
+    }
+
+    #[test]
+    fn test_param() {
+        check_find_references(
+            r#"
+def f(ab$0c):
+      #^^
+    return abc
+           #^^
+"#,
+            true,
+        )
+    }
+}
@@ -0,0 +1,203 @@
+use starpls_common::{parse, Db as _, FileId};
+use starpls_syntax::{
+    ast::{self, AstNode},
+    SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize, T,
+};
+
+use crate::Database;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FoldKind {
+    Comment,
+    Region,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FoldingRange {
+    pub range: TextRange,
+    pub kind: FoldKind,
+}
+
+pub(crate) fn folding_ranges(db: &Database, file_id: FileId) -> Option<Vec<FoldingRange>> {
+    let file = db.get_file(file_id)?;
+    let contents = file.contents(db);
+    let root = parse(db, file).syntax(db);
+    let mut ranges = Vec::new();
+    let mut add_region = |start: TextSize, end: Option<TextSize>| {
+        if let Some(end) = end {
+            let range = TextRange::new(start, end);
+            if contents[range].contains('\n') {
+                ranges.push(FoldingRange {
+                    range,
+                    kind: FoldKind::Region,
+                });
+            }
+        }
+    };
+
+    for node in root.descendants() {
+        add_node_ranges(&node, &mut add_region);
+    }
+
+    add_comment_ranges(contents, &root, &mut ranges);
+    ranges.sort_by_key(|range| range.range.start());
+    Some(ranges)
+}
+
+fn add_node_ranges(node: &SyntaxNode, add_region: &mut impl FnMut(TextSize, Option<TextSize>)) {
+    let start = node.text_range().start();
+    if let Some(stmt) = ast::DefStmt::cast(node.clone()) {
+        add_region(
+            start,
+            stmt.suite().and_then(|suite| trimmed_end(suite.syntax())),
+        );
+    } else if let Some(stmt) = ast::ForStmt::cast(node.clone()) {
+        add_region(
+            start,
+            stmt.suite().and_then(|suite| trimmed_end(suite.syntax())),
+        );
+    } else if let Some(stmt) = ast::IfStmt::cast(node.clone()) {
+        // The `if` and `else` suites are folded separately. `elif` clauses are nested `if`
+        // statements, and so are handled when their nodes are visited.
+        add_region(
+            start,
+            stmt.if_suite()
+                .and_then(|suite| trimmed_end(suite.syntax())),
+        );
+        if let (Some(else_token), Some(else_suite)) = (
+            stmt.syntax()
+                .children_with_tokens()
+                .filter_map(|element| element.into_token())
+                .find(|token| token.kind() == T![else]),
+            stmt.else_suite(),
+        ) {
+            add_region(
+                else_token.text_range().start(),
+                trimmed_end(else_suite.syntax()),
+            );
+        }
+    } else if matches!(
+        node.kind(),
+        SyntaxKind::LIST_EXPR
+            | SyntaxKind::LIST_COMP
+            | SyntaxKind::DICT_EXPR
+            | SyntaxKind::DICT_COMP
+    ) {
+        add_region(start, Some(node.text_range().end()));
+    }
+}
+
+/// Returns the end of the last token in the given node that isn't trivia, e.g. excluding the
+/// trailing newlines and comments of a suite.
+fn trimmed_end(node: &SyntaxNode) -> Option<TextSize> {
+    node.descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| {
+            !token.kind().is_trivia_token()
+                && !matches!(
+                    token.kind(),
+                    SyntaxKind::NEWLINE | SyntaxKind::INDENT | SyntaxKind::DEDENT
+                )
+        })
+        .last()
+        .map(|token| token.text_range().end())
+}
+
+/// Folds runs of comments that are on consecutive lines, not counting comments that follow
+/// code on the same line.
+fn add_comment_ranges(contents: &str, root: &SyntaxNode, acc: &mut Vec<FoldingRange>) {
+    let mut run: Option<(SyntaxToken, SyntaxToken)> = None;
+    let finish_run = |run: Option<(SyntaxToken, SyntaxToken)>, acc: &mut Vec<FoldingRange>| {
+        if let Some((first, last)) = run {
+            if first != last {
+                acc.push(FoldingRange {
+                    range: TextRange::new(first.text_range().start(), last.text_range().end()),
+                    kind: FoldKind::Comment,
+                });
+            }
+        }
+    };
+
+    for comment in root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() == SyntaxKind::COMMENT)
+    {
+        let start = usize::from(comment.text_range().start());
+        let line_start = contents[..start].rfind('\n').map_or(0, |pos| pos + 1);
+        if !contents[line_start..start].trim().is_empty() {
+            finish_run(run.take(), acc);
+            continue;
+        }
+
+        run = match run.take() {
+            Some((first, last)) => {
+                let between = &contents[usize::from(last.text_range().end())..start];
+                if between.trim().is_empty() && between.matches('\n').count() == 1 {
+                    Some((first, comment))
+                } else {
+                    finish_run(Some((first, last)), acc);
+                    Some((comment.clone(), comment))
+                }
+            }
+            None => Some((comment.clone(), comment)),
+        };
+    }
+
+    finish_run(run, acc);
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+
+    use crate::AnalysisSnapshot;
+
+    fn check(input: &str, expect: Expect) {
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            input,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let ranges = snap.folding_ranges(file_id).unwrap().unwrap();
+        let mut actual = String::new();
+        for range in ranges {
+            actual.push_str(&format!(
+                "{:?} {:?}: {:?}\n",
+                range.range, &input[range.range], range.kind
+            ));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_folding_ranges() {
+        check(
+            r#"# First line.
+# Second line.
+def f(x):
+    if x:
+        return [
+            1,
+        ]
+    else:
+        pass
+
+x = {"a": 1}  # Trailing comment.
+# Unrelated.
+"#,
+            expect![[r##"
+                0..28 "# First line.\n# Second line.": Comment
+                29..113 "def f(x):\n    if x:\n        return [\n            1,\n        ]\n    else:\n        pass": Region
+                43..90 "if x:\n        return [\n            1,\n        ]": Region
+                64..90 "[\n            1,\n        ]": Region
+                95..113 "else:\n        pass": Region
+            "##]],
+        );
+    }
+}
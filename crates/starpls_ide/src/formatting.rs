@@ -0,0 +1,678 @@
+use std::borrow::Cow;
+
+use rustc_hash::FxHashMap;
+use starpls_common::{parse, Db as _, FileId};
+use starpls_syntax::{
+    ast::{self, AstNode},
+    parse_module, SyntaxElement, SyntaxKind,
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+
+use crate::{Database, TextEdit};
+
+/// The maximum number of consecutive blank lines that are preserved.
+const MAX_BLANK_LINES: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormattingOptions {
+    pub tab_size: u32,
+    pub insert_spaces: bool,
+}
+
+impl Default for FormattingOptions {
+    fn default() -> Self {
+        Self {
+            tab_size: 4,
+            insert_spaces: true,
+        }
+    }
+}
+
+/// Formats the given file, returning a single edit that replaces the entire document. No edits
+/// are returned if the file has syntax errors.
+pub(crate) fn format(
+    db: &Database,
+    file_id: FileId,
+    options: FormattingOptions,
+) -> Option<Vec<TextEdit>> {
+    let file = db.get_file(file_id)?;
+    let contents = file.contents(db);
+    let formatted = format_text(contents, options)?;
+    if formatted.text == *contents {
+        return Some(Vec::new());
+    }
+
+    Some(vec![TextEdit {
+        range: TextRange::up_to(TextSize::of(contents.as_str())),
+        new_text: formatted.text,
+    }])
+}
+
+/// Formats the statements overlapping the given range. The range is expanded to cover whole
+/// statements, and indentation is computed from the rest of the file.
+pub(crate) fn format_range(
+    db: &Database,
+    file_id: FileId,
+    range: TextRange,
+    options: FormattingOptions,
+) -> Option<Vec<TextEdit>> {
+    let file = db.get_file(file_id)?;
+    let contents = file.contents(db);
+    let formatted = format_text(contents, options)?;
+    let root = parse(db, file).syntax(db);
+    let (mut first, last) = selected_statements(&root, range)?;
+
+    // Statements separated by semicolons share a line, so the selection must start with the
+    // first statement on its line.
+    let start = loop {
+        let token = significant_tokens(&first).next()?;
+        match formatted.tokens.get(&token.text_range().start()) {
+            Some(PrintedToken {
+                line_start: Some(line_start),
+                ..
+            }) => {
+                let token_start = usize::from(token.text_range().start());
+                let original_line_start =
+                    contents[..token_start].rfind('\n').map_or(0, |pos| pos + 1);
+                break (original_line_start, *line_start);
+            }
+            _ => first = first.prev_sibling()?,
+        }
+    };
+    let last_token = significant_tokens(&last).last()?;
+    let end = (
+        usize::from(last_token.text_range().end()),
+        formatted.tokens.get(&last_token.text_range().start())?.end,
+    );
+
+    if start.0 > end.0 || start.1 > end.1 {
+        return None;
+    }
+
+    let new_text = &formatted.text[start.1..end.1];
+    if *new_text == contents[start.0..end.0] {
+        return Some(Vec::new());
+    }
+
+    Some(vec![TextEdit {
+        range: TextRange::new(
+            TextSize::try_from(start.0).ok()?,
+            TextSize::try_from(end.0).ok()?,
+        ),
+        new_text: new_text.to_string(),
+    }])
+}
+
+/// Finds the first and last statements overlapping the given range, within the innermost suite
+/// containing the range.
+fn selected_statements(root: &SyntaxNode, range: TextRange) -> Option<(SyntaxNode, SyntaxNode)> {
+    let suite = match root.covering_element(range) {
+        SyntaxElement::Node(node) => node,
+        SyntaxElement::Token(token) => token.parent()?,
+    }
+    .ancestors()
+    .find(|node| matches!(node.kind(), MODULE | SUITE))?;
+
+    let mut stmts = suite.children().filter(|stmt| {
+        let stmt_range = stmt.text_range();
+        stmt.kind() != TYPE_COMMENT
+            && if range.is_empty() {
+                stmt_range.contains_inclusive(range.start())
+            } else {
+                stmt_range.start() < range.end() && range.start() < stmt_range.end()
+            }
+    });
+    let first = stmts.next()?;
+    let last = stmts.last().unwrap_or_else(|| first.clone());
+    Some((first, last))
+}
+
+/// Returns the tokens of the given node that are written by the printer as-is, i.e. excluding
+/// whitespace, comments and type comments.
+fn significant_tokens(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| {
+            !token.kind().is_trivia_token()
+                && !matches!(token.kind(), NEWLINE | INDENT | DEDENT)
+                && !token
+                    .parent_ancestors()
+                    .any(|node| node.kind() == TYPE_COMMENT)
+        })
+}
+
+struct Formatted {
+    text: String,
+    /// The positions of the printed tokens, keyed by their offsets in the original text.
+    tokens: FxHashMap<TextSize, PrintedToken>,
+}
+
+struct PrintedToken {
+    end: usize,
+    /// The start of the printed line, if the token is the first on its line.
+    line_start: Option<usize>,
+}
+
+fn format_text(text: &str, options: FormattingOptions) -> Option<Formatted> {
+    let root = parse_without_errors(text)?;
+    let indent_unit = if options.insert_spaces {
+        " ".repeat(options.tab_size as usize)
+    } else {
+        "\t".to_string()
+    };
+    let mut printer = Printer::new(indent_unit);
+    for element in root.children_with_tokens() {
+        printer.element(element);
+    }
+    let formatted = printer.finish();
+
+    // As a safeguard against bugs in the printer, the output must parse to the same tree as the
+    // input, ignoring whitespace and the order of `load` symbols.
+    let formatted_root = parse_without_errors(&formatted.text)?;
+    (shape(&root) == shape(&formatted_root)).then_some(formatted)
+}
+
+fn parse_without_errors(text: &str) -> Option<SyntaxNode> {
+    let mut has_errors = false;
+    let tree = parse_module(text, &mut |_| has_errors = true);
+    (!has_errors).then(|| tree.syntax())
+}
+
+struct Printer {
+    out: String,
+    tokens: FxHashMap<TextSize, PrintedToken>,
+    indent_unit: String,
+    /// The indentation level of the current block.
+    indent_level: usize,
+    /// The indentation levels of the contents of each open bracket.
+    brackets: Vec<usize>,
+    /// The indentation level of the current line.
+    line_level: usize,
+    /// The indentation level used by brackets opened on the current line. Brackets opened on the
+    /// same line share a level, so that e.g. `foo([` only indents its contents once.
+    opened_on_line: Option<usize>,
+    at_line_start: bool,
+    /// Whether the current line was ended with a backslash.
+    continuation: bool,
+    pending_blank_lines: usize,
+    /// The kind of the last token written on the current line, along with the kind of its parent.
+    prev: Option<(SyntaxKind, Option<SyntaxKind>)>,
+}
+
+impl Printer {
+    fn new(indent_unit: String) -> Self {
+        Self {
+            out: String::new(),
+            tokens: FxHashMap::default(),
+            indent_unit,
+            indent_level: 0,
+            brackets: Vec::new(),
+            line_level: 0,
+            opened_on_line: None,
+            at_line_start: true,
+            continuation: false,
+            pending_blank_lines: 0,
+            prev: None,
+        }
+    }
+
+    fn finish(mut self) -> Formatted {
+        self.out.truncate(self.out.trim_end().len());
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        Formatted {
+            text: self.out,
+            tokens: self.tokens,
+        }
+    }
+
+    fn element(&mut self, element: SyntaxElement) {
+        match element {
+            SyntaxElement::Node(node) => match node.kind() {
+                TYPE_COMMENT => self.comment(&node.text().to_string()),
+                LOAD_STMT if !has_comments(&node) => self.load_stmt(&node),
+                _ => {
+                    for child in node.children_with_tokens() {
+                        self.element(child);
+                    }
+                }
+            },
+            SyntaxElement::Token(token) => self.token(&token),
+        }
+    }
+
+    /// Writes a `load` statement with its symbols sorted by the names they are bound to. The
+    /// punctuation between the symbols is left in place.
+    fn load_stmt(&mut self, node: &SyntaxNode) {
+        let mut items = node
+            .children()
+            .filter(|child| ast::LoadItem::can_cast(child.kind()))
+            .collect::<Vec<_>>();
+        items.sort_by_cached_key(load_item_key);
+        let mut items = items.into_iter();
+        for child in node.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(ref child_node)
+                    if ast::LoadItem::can_cast(child_node.kind()) =>
+                {
+                    if let Some(item) = items.next() {
+                        self.element(SyntaxElement::Node(item));
+                    }
+                }
+                child => self.element(child),
+            }
+        }
+    }
+
+    fn token(&mut self, token: &SyntaxToken) {
+        match token.kind() {
+            NEWLINE => self.newline(),
+            WHITESPACE if token.text().contains('\n') => {
+                if self.brackets.is_empty() {
+                    self.backslash_continuation();
+                } else {
+                    for _ in token.text().matches('\n') {
+                        self.newline();
+                    }
+                }
+            }
+            WHITESPACE => {}
+            INDENT => self.indent_level += 1,
+            DEDENT => self.indent_level = self.indent_level.saturating_sub(1),
+            COMMENT => self.comment(token.text()),
+            _ => self.word(token),
+        }
+    }
+
+    fn newline(&mut self) {
+        if self.at_line_start {
+            self.pending_blank_lines += 1;
+        } else {
+            self.trim_line_end();
+            self.out.push('\n');
+            self.at_line_start = true;
+        }
+    }
+
+    fn backslash_continuation(&mut self) {
+        if !self.at_line_start {
+            self.trim_line_end();
+            self.out.push_str(" \\\n");
+            self.at_line_start = true;
+            self.continuation = true;
+        }
+    }
+
+    fn comment(&mut self, text: &str) {
+        if self.at_line_start {
+            self.start_line(COMMENT);
+        } else {
+            self.trim_line_end();
+            self.out.push_str("  ");
+        }
+        self.out.push_str(text.trim_end());
+        self.prev = Some((COMMENT, None));
+    }
+
+    fn word(&mut self, token: &SyntaxToken) {
+        let kind = token.kind();
+        let parent = token.parent().map(|parent| parent.kind());
+        let line_start = if self.at_line_start {
+            Some(self.start_line(kind))
+        } else {
+            if self.needs_space(kind) {
+                self.out.push(' ');
+            }
+            None
+        };
+
+        match kind {
+            STRING | BYTES => self.out.push_str(&normalize_quotes(token.text())),
+            _ => self.out.push_str(token.text()),
+        }
+        self.tokens.insert(
+            token.text_range().start(),
+            PrintedToken {
+                end: self.out.len(),
+                line_start,
+            },
+        );
+
+        if is_open_bracket(kind) {
+            let level = *self.opened_on_line.get_or_insert(self.line_level + 1);
+            self.brackets.push(level);
+        } else if is_close_bracket(kind) {
+            self.brackets.pop();
+        }
+        self.prev = Some((kind, parent));
+    }
+
+    /// Starts a new line, returning the offset of the start of the line.
+    fn start_line(&mut self, kind: SyntaxKind) -> usize {
+        let level = match self.brackets.last() {
+            Some(&level) if is_close_bracket(kind) => level.saturating_sub(1),
+            Some(&level) => level,
+            None if self.continuation => self.indent_level + 1,
+            None => self.indent_level,
+        };
+
+        if !self.out.is_empty() {
+            for _ in 0..self.pending_blank_lines.min(MAX_BLANK_LINES) {
+                self.out.push('\n');
+            }
+        }
+        let line_start = self.out.len();
+        for _ in 0..level {
+            self.out.push_str(&self.indent_unit);
+        }
+
+        self.pending_blank_lines = 0;
+        self.line_level = level;
+        self.opened_on_line = None;
+        self.at_line_start = false;
+        self.continuation = false;
+        self.prev = None;
+        line_start
+    }
+
+    fn needs_space(&self, kind: SyntaxKind) -> bool {
+        let (prev_kind, prev_parent) = match self.prev {
+            Some(prev) => prev,
+            None => return false,
+        };
+
+        if is_open_bracket(prev_kind)
+            || prev_kind == DOT
+            || is_close_bracket(kind)
+            || matches!(kind, COMMA | SEMI | COLON | DOT)
+        {
+            return false;
+        }
+
+        // Slices are written without spaces, e.g. `x[1:-1]`.
+        if prev_kind == COLON && prev_parent == Some(SLICE_EXPR) {
+            return false;
+        }
+
+        // Calls and index expressions, e.g. `f(x)` and `x[0]`.
+        if matches!(kind, OPEN_PAREN | OPEN_BRACK)
+            && (matches!(prev_kind, IDENT | STRING | BYTES) || is_close_bracket(prev_kind))
+        {
+            return false;
+        }
+
+        match (prev_kind, prev_parent) {
+            (PLUS | MINUS | TILDE, Some(UNARY_EXPR)) => false,
+            // Unpacked arguments and variadic parameters, e.g. `f(*args, **kwargs)`.
+            (STAR | STAR_STAR, parent) => parent == Some(BINARY_EXPR),
+            _ => true,
+        }
+    }
+
+    fn trim_line_end(&mut self) {
+        let len = self.out.trim_end_matches([' ', '\t']).len();
+        self.out.truncate(len);
+    }
+}
+
+fn has_comments(node: &SyntaxNode) -> bool {
+    node.descendants_with_tokens()
+        .any(|element| matches!(element.kind(), COMMENT | TYPE_COMMENT))
+}
+
+pub(crate) fn load_item_key(node: &SyntaxNode) -> String {
+    match ast::LoadItem::cast(node.clone()) {
+        Some(ast::LoadItem::Direct(item)) => item
+            .name()
+            .map(|name| name.text().trim_matches(['"', '\'']).to_string()),
+        Some(ast::LoadItem::Aliased(item)) => item
+            .alias()
+            .and_then(|alias| alias.name())
+            .map(|name| name.text().to_string()),
+        None => None,
+    }
+    .unwrap_or_default()
+}
+
+/// Rewrites single-quoted strings to use double quotes, e.g. `'a'` to `"a"`. Strings that would
+/// need additional escapes to be rewritten are left unchanged.
+fn normalize_quotes(text: &str) -> Cow<str> {
+    let prefix_len = match text.find(['\'', '"']) {
+        Some(prefix_len) => prefix_len,
+        None => return Cow::Borrowed(text),
+    };
+    let (prefix, quoted) = text.split_at(prefix_len);
+    if !quoted.starts_with('\'') || quoted.starts_with("'''") || quoted.len() < 2 {
+        return Cow::Borrowed(text);
+    }
+
+    let body = &quoted[1..quoted.len() - 1];
+    let is_raw = prefix.contains(['r', 'R']);
+    if body.contains('"') || (is_raw && body.contains('\\')) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut normalized = String::with_capacity(text.len());
+    normalized.push_str(prefix);
+    normalized.push('"');
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('\'') => normalized.push('\''),
+                Some(next) => {
+                    normalized.push('\\');
+                    normalized.push(next);
+                }
+                None => normalized.push('\\'),
+            }
+        } else {
+            normalized.push(c);
+        }
+    }
+    normalized.push('"');
+    Cow::Owned(normalized)
+}
+
+/// Summarizes a syntax tree as its node kinds and the text of its tokens, in order. Whitespace
+/// and newlines are ignored, comments are compared without trailing whitespace, strings are
+/// compared with normalized quotes, and `load` symbols are compared regardless of their order.
+fn shape(root: &SyntaxNode) -> (Vec<SyntaxKind>, Vec<(SyntaxKind, String)>) {
+    fn walk(
+        node: &SyntaxNode,
+        nodes: &mut Vec<SyntaxKind>,
+        tokens: &mut Vec<(SyntaxKind, String)>,
+    ) {
+        nodes.push(node.kind());
+        if node.kind() == LOAD_STMT {
+            // The symbols are swapped in place, so their tokens are substituted in sorted order
+            // while the punctuation between them keeps its position.
+            let mut items = node
+                .children()
+                .filter(|child| ast::LoadItem::can_cast(child.kind()))
+                .map(|item| {
+                    let mut item_nodes = Vec::new();
+                    let mut item_tokens = Vec::new();
+                    walk(&item, &mut item_nodes, &mut item_tokens);
+                    (item_nodes, item_tokens)
+                })
+                .collect::<Vec<_>>();
+            items.sort();
+            let mut items = items.into_iter();
+            for child in node.children_with_tokens() {
+                match child {
+                    SyntaxElement::Node(child) if ast::LoadItem::can_cast(child.kind()) => {
+                        if let Some((item_nodes, item_tokens)) = items.next() {
+                            nodes.extend(item_nodes);
+                            tokens.extend(item_tokens);
+                        }
+                    }
+                    SyntaxElement::Node(child) => walk(&child, nodes, tokens),
+                    SyntaxElement::Token(token) => push_token(&token, tokens),
+                }
+            }
+        } else {
+            for child in node.children_with_tokens() {
+                match child {
+                    SyntaxElement::Node(child) => walk(&child, nodes, tokens),
+                    SyntaxElement::Token(token) => push_token(&token, tokens),
+                }
+            }
+        }
+    }
+
+    fn push_token(token: &SyntaxToken, tokens: &mut Vec<(SyntaxKind, String)>) {
+        let kind = token.kind();
+        if matches!(kind, WHITESPACE | NEWLINE | INDENT | DEDENT) {
+            return;
+        }
+        let text = match kind {
+            COMMENT => token.text().trim_end().to_string(),
+            STRING | BYTES => normalize_quotes(token.text()).into_owned(),
+            _ => token.text().to_string(),
+        };
+        tokens.push((kind, text));
+    }
+
+    let mut nodes = Vec::new();
+    let mut tokens = Vec::new();
+    walk(root, &mut nodes, &mut tokens);
+    (nodes, tokens)
+}
+
+fn is_open_bracket(kind: SyntaxKind) -> bool {
+    matches!(kind, OPEN_PAREN | OPEN_BRACK | OPEN_BRACE)
+}
+
+fn is_close_bracket(kind: SyntaxKind) -> bool {
+    matches!(kind, CLOSE_PAREN | CLOSE_BRACK | CLOSE_BRACE)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+    use starpls_syntax::{TextRange, TextSize};
+
+    use super::{format_text, parse_without_errors, shape, FormattingOptions};
+    use crate::AnalysisSnapshot;
+
+    fn check(input: &str, expect: Expect) {
+        let actual = format_text(input, FormattingOptions::default())
+            .unwrap()
+            .text;
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_format() {
+        check(
+            r#"
+
+
+load('//:foo.bzl', 'foo', bar = 'baz', "alpha")
+x=[1,2 ,3]   # Trailing comment.
+def f( a,b = 1, *args, **kwargs ):
+  if a :
+     return -a+x [ 1 : 2 ]
+  return f (
+    a,
+        b,
+  )
+
+
+
+y = {'a': 'it\'s', "b": 'say "hi"'}
+"#,
+            expect![[r#"
+                load("//:foo.bzl", "alpha", bar = "baz", "foo")
+                x = [1, 2, 3]  # Trailing comment.
+                def f(a, b = 1, *args, **kwargs):
+                    if a:
+                        return -a + x[1:2]
+                    return f(
+                        a,
+                        b,
+                    )
+
+                y = {"a": "it's", "b": 'say "hi"'}
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_format_tabs() {
+        let actual = format_text(
+            "def f():\n    pass\n",
+            FormattingOptions {
+                tab_size: 4,
+                insert_spaces: false,
+            },
+        )
+        .unwrap()
+        .text;
+        assert_eq!(actual, "def f():\n\tpass\n");
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        assert!(format_text("x = (", FormattingOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_shape() {
+        let shape_of = |text: &str| shape(&parse_without_errors(text).unwrap());
+        assert_eq!(
+            shape_of("load('a.bzl', 'b', c = 'd')\nx = [ 'y' ]\n"),
+            shape_of("load(\"a.bzl\", c = \"d\", \"b\")\nx = [\"y\"]\n"),
+        );
+        assert_ne!(shape_of("x = a\n"), shape_of("x = b\n"));
+        assert_ne!(shape_of("x = a - b\n"), shape_of("x = b - a\n"));
+        assert_ne!(shape_of("x = 1  # a\n"), shape_of("x = 1\n"));
+        assert_ne!(shape_of("# a\nx = 1\n# b\n"), shape_of("# b\nx = 1\n# a\n"));
+    }
+
+    #[test]
+    fn test_format_comments() {
+        check(
+            r#"
+x=1   # Trailing comment.
+# Standalone comment.
+def f():
+    # Comment in a block.
+    return x
+"#,
+            expect![[r#"
+                x = 1  # Trailing comment.
+                # Standalone comment.
+                def f():
+                    # Comment in a block.
+                    return x
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_format_range() {
+        let input = "x=1\ndef f():\n    y=[1,2]\n    z=3\n";
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            input,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let pos = TextSize::try_from(input.find("[1").unwrap()).unwrap();
+        let edits = snap
+            .format_range(file_id, TextRange::empty(pos), FormattingOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(&input[edits[0].range], "    y=[1,2]");
+        assert_eq!(edits[0].new_text, "    y = [1, 2]");
+    }
+}
@@ -198,6 +198,30 @@ pub(crate) fn goto_definition(
     None
 }
 
+/// Finds the declaration of the type of the expression at the given position, e.g. the
+/// `provider()` call for a provider instance. Builtin types have no declaration.
+pub(crate) fn goto_type_definition(
+    db: &Database,
+    FilePosition { file_id, pos }: FilePosition,
+) -> Option<Vec<LocationLink>> {
+    let sema = Semantics::new(db);
+    let file = db.get_file(file_id)?;
+    let parse = parse_query(db, file);
+    let token = pick_best_token(parse.syntax(db).token_at_offset(pos), |kind| match kind {
+        T![ident] => 2,
+        kind if kind.is_trivia_token() => 0,
+        _ => 1,
+    })?;
+    let expr = token.parent_ancestors().find_map(ast::Expression::cast)?;
+    let def = sema.type_of_expr(file, &expr)?.definition(db)?;
+    Some(vec![LocationLink::Local {
+        origin_selection_range: None,
+        target_range: def.range,
+        target_selection_range: def.range,
+        target_file_id: def.file_id,
+    }])
+}
+
 #[cfg(test)]
 mod tests {
     use starpls_bazel::APIContext;
@@ -229,6 +253,29 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    fn check_goto_type_definition(fixture: &str) {
+        let (contents, pos, expected) = parse_fixture(fixture);
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            &contents,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let actual = snap
+            .goto_type_definition(FilePosition { file_id, pos })
+            .unwrap()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|loc| match loc {
+                LocationLink::Local { target_range, .. } => target_range,
+                _ => panic!("expected local location"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn test_simple() {
         check_goto_definition(
@@ -287,6 +334,49 @@ GoInfo = provider(
 )
 info = GoInfo(foo = 123)
 info.fo$0o
+"#,
+        )
+    }
+
+    #[test]
+    fn test_unresolved_load_item() {
+        check_goto_definition(
+            r#"
+load("//:foo.bzl", "foo")
+f$0oo
+"#,
+        )
+    }
+
+    #[test]
+    fn test_type_definition_provider_instance() {
+        check_goto_type_definition(
+            r#"
+FooInfo = provider(fields = ["a"])
+          #^^^^^^^^^^^^^^^^^^^^^^^
+x = FooInfo(a = 1)
+x$0
+"#,
+        )
+    }
+
+    #[test]
+    fn test_type_definition_struct() {
+        check_goto_type_definition(
+            r#"
+s = struct(foo = "bar")
+    #^^^^^^^^^^^^^^^^^^
+s$0.foo
+"#,
+        )
+    }
+
+    #[test]
+    fn test_type_definition_builtin() {
+        check_goto_type_definition(
+            r#"
+x = 1
+x$0
 "#,
         )
     }
@@ -1,11 +1,11 @@
 use std::fmt::Write;
 
-use starpls_common::{parse, Db as _};
+use starpls_common::{parse, Db as _, File};
 use starpls_hir::{DisplayWithDb, Semantics, Type};
 use starpls_syntax::{
     ast::{self, AstNode},
     SyntaxKind::*,
-    TextRange, T,
+    SyntaxToken, TextRange, T,
 };
 
 use crate::{
@@ -44,6 +44,20 @@ pub(crate) fn hover(db: &Database, FilePosition { file_id, pos }: FilePosition)
         _ => 1,
     })?;
 
+    // Report the range of the hovered token so that clients can highlight it.
+    let range = token.text_range();
+    hover_for_token(db, &sema, file, token).map(|hover| Hover {
+        range: Some(range),
+        ..hover
+    })
+}
+
+fn hover_for_token(
+    db: &Database,
+    sema: &Semantics,
+    file: File,
+    token: SyntaxToken,
+) -> Option<Hover> {
     // Check for keyword hovers first.
     if token.kind().is_keyword() {
         let text = match token.kind() {
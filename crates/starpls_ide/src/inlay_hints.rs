@@ -0,0 +1,144 @@
+use starpls_common::{parse, Db as _, File, FileId};
+use starpls_hir::{DisplayWithDb, Semantics};
+use starpls_syntax::{
+    ast::{self, AssignOp, AstNode},
+    TextRange, TextSize,
+};
+
+use crate::Database;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InlayHintKind {
+    Type,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlayHint {
+    pub position: TextSize,
+    pub label: String,
+    pub kind: InlayHintKind,
+}
+
+pub(crate) fn inlay_hints(
+    db: &Database,
+    file_id: FileId,
+    range: Option<TextRange>,
+) -> Option<Vec<InlayHint>> {
+    let sema = Semantics::new(db);
+    let file = db.get_file(file_id)?;
+    let root = parse(db, file).syntax(db);
+    let mut hints = Vec::new();
+    for stmt in root.descendants().filter_map(ast::AssignStmt::cast) {
+        // Only compute hints for the requested range, e.g. the lines visible in the editor.
+        if range.map_or(false, |range| {
+            range.intersect(stmt.syntax().text_range()).is_none()
+        }) {
+            continue;
+        }
+
+        // Skip assignments whose types are already written out in a type comment, as well as
+        // augmented assignments, whose targets must have been declared previously.
+        if stmt.type_comment().is_some()
+            || !matches!(stmt.assign_op_info(), Some((_, AssignOp::Normal)))
+        {
+            continue;
+        }
+
+        if let Some(lhs) = stmt.lhs() {
+            add_hints_for_target(db, &sema, file, lhs, &mut hints);
+        }
+    }
+
+    Some(hints)
+}
+
+fn add_hints_for_target(
+    db: &Database,
+    sema: &Semantics,
+    file: File,
+    target: ast::Expression,
+    acc: &mut Vec<InlayHint>,
+) {
+    match target {
+        ast::Expression::Name(ref name_ref) => {
+            let ty = match sema.type_of_expr(file, &target) {
+                Some(ty) if !ty.is_unknown() && !ty.is_unbound() => ty,
+                _ => return,
+            };
+            acc.push(InlayHint {
+                position: name_ref.syntax().text_range().end(),
+                label: format!(": {}", ty.display(db)),
+                kind: InlayHintKind::Type,
+            });
+        }
+        ast::Expression::List(list) => list
+            .elements()
+            .for_each(|element| add_hints_for_target(db, sema, file, element, acc)),
+        ast::Expression::Tuple(tuple) => tuple
+            .elements()
+            .for_each(|element| add_hints_for_target(db, sema, file, element, acc)),
+        ast::Expression::Paren(paren) => {
+            if let Some(expr) = paren.expr() {
+                add_hints_for_target(db, sema, file, expr, acc);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+
+    use crate::AnalysisSnapshot;
+
+    fn check(input: &str, expect: Expect) {
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            input,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let hints = snap.inlay_hints(file_id, None).unwrap().unwrap();
+        let mut actual = String::new();
+        for hint in hints {
+            actual.push_str(&format!("{:?} {}", hint.position, hint.label));
+            actual.push('\n');
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_assignments() {
+        check(
+            r#"
+x = [1, 2]
+a, (b, c) = 1, ("abc", None)
+y = 1 # type: int
+y += 1
+"#,
+            expect![[r#"
+                2 : list[int]
+                13 : Literal[1]
+                17 : Literal["abc"]
+                20 : None
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_skip_unknown() {
+        check(
+            r#"
+def f(x):
+    y = x
+    return y
+"#,
+            expect![""],
+        );
+    }
+}
@@ -13,24 +13,45 @@ use starpls_syntax::{LineIndex, TextRange, TextSize};
 use starpls_test_util::make_test_builtins;
 
 pub use crate::{
+    call_hierarchy::{CallHierarchyItem, IncomingCall, OutgoingCall},
+    code_actions::{CodeAction, CodeActionKind},
+    code_lens::CodeLens,
     completions::{
         CompletionItem, CompletionItemKind, CompletionMode, Edit, InsertReplaceEdit, TextEdit,
     },
     document_symbols::{DocumentSymbol, SymbolKind, SymbolTag},
+    folding_ranges::{FoldKind, FoldingRange},
+    formatting::FormattingOptions,
     hover::{Hover, Markup},
+    inlay_hints::{InlayHint, InlayHintKind},
+    semantic_tokens::{SemanticToken, SemanticTokenKind, SemanticTokenModifier},
     signature_help::{ParameterInfo, SignatureHelp, SignatureInfo},
+    workspace_symbols::WorkspaceSymbol,
 };
 
+#[cfg(test)]
+mod benchmarks;
+mod call_hierarchy;
+mod code_actions;
+mod code_lens;
 mod completions;
 mod diagnostics;
 mod document_symbols;
+mod find_references;
+mod folding_ranges;
+mod formatting;
 mod goto_definition;
 mod hover;
+mod inlay_hints;
 mod line_index;
+mod rename;
+mod selection_ranges;
+mod semantic_tokens;
 mod show_hir;
 mod show_syntax_tree;
 mod signature_help;
 mod util;
+mod workspace_symbols;
 
 pub type Cancellable<T> = Result<T, Cancelled>;
 
@@ -47,7 +68,23 @@ pub(crate) struct Database {
 impl Database {
     fn apply_file_changes(&mut self, changes: Vec<(FileId, FileChange)>) {
         let gcx = self.gcx.clone();
-        let _guard = gcx.cancel();
+
+        // When only existing files are updated, inference results are kept for the files that
+        // aren't affected by the change. New files can affect how `load` statements are resolved,
+        // and all files implicitly depend on the prelude, so these cases discard all results.
+        let updated_files = changes
+            .iter()
+            .map(|(file_id, change)| match change {
+                FileChange::Update { .. } if self.prelude_file != Some(*file_id) => {
+                    self.get_file(*file_id)
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let _guard = match updated_files {
+            Some(files) => gcx.cancel_for_files(files),
+            None => gcx.cancel(),
+        };
         for (file_id, change) in changes {
             match change {
                 FileChange::Create {
@@ -324,6 +361,14 @@ impl AnalysisSnapshot {
         (analysis.snapshot(), file_id)
     }
 
+    pub fn code_actions(
+        &self,
+        file_id: FileId,
+        range: TextRange,
+    ) -> Cancellable<Option<Vec<CodeAction>>> {
+        self.query(|db| code_actions::code_actions(db, file_id, range))
+    }
+
     pub fn completion(
         &self,
         pos: FilePosition,
@@ -336,10 +381,47 @@ impl AnalysisSnapshot {
         self.query(|db| diagnostics::diagnostics(db, file_id))
     }
 
+    /// Computes the diagnostics for several files at once. See `diagnostics` for a single file.
+    pub fn diagnostics_for_files(
+        &self,
+        file_ids: &[FileId],
+    ) -> Cancellable<Vec<(FileId, Vec<Diagnostic>)>> {
+        self.query(|db| diagnostics::diagnostics_for_files(db, file_ids))
+    }
+
     pub fn document_symbols(&self, file_id: FileId) -> Cancellable<Option<Vec<DocumentSymbol>>> {
         self.query(|db| document_symbols::document_symbols(db, file_id))
     }
 
+    pub fn find_references(
+        &self,
+        pos: FilePosition,
+        include_declaration: bool,
+    ) -> Cancellable<Option<Vec<LocationLink>>> {
+        self.query(|db| find_references::find_references(db, pos, include_declaration))
+    }
+
+    pub fn folding_ranges(&self, file_id: FileId) -> Cancellable<Option<Vec<FoldingRange>>> {
+        self.query(|db| folding_ranges::folding_ranges(db, file_id))
+    }
+
+    pub fn format(
+        &self,
+        file_id: FileId,
+        options: FormattingOptions,
+    ) -> Cancellable<Option<Vec<TextEdit>>> {
+        self.query(|db| formatting::format(db, file_id, options))
+    }
+
+    pub fn format_range(
+        &self,
+        file_id: FileId,
+        range: TextRange,
+        options: FormattingOptions,
+    ) -> Cancellable<Option<Vec<TextEdit>>> {
+        self.query(|db| formatting::format_range(db, file_id, range, options))
+    }
+
     pub fn goto_definition(&self, pos: FilePosition) -> Cancellable<Option<Vec<LocationLink>>> {
         self.query(|db| {
             let res = goto_definition::goto_definition(db, pos);
@@ -347,14 +429,75 @@ impl AnalysisSnapshot {
         })
     }
 
+    pub fn goto_type_definition(
+        &self,
+        pos: FilePosition,
+    ) -> Cancellable<Option<Vec<LocationLink>>> {
+        self.query(|db| goto_definition::goto_type_definition(db, pos))
+    }
+
     pub fn hover(&self, pos: FilePosition) -> Cancellable<Option<Hover>> {
         self.query(|db| hover::hover(db, pos))
     }
 
+    pub fn code_lenses(&self, file_id: FileId) -> Cancellable<Option<Vec<CodeLens>>> {
+        self.query(|db| code_lens::code_lenses(db, file_id))
+    }
+
+    pub fn inlay_hints(
+        &self,
+        file_id: FileId,
+        range: Option<TextRange>,
+    ) -> Cancellable<Option<Vec<InlayHint>>> {
+        self.query(|db| inlay_hints::inlay_hints(db, file_id, range))
+    }
+
+    pub fn incoming_calls(&self, pos: FilePosition) -> Cancellable<Option<Vec<IncomingCall>>> {
+        self.query(|db| call_hierarchy::incoming_calls(db, pos))
+    }
+
     pub fn line_index<'a>(&'a self, file_id: FileId) -> Cancellable<Option<&'a LineIndex>> {
         self.query(move |db| line_index::line_index(db, file_id))
     }
 
+    pub fn outgoing_calls(&self, pos: FilePosition) -> Cancellable<Option<Vec<OutgoingCall>>> {
+        self.query(|db| call_hierarchy::outgoing_calls(db, pos))
+    }
+
+    pub fn prepare_call_hierarchy(
+        &self,
+        pos: FilePosition,
+    ) -> Cancellable<Option<Vec<CallHierarchyItem>>> {
+        self.query(|db| call_hierarchy::prepare_call_hierarchy(db, pos))
+    }
+
+    pub fn prepare_rename(
+        &self,
+        pos: FilePosition,
+    ) -> Cancellable<anyhow::Result<Option<TextRange>>> {
+        self.query(|db| rename::prepare_rename(db, pos))
+    }
+
+    pub fn rename(
+        &self,
+        pos: FilePosition,
+        new_name: String,
+    ) -> Cancellable<anyhow::Result<Option<Vec<TextEdit>>>> {
+        self.query(|db| rename::rename(db, pos, &new_name))
+    }
+
+    pub fn selection_ranges(
+        &self,
+        file_id: FileId,
+        positions: Vec<TextSize>,
+    ) -> Cancellable<Option<Vec<Vec<TextRange>>>> {
+        self.query(|db| selection_ranges::selection_ranges(db, file_id, positions))
+    }
+
+    pub fn semantic_tokens(&self, file_id: FileId) -> Cancellable<Option<Vec<SemanticToken>>> {
+        self.query(|db| semantic_tokens::semantic_tokens(db, file_id))
+    }
+
     pub fn show_hir(&self, file_id: FileId) -> Cancellable<Option<String>> {
         self.query(|db| show_hir::show_hir(db, file_id))
     }
@@ -367,6 +510,10 @@ impl AnalysisSnapshot {
         self.query(|db| signature_help::signature_help(db, pos))
     }
 
+    pub fn workspace_symbols(&self, query: String) -> Cancellable<Vec<WorkspaceSymbol>> {
+        self.query(|db| workspace_symbols::workspace_symbols(db, &query))
+    }
+
     /// Helper method to handle Salsa cancellations.
     fn query<'a, F, T>(&'a self, f: F) -> Cancellable<T>
     where
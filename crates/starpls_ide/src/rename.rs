@@ -0,0 +1,313 @@
+use anyhow::anyhow;
+use starpls_common::{parse, Db as _};
+use starpls_hir::{Name, ScopeDef, Semantics};
+use starpls_syntax::{
+    ast::{self, AstNode},
+    SyntaxToken, TextRange, T,
+};
+
+use crate::{
+    find_references::find_references,
+    util::{is_valid_identifier, pick_best_token},
+    Database, FilePosition, LocationLink, TextEdit,
+};
+
+pub(crate) fn prepare_rename(
+    db: &Database,
+    pos: FilePosition,
+) -> anyhow::Result<Option<TextRange>> {
+    Ok(rename_target(db, pos)?.map(|token| token.text_range()))
+}
+
+pub(crate) fn rename(
+    db: &Database,
+    pos: FilePosition,
+    new_name: &str,
+) -> anyhow::Result<Option<Vec<TextEdit>>> {
+    if !is_valid_identifier(new_name) {
+        return Err(anyhow!("\"{}\" is not a valid identifier", new_name));
+    }
+
+    if rename_target(db, pos)?.is_none() {
+        return Ok(None);
+    }
+
+    // The declaration itself is included in the references, so one pass over the
+    // references covers every edit.
+    let locations = match find_references(db, pos, true) {
+        Some(locations) => locations,
+        None => return Ok(None),
+    };
+    Ok(Some(
+        locations
+            .into_iter()
+            .filter_map(|location| match location {
+                LocationLink::Local { target_range, .. } => Some(TextEdit {
+                    range: target_range,
+                    new_text: new_name.to_string(),
+                }),
+                _ => None,
+            })
+            .collect(),
+    ))
+}
+
+/// Finds the identifier under the cursor and checks that it refers to a symbol declared in
+/// the current file.
+fn rename_target(
+    db: &Database,
+    FilePosition { file_id, pos }: FilePosition,
+) -> anyhow::Result<Option<SyntaxToken>> {
+    let file = match db.get_file(file_id) {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let root = parse(db, file).syntax(db);
+    let token = match pick_best_token(root.token_at_offset(pos), |kind| match kind {
+        T![ident] => 2,
+        kind if kind.is_trivia_token() => 0,
+        _ => 1,
+    }) {
+        Some(token) if token.kind() == T![ident] => token,
+        _ => return Ok(None),
+    };
+    let parent = match token.parent() {
+        Some(parent) => parent,
+        None => return Ok(None),
+    };
+
+    // Names of functions and parameters are always declared in the current file. Other names,
+    // such as those of keyword arguments, don't refer to a declaration and can't be renamed.
+    if let Some(name) = ast::Name::cast(parent.clone()) {
+        let owner = match name.syntax().parent() {
+            Some(owner) => owner,
+            None => return Ok(None),
+        };
+        if ast::DefStmt::can_cast(owner.kind()) {
+            // Functions declared in `if` or `for` statements at the top level are also globals.
+            let is_global = !owner
+                .ancestors()
+                .skip(1)
+                .any(|node| ast::DefStmt::can_cast(node.kind()));
+            if is_global {
+                check_not_exported(&token)?;
+            }
+            return Ok(Some(token));
+        }
+        return Ok(ast::Parameter::can_cast(owner.kind()).then_some(token));
+    }
+
+    let name_ref = match ast::NameRef::cast(parent) {
+        Some(name_ref) => name_ref,
+        None => return Ok(None),
+    };
+    let sema = Semantics::new(db);
+    let scope = match ast::Expression::cast(name_ref.syntax().clone())
+        .and_then(|expr| sema.scope_for_expr(file, &expr))
+    {
+        Some(scope) => scope,
+        None => return Ok(None),
+    };
+    let name = Name::from_ast_node(name_ref);
+    let defs = scope.resolve_name(&name);
+
+    if defs.is_empty() {
+        return Ok(None);
+    }
+
+    for def in defs.iter() {
+        match def {
+            // Parameters can only be referenced from within their function.
+            ScopeDef::Parameter(_) => {}
+            ScopeDef::LoadItem(_) => {
+                return Err(anyhow!(
+                    "Cannot rename \"{}\" because it is loaded from another file",
+                    token.text()
+                ))
+            }
+            _ => match def.file(db) {
+                Some(def_file) if def_file == file => {}
+                Some(_) => {
+                    return Err(anyhow!(
+                        "Cannot rename \"{}\" because it is defined in another file",
+                        token.text()
+                    ))
+                }
+                None => return Err(anyhow!("Cannot rename builtin symbol \"{}\"", token.text())),
+            },
+        }
+    }
+
+    if scope.resolves_to_module_global(&name) {
+        check_not_exported(&token)?;
+    }
+
+    Ok(Some(token))
+}
+
+/// Only the current file is edited by a rename, so globals that other files can load are
+/// rejected instead of leaving those files with dangling references.
+fn check_not_exported(token: &SyntaxToken) -> anyhow::Result<()> {
+    if token.text().starts_with('_') {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Cannot rename \"{}\" because it is a global that can be loaded from other files",
+            token.text()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+    use starpls_syntax::TextRange;
+    use starpls_test_util::parse_fixture;
+
+    use crate::{AnalysisSnapshot, FilePosition};
+
+    fn snapshot(fixture: &str) -> (AnalysisSnapshot, FilePosition, Vec<TextRange>) {
+        let (contents, pos, expected) = parse_fixture(fixture);
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            &contents,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        (snap, FilePosition { file_id, pos }, expected)
+    }
+
+    fn check_rename(fixture: &str) {
+        let (snap, pos, expected) = snapshot(fixture);
+        let actual = snap
+            .rename(pos, "bar".to_string())
+            .unwrap()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|edit| {
+                assert_eq!(edit.new_text, "bar");
+                edit.range
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+    }
+
+    fn check_rename_error(fixture: &str) {
+        let (snap, pos, _) = snapshot(fixture);
+        assert!(snap.rename(pos, "bar".to_string()).unwrap().is_err());
+        assert!(snap.prepare_rename(pos).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_variable() {
+        check_rename(
+            r#"
+_foo = 1
+#^^^
+def f():
+    return _foo
+           #^^^
+_f$0oo + 1
+#^^^
+"#,
+        )
+    }
+
+    #[test]
+    fn test_param_skips_keyword_arguments() {
+        check_rename(
+            r#"
+def f(fo$0o):
+      #^^
+    return foo
+           #^^
+
+f(foo = 1)
+"#,
+        )
+    }
+
+    #[test]
+    fn test_prepare_rename() {
+        let (snap, pos, expected) = snapshot(
+            r#"
+def _foo():
+    pass
+
+_fo$0o()
+#^^^
+"#,
+        );
+        assert_eq!(
+            snap.prepare_rename(pos).unwrap().unwrap(),
+            expected.first().cloned()
+        );
+    }
+
+    #[test]
+    fn test_builtin() {
+        check_rename_error(
+            r#"
+str$0uct(a = 1)
+"#,
+        )
+    }
+
+    #[test]
+    fn test_keyword_new_name() {
+        let (snap, pos, _) = snapshot(
+            r#"
+_fo$0o = 1
+"#,
+        );
+        for new_name in ["def", "None", "while"] {
+            assert!(snap.rename(pos, new_name.to_string()).unwrap().is_err());
+        }
+        assert!(snap.rename(pos, "define".to_string()).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_exported_global() {
+        check_rename_error(
+            r#"
+foo = 1
+fo$0o + 1
+"#,
+        );
+        check_rename_error(
+            r#"
+def fo$0o():
+    pass
+"#,
+        )
+    }
+
+    #[test]
+    fn test_local_shadowing_exported_global() {
+        check_rename(
+            r#"
+foo = 1
+
+def f():
+    foo = 2
+    #^^
+    return fo$0o
+           #^^
+"#,
+        )
+    }
+
+    #[test]
+    fn test_load_item() {
+        check_rename_error(
+            r#"
+load("//:foo.bzl", "foo")
+fo$0o()
+"#,
+        )
+    }
+}
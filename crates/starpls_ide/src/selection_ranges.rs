@@ -0,0 +1,86 @@
+use starpls_common::{parse, Db as _, FileId};
+use starpls_syntax::{TextRange, TextSize};
+
+use crate::{util::pick_best_token, Database};
+
+/// Computes the ranges of the syntax nodes enclosing each of the given positions, ordered from
+/// innermost to outermost. The innermost range is always that of the token at the position,
+/// e.g. a whole string literal.
+pub(crate) fn selection_ranges(
+    db: &Database,
+    file_id: FileId,
+    positions: Vec<TextSize>,
+) -> Option<Vec<Vec<TextRange>>> {
+    let file = db.get_file(file_id)?;
+    let root = parse(db, file).syntax(db);
+    Some(
+        positions
+            .into_iter()
+            .map(|pos| {
+                let token = match pick_best_token(root.token_at_offset(pos), |kind| {
+                    if kind.is_trivia_token() {
+                        0
+                    } else {
+                        1
+                    }
+                }) {
+                    Some(token) => token,
+                    None => return vec![TextRange::empty(pos)],
+                };
+
+                let mut ranges = vec![token.text_range()];
+                for node in token.parent_ancestors() {
+                    let range = node.text_range();
+                    if ranges.last() != Some(&range) {
+                        ranges.push(range);
+                    }
+                }
+                ranges
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+    use starpls_test_util::parse_fixture;
+
+    use crate::AnalysisSnapshot;
+
+    fn check(fixture: &str, expect: Expect) {
+        let (contents, pos, _) = parse_fixture(fixture);
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            &contents,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let ranges = snap.selection_ranges(file_id, vec![pos]).unwrap().unwrap();
+        let mut actual = String::new();
+        for range in ranges[0].iter() {
+            actual.push_str(&format!("{:?}\n", &contents[*range]));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_selection_ranges() {
+        check(
+            r#"def f(x):
+    return x + "a b$0 c"
+"#,
+            expect![[r#"
+                "\"a b c\""
+                "x + \"a b c\""
+                "return x + \"a b c\""
+                "\n    return x + \"a b c\"\n"
+                "def f(x):\n    return x + \"a b c\"\n"
+            "#]],
+        );
+    }
+}
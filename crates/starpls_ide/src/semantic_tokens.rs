@@ -0,0 +1,179 @@
+use starpls_common::{parse, Db as _, File, FileId};
+use starpls_hir::{Name, ScopeDef, Semantics};
+use starpls_syntax::{
+    ast::{self, AstNode},
+    SyntaxNode, TextRange,
+};
+
+use crate::{util::is_constant_name, Database};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SemanticTokenKind {
+    Function,
+    Parameter,
+    Variable,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SemanticTokenModifier {
+    /// The symbol is a builtin, e.g. `len` or `struct`.
+    DefaultLibrary,
+    /// The symbol is a constant, i.e. an all-caps module global.
+    Readonly,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub range: TextRange,
+    pub kind: SemanticTokenKind,
+    pub modifiers: Vec<SemanticTokenModifier>,
+}
+
+pub(crate) fn semantic_tokens(db: &Database, file_id: FileId) -> Option<Vec<SemanticToken>> {
+    let sema = Semantics::new(db);
+    let file = db.get_file(file_id)?;
+    let root = parse(db, file).syntax(db);
+    let mut tokens = Vec::new();
+    for node in root.descendants() {
+        if let Some(name_ref) = ast::NameRef::cast(node.clone()) {
+            if let Some(token) = classify_name_ref(db, &sema, file, &root, name_ref) {
+                tokens.push(token);
+            }
+        } else if let Some(name) = ast::Name::cast(node) {
+            // Names only declare functions and parameters; other names, e.g. those of keyword
+            // arguments and fields, aren't highlighted.
+            let kind = match name.syntax().parent() {
+                Some(owner) if ast::DefStmt::can_cast(owner.kind()) => SemanticTokenKind::Function,
+                Some(owner) if ast::Parameter::can_cast(owner.kind()) => {
+                    SemanticTokenKind::Parameter
+                }
+                _ => continue,
+            };
+            tokens.push(SemanticToken {
+                range: name.syntax().text_range(),
+                kind,
+                modifiers: Vec::new(),
+            });
+        }
+    }
+
+    Some(tokens)
+}
+
+fn classify_name_ref(
+    db: &Database,
+    sema: &Semantics,
+    file: File,
+    root: &SyntaxNode,
+    name_ref: ast::NameRef,
+) -> Option<SemanticToken> {
+    let range = name_ref.syntax().text_range();
+    let scope = sema.scope_for_expr(file, &ast::Expression::cast(name_ref.syntax().clone())?)?;
+    let name = Name::from_ast_node(name_ref);
+    let def = scope.resolve_name(&name).into_iter().next()?;
+    let mut modifiers = Vec::new();
+    let kind = match def {
+        ScopeDef::Parameter(_) => SemanticTokenKind::Parameter,
+        ScopeDef::Callable(ref callable) => {
+            if !callable.is_user_defined() {
+                modifiers.push(SemanticTokenModifier::DefaultLibrary);
+            }
+            SemanticTokenKind::Function
+        }
+        ScopeDef::Variable(_) | ScopeDef::LoadItem(_) => {
+            if !def.is_user_defined() {
+                modifiers.push(SemanticTokenModifier::DefaultLibrary);
+            } else if is_constant_name(name.as_str()) && is_module_global(db, file, root, &def) {
+                modifiers.push(SemanticTokenModifier::Readonly);
+            }
+            if def.ty(db).is_function() {
+                SemanticTokenKind::Function
+            } else {
+                SemanticTokenKind::Variable
+            }
+        }
+    };
+
+    Some(SemanticToken {
+        range,
+        kind,
+        modifiers,
+    })
+}
+
+fn is_module_global(db: &Database, file: File, root: &SyntaxNode, def: &ScopeDef) -> bool {
+    // Definitions from other files, e.g. the prelude or a `load` statement, are always globals.
+    if def.file(db) != Some(file) || matches!(def, ScopeDef::LoadItem(_)) {
+        return true;
+    }
+    def.syntax_node_ptr(db, file)
+        .and_then(|ptr| ptr.try_to_node(root))
+        .map_or(false, |node| {
+            !node
+                .ancestors()
+                .any(|node| ast::DefStmt::can_cast(node.kind()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+
+    use crate::AnalysisSnapshot;
+
+    fn check(input: &str, expect: Expect) {
+        let (snap, file_id) = AnalysisSnapshot::from_single_file(
+            input,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let tokens = snap.semantic_tokens(file_id).unwrap().unwrap();
+        let mut actual = String::new();
+        for token in tokens {
+            actual.push_str(&format!(
+                "{:?} {:?}: {:?} {:?}\n",
+                token.range, &input[token.range], token.kind, token.modifiers
+            ));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        check(
+            r#"FOO = 1
+bar = 2
+
+def f(x):
+    LOCAL = len(x)
+    return LOCAL + FOO + bar + f(x)
+
+s = struct(a = 1)
+g = f
+"#,
+            expect![[r#"
+                0..3 "FOO": Variable [Readonly]
+                8..11 "bar": Variable []
+                21..22 "f": Function []
+                23..24 "x": Parameter []
+                31..36 "LOCAL": Variable []
+                39..42 "len": Function [DefaultLibrary]
+                43..44 "x": Parameter []
+                57..62 "LOCAL": Variable []
+                65..68 "FOO": Variable [Readonly]
+                71..74 "bar": Variable []
+                77..78 "f": Function []
+                79..80 "x": Parameter []
+                83..84 "s": Variable []
+                87..93 "struct": Function [DefaultLibrary]
+                101..102 "g": Function []
+                105..106 "f": Function []
+            "#]],
+        );
+    }
+}
@@ -1,7 +1,5 @@
-use std::fmt::Write;
-
 use starpls_common::{parse, Db as _};
-use starpls_hir::{DisplayWithDb, Semantics};
+use starpls_hir::Semantics;
 use starpls_syntax::{
     ast::{self, AstNode, Direction},
     T,
@@ -54,69 +52,13 @@ pub(crate) fn signature_help(
     let params = func.params(db);
     let param_labels: Vec<String> = params
         .iter()
-        .map(|(param, ty)| {
-            let mut s = String::new();
-            if param.is_args_list(db) {
-                s.push('*');
-            } else if param.is_kwargs_dict(db) {
-                s.push_str("**");
-            }
-
-            match param.name(db) {
-                Some(name) if !name.is_missing() && !name.as_str().is_empty() => {
-                    s.push_str(name.as_str());
-
-                    let ty = if param.is_args_list(db) {
-                        ty.variable_tuple_element_ty()
-                    } else if param.is_kwargs_dict(db) {
-                        ty.dict_value_ty()
-                    } else {
-                        ty.clone().into()
-                    };
-
-                    match ty {
-                        Some(ty) if !ty.is_unknown() => {
-                            let _ = write!(&mut s, ": {}", ty.display(db));
-                        }
-                        _ => {}
-                    }
-
-                    match param.default_value(db) {
-                        Some(default_value) if !default_value.is_empty() => {
-                            s.push_str(" = ");
-                            s.push_str(&default_value);
-                        }
-                        _ => {}
-                    }
-                }
-                _ => {}
-            }
-
-            s
-        })
+        .map(|(param, ty)| param.label(db, ty))
         .collect();
-
-    // Construct the labels for the function signature.
-    // TODO(withered-magic): Some of this logic is duplicated from the `DisplayWithDb` implementation on `TyKind`.
-    let mut label = String::new();
-    label.push_str("def ");
-    label.push_str(func.name(db).as_str());
-    label.push('(');
-
-    let is_rule_or_tag = func.is_rule() || func.is_tag();
-    if is_rule_or_tag {
-        label.push_str("*");
-    }
-
-    for (index, param_label) in param_labels.iter().enumerate() {
-        if index > 0 || is_rule_or_tag {
-            label.push_str(", ");
-        }
-        label.push_str(&param_label);
-    }
-
-    label.push_str(") -> ");
-    let _ = write!(&mut label, "{}", func.ret_ty(db).display(db));
+    let label = format!(
+        "def {}{}",
+        func.name(db).as_str(),
+        func.ty(db).signature_string(db)?
+    );
 
     // Check if token's direct parent is an `Arguments` node. If so, that means we are at a ',', '(', or ')'.
     // The active parameter index is equal to the number of commas that we see to the left (including ourselves).
@@ -147,7 +89,10 @@ pub(crate) fn signature_help(
     Some(SignatureHelp {
         signatures: vec![SignatureInfo {
             label,
-            documentation: func.doc(db).map(|doc| unindent_doc(&doc)),
+            documentation: func
+                .doc(db)
+                .filter(|doc| !doc.is_empty())
+                .map(|doc| unindent_doc(&doc)),
             parameters: Some(
                 params
                     .into_iter()
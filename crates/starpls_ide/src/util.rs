@@ -1,4 +1,4 @@
-use starpls_syntax::{SyntaxKind, SyntaxToken, TokenAtOffset};
+use starpls_syntax::{parse_module, SyntaxKind, SyntaxToken, TokenAtOffset};
 
 pub(crate) fn pick_best_token(
     tokens: TokenAtOffset<SyntaxToken>,
@@ -7,6 +7,28 @@ pub(crate) fn pick_best_token(
     tokens.max_by_key(|token| f(token.kind()))
 }
 
+/// Returns whether the given name follows the convention for constants, e.g. `DEFAULT_VISIBILITY`.
+pub(crate) fn is_constant_name(name: &str) -> bool {
+    name.chars().any(|c| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Returns whether the given name can be used as an identifier. Keywords, including the ones
+/// reserved for future use like `while`, are lexed as their own tokens and are rejected.
+pub(crate) fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && parse_module(name, &mut |_| {})
+            .syntax()
+            .first_token()
+            .map_or(false, |token| token.kind() == SyntaxKind::IDENT)
+}
+
 // TODO(withered-magic): This logic should probably be more sophisticated, but it works well
 // enough for now.
 pub(crate) fn unindent_doc(doc: &str) -> String {
@@ -0,0 +1,134 @@
+use starpls_common::FileId;
+use starpls_syntax::TextRange;
+
+use crate::{document_symbols::document_symbols, Database, SymbolKind};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub file_id: FileId,
+    pub range: TextRange,
+}
+
+/// Searches the top-level symbols of all files for the ones whose names fuzzy-match the given
+/// query. Exact matches are ranked first, followed by prefix matches, substring matches, and
+/// finally names containing the query's characters in order.
+pub(crate) fn workspace_symbols(db: &Database, query: &str) -> Vec<WorkspaceSymbol> {
+    let query = query.to_lowercase();
+    let mut file_ids = db
+        .files
+        .iter()
+        .map(|entry| *entry.key())
+        .collect::<Vec<_>>();
+    file_ids.sort();
+
+    let mut symbols = Vec::new();
+    for file_id in file_ids {
+        // Computing each file's symbols goes through Salsa queries, which unwind if the search is
+        // cancelled by a pending change.
+        for symbol in document_symbols(db, file_id).into_iter().flatten() {
+            if let Some(rank) = match_rank(&query, &symbol.name) {
+                symbols.push((
+                    rank,
+                    WorkspaceSymbol {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        file_id,
+                        range: symbol.selection_range,
+                    },
+                ));
+            }
+        }
+    }
+
+    // The sort is stable, so symbols with the same rank and name stay ordered by file.
+    symbols.sort_by(|(rank1, symbol1), (rank2, symbol2)| {
+        rank1
+            .cmp(rank2)
+            .then_with(|| symbol1.name.cmp(&symbol2.name))
+    });
+    symbols.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+fn match_rank(query: &str, name: &str) -> Option<u8> {
+    let name = name.to_lowercase();
+    if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if name.contains(query) {
+        Some(2)
+    } else {
+        let mut chars = name.chars();
+        query
+            .chars()
+            .all(|query_char| chars.any(|char| char == query_char))
+            .then_some(3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use starpls_bazel::APIContext;
+    use starpls_common::{Dialect, FileInfo};
+
+    use crate::AnalysisSnapshot;
+
+    fn check(input: &str, query: &str, expect: Expect) {
+        let (snap, _) = AnalysisSnapshot::from_single_file(
+            input,
+            Dialect::Bazel,
+            Some(FileInfo::Bazel {
+                api_context: APIContext::Bzl,
+                is_external: false,
+            }),
+        );
+        let mut actual = String::new();
+        for symbol in snap.workspace_symbols(query.to_string()).unwrap() {
+            actual.push_str(&format!(
+                "{:?} {:?}: {:?}\n",
+                symbol.range, &input[symbol.range], symbol.kind
+            ));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn test_workspace_symbols() {
+        check(
+            r#"def foo_bar():
+    pass
+
+def foo():
+    pass
+
+FOO_VALUE = 1
+bar = 2
+"#,
+            "foo",
+            expect![[r#"
+                29..32 "foo": Function
+                46..55 "FOO_VALUE": Constant
+                4..11 "foo_bar": Function
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_workspace_symbols_fuzzy() {
+        check(
+            r#"def make_rule_impl():
+    pass
+
+def other():
+    pass
+"#,
+            "mri",
+            expect![[r#"
+                4..18 "make_rule_impl": Function
+            "#]],
+        );
+    }
+}
@@ -92,16 +92,19 @@ fn comparison_expr(p: &mut Parser) -> Option<CompletedMarker> {
     };
 
     loop {
-        let is_not_in = if COMP_TOKENS.contains(&p.current()) {
+        // `not in` and `is not` are the only operators that span two tokens.
+        let is_two_tokens = if COMP_TOKENS.contains(&p.current()) {
             false
         } else if p.at(T![not]) && p.nth_at(1, T![in]) {
             true
+        } else if p.at(T![is]) {
+            p.nth_at(1, T![not])
         } else {
             break;
         };
         let binary_marker = m.precede(p);
         p.bump_any();
-        if is_not_in {
+        if is_two_tokens {
             p.bump_any();
         }
         bitwise_or_expr(p);
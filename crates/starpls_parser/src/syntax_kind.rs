@@ -208,6 +208,7 @@ macro_rules! T {
     [<=] => { $ crate :: SyntaxKind :: LE };
     [>=] => { $ crate :: SyntaxKind :: GE };
     [in] => { $ crate :: SyntaxKind :: IN };
+    [is] => { $ crate :: SyntaxKind :: IS };
     [|] => { $ crate :: SyntaxKind :: BAR };
     [^] => { $ crate :: SyntaxKind :: CARET };
     [&] => { $ crate :: SyntaxKind :: AMPERSAND };
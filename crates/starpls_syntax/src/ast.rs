@@ -605,6 +605,17 @@ impl BinaryExpr {
                     T![or] => BinaryOp::Logic(LogicOp::Or),
                     T![in] => BinaryOp::MemberOp(MemberOp::In),
                     T![not] => BinaryOp::MemberOp(MemberOp::NotIn),
+                    T![is] => BinaryOp::IdentityOp(
+                        if self
+                            .syntax
+                            .children_with_tokens()
+                            .any(|el| el.kind() == T![not])
+                        {
+                            IdentityOp::IsNot
+                        } else {
+                            IdentityOp::Is
+                        },
+                    ),
                     _ => return None,
                 };
                 Some((token, op))
@@ -993,6 +1004,7 @@ pub enum BinaryOp {
     Cmp(CmpOp),
     Logic(LogicOp),
     MemberOp(MemberOp),
+    IdentityOp(IdentityOp),
 }
 
 impl std::fmt::Display for BinaryOp {
@@ -1003,6 +1015,7 @@ impl std::fmt::Display for BinaryOp {
             BinaryOp::Cmp(op) => std::fmt::Display::fmt(op, f),
             BinaryOp::Logic(op) => std::fmt::Display::fmt(op, f),
             BinaryOp::MemberOp(op) => std::fmt::Display::fmt(op, f),
+            BinaryOp::IdentityOp(op) => std::fmt::Display::fmt(op, f),
         }
     }
 }
@@ -1104,6 +1117,21 @@ impl std::fmt::Display for MemberOp {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdentityOp {
+    Is,
+    IsNot,
+}
+
+impl std::fmt::Display for IdentityOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IdentityOp::Is => "is",
+            IdentityOp::IsNot => "is not",
+        })
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AssignOp {
     Normal,